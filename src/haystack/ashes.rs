@@ -1,11 +1,84 @@
-use crate::needle::{variant::NeedleVariant, Needle};
+use std::{net::SocketAddr, ops::Range};
+
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::needle::{
+    macaddr::oui_lookup::{oui_db, Vendor},
+    string::variant::{LengthPrefix, StringEncoding},
+    variant::NeedleVariant,
+    Needle,
+};
+
+use super::hay::Layer;
+
+/// Where a set of Ashes came from when burning a packet capture
+#[derive(Clone, Debug, PartialEq)]
+pub struct PacketInfo {
+    pub index: usize,
+    pub timestamp: OffsetDateTime,
+}
+
+impl PacketInfo {
+    pub fn new(index: usize, timestamp: OffsetDateTime) -> Self {
+        Self { index, timestamp }
+    }
+}
+
+/// A run of bytes in a reassembled stream, and where in a packet's payload they came from
+#[derive(Clone, Debug, PartialEq)]
+pub struct PacketSpan {
+    pub packet: PacketInfo,
+    pub offset: usize,
+    pub stream_offset: usize,
+    pub len: usize,
+}
+
+impl PacketSpan {
+    pub fn new(packet: PacketInfo, offset: usize, stream_offset: usize, len: usize) -> Self {
+        Self {
+            packet,
+            offset,
+            stream_offset,
+            len,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    ClientToServer,
+    ServerToClient,
+}
+
+/// Which side of a TCP connection a set of Ashes were found in
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlowInfo {
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    pub direction: Direction,
+}
+
+impl FlowInfo {
+    pub fn new(source: SocketAddr, destination: SocketAddr, direction: Direction) -> Self {
+        Self {
+            source,
+            destination,
+            direction,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Ashes<'a> {
     pub target: &'a Needle,
     pub actual: Needle,
     pub variant: NeedleVariant,
-    pub offset: usize,
+    pub offset: u64, // Relative to the start of the layer that was burned
+    pub layer: Option<Layer>,
+    pub frame_offset: Option<u64>,
+    pub packet: Option<PacketInfo>,
+    pub spans: Vec<PacketSpan>,
+    pub flow: Option<FlowInfo>,
 }
 
 impl<'a> Ashes<'a> {
@@ -14,7 +87,75 @@ impl<'a> Ashes<'a> {
             target,
             actual,
             variant,
-            offset,
+            offset: offset as u64,
+            layer: None,
+            frame_offset: None,
+            packet: None,
+            spans: Default::default(),
+            flow: None,
+        }
+    }
+
+    pub fn with_packet(
+        target: &'a Needle,
+        actual: Needle,
+        variant: NeedleVariant,
+        offset: usize,
+        packet: PacketInfo,
+    ) -> Self {
+        Self {
+            target,
+            actual,
+            variant,
+            offset: offset as u64,
+            layer: None,
+            frame_offset: None,
+            packet: Some(packet),
+            spans: Default::default(),
+            flow: None,
+        }
+    }
+
+    /// The bytes the needle was found as, relative to the start of the layer that was burned
+    pub fn span(&self) -> Range<u64> {
+        self.offset..self.offset + self.variant.byte_len() as u64
+    }
+
+    /// Who the MAC address that was found was assigned to, including one found as an IPv6 address's interface ID or a UUID's node
+    pub fn vendor(&self) -> Option<Vendor> {
+        let macaddr = match &self.actual {
+            Needle::MacAddr(macaddr) => macaddr.value?,
+            Needle::Ipv6Addr(ipv6) => ipv6.eui64_macaddr()?,
+            Needle::Uuid(uuid) => uuid.node()?.value?,
+            _ => return None,
+        };
+
+        oui_db().lookup(macaddr)
+    }
+
+    /// The encoding the string that was found was written out in
+    pub fn string_encoding(&self) -> Option<StringEncoding> {
+        match &self.variant {
+            NeedleVariant::String(string) => Some(string.encoding()),
+            _ => None,
+        }
+    }
+
+    /// How the length of the string that was found was given before it, if it was
+    pub fn length_prefix(&self) -> Option<LengthPrefix> {
+        match &self.variant {
+            NeedleVariant::String(string) => string.prefix(),
+            _ => None,
+        }
+    }
+
+    /// The UTC offset the timestamp that was found was stored in, out of those the needle allowed for
+    pub fn utc_offset(&self) -> Option<UtcOffset> {
+        match (self.target, &self.actual) {
+            (Needle::Timestamp(target), Needle::Timestamp(actual)) => {
+                target.matching_offset(actual)
+            }
+            _ => None,
         }
     }
 }
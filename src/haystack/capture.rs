@@ -0,0 +1,752 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use etherparse::{NetSlice, SlicedPacket};
+use time::OffsetDateTime;
+
+use crate::needle::Needle;
+
+use super::{
+    ashes::{Ashes, Direction, FlowInfo, PacketInfo},
+    defrag::defragment,
+    engine::{Engine, Registry},
+    hay::{burn_layer, Layer},
+    tcp::reassemble,
+};
+
+/*
+
+    Capture files are parsed here rather than through the pcap crate, so that reading them
+    doesn't depend on libpcap/Npcap being installed. Only the parts of each format needed to get at
+    the frames and their timestamps are handled:
+
+    pcap   - https://www.ietf.org/archive/id/draft-ietf-opsawg-pcap-03.html
+    pcapng - https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-01.html
+
+*/
+
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b23c4d;
+
+const PCAPNG_SECTION_HEADER: u32 = 0x0a0d0d0a;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b3c4d;
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 0x00000001;
+const PCAPNG_SIMPLE_PACKET: u32 = 0x00000003;
+const PCAPNG_ENHANCED_PACKET: u32 = 0x00000006;
+const PCAPNG_OPTION_END: u16 = 0;
+const PCAPNG_OPTION_IF_TSRESOL: u16 = 9;
+
+/// The LINKTYPE_ values found in capture file headers (see https://www.tcpdump.org/linktypes.html)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinkType(pub u16);
+
+impl LinkType {
+    pub const NULL: Self = Self(0);
+    pub const ETHERNET: Self = Self(1);
+    pub const RAW: Self = Self(101);
+    pub const LOOP: Self = Self(108);
+    pub const LINUX_SLL: Self = Self(113);
+    pub const IPV4: Self = Self(228);
+    pub const IPV6: Self = Self(229);
+}
+
+/// A single frame read from a pcap or pcapng file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Packet {
+    pub index: usize,
+    pub timestamp: OffsetDateTime,
+    pub linktype: LinkType,
+    pub data: Vec<u8>,
+}
+
+impl Packet {
+    pub fn new(index: usize, timestamp: OffsetDateTime, linktype: LinkType, data: Vec<u8>) -> Self {
+        Self {
+            index,
+            timestamp,
+            linktype,
+            data,
+        }
+    }
+
+    /// Slice the frame into its protocol layers, based on the link type of the capture it came from
+    pub fn slice(&self) -> Result<SlicedPacket<'_>> {
+        let sliced = match self.linktype {
+            LinkType::ETHERNET => SlicedPacket::from_ethernet(&self.data),
+            LinkType::LINUX_SLL => SlicedPacket::from_linux_sll(&self.data),
+            LinkType::RAW | LinkType::IPV4 | LinkType::IPV6 => SlicedPacket::from_ip(&self.data),
+            // BSD loopback frames start with a 4 byte address family header
+            LinkType::NULL | LinkType::LOOP if self.data.len() >= 4 => {
+                SlicedPacket::from_ip(&self.data[4..])
+            }
+            _ => return Err(anyhow!("Unsupported link type: {:?}", self.linktype)),
+        };
+
+        sliced.map_err(|e| anyhow!("Failed to slice packet {}: {}", self.index, e))
+    }
+
+    pub fn info(&self) -> PacketInfo {
+        PacketInfo::new(self.index, self.timestamp)
+    }
+}
+
+pub struct Capture {
+    pub packets: Vec<Packet>,
+    pub needles: Vec<Needle>,
+    pub layer: Layer,
+    pub registry: Registry,
+}
+
+impl Capture {
+    pub fn new(packets: Vec<Packet>) -> Self {
+        Self {
+            packets,
+            needles: Default::default(),
+            layer: Default::default(),
+            registry: Default::default(),
+        }
+    }
+
+    pub fn with_needles(packets: Vec<Packet>, needles: Vec<Needle>) -> Self {
+        Self {
+            packets,
+            needles,
+            layer: Default::default(),
+            registry: Default::default(),
+        }
+    }
+
+    pub fn with_layer(packets: Vec<Packet>, needles: Vec<Needle>, layer: Layer) -> Self {
+        Self {
+            packets,
+            needles,
+            layer,
+            registry: Default::default(),
+        }
+    }
+
+    /// Read every frame from a pcap or pcapng file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(anyhow!("Capture file does not exist: {}", path.display()));
+        }
+
+        let data = std::fs::read(path)?;
+
+        Ok(Self::new(read_capture(&data)?))
+    }
+
+    pub fn from_file_with_needles<P: AsRef<Path>>(path: P, needles: Vec<Needle>) -> Result<Self> {
+        let mut capture = Self::from_file(path)?;
+        capture.needles = needles;
+        Ok(capture)
+    }
+
+    pub fn burn(&self) -> Vec<Ashes<'_>> {
+        let mut ash_pile = Vec::<Ashes>::new();
+        let engine = Engine::new(&self.needles, &self.registry);
+
+        // Fragments only need putting back together if we're burning above the IP header
+        let reassemble = !matches!(self.layer, Layer::Frame | Layer::LinkPayload);
+
+        for packet in &self.packets {
+            // Frames we can't slice are burned as they are
+            let ashes = match packet.slice() {
+                Ok(sliced) if reassemble && is_fragment(&sliced) => continue,
+                Ok(sliced) => burn_layer(&sliced, Some(&packet.data), self.layer, &engine),
+                Err(_) => {
+                    let mut ashes = engine.burn(&packet.data);
+                    for ash in &mut ashes {
+                        ash.layer = Some(Layer::Frame);
+                        ash.frame_offset = Some(ash.offset);
+                    }
+                    ashes
+                }
+            };
+
+            for mut ash in ashes {
+                ash.packet = Some(packet.info());
+                ash_pile.push(ash);
+            }
+        }
+
+        if !reassemble {
+            return ash_pile;
+        }
+
+        // IPv4 fragments are burned once they've been put back together
+        for datagram in defragment(&self.packets) {
+            let header_len = match self.layer {
+                Layer::TransportPayload => datagram.header_len(),
+                _ => 0,
+            };
+
+            for run in &datagram.runs {
+                let skip = header_len.saturating_sub(run.offset).min(run.data.len());
+
+                for mut ash in engine.burn(&run.data[skip..]) {
+                    let offset = skip + ash.offset as usize;
+
+                    ash.spans = run.spans_for(offset, ash.variant.byte_len());
+                    ash.packet = ash.spans.first().map(|span| span.packet.clone());
+                    ash.offset = (run.offset + offset - header_len) as u64;
+                    ash.layer = Some(self.layer);
+
+                    ash_pile.push(ash);
+                }
+            }
+        }
+
+        // Keep the ashes from reassembled datagrams in capture order with everything else
+        ash_pile.sort_by_key(|ash| ash.packet.as_ref().map(|packet| packet.index));
+
+        ash_pile
+    }
+
+    /// Reassemble each TCP connection, and burn the client to server and server to client streams rather than individual packets
+    pub fn burn_streams(&self) -> Vec<Ashes<'_>> {
+        let mut ash_pile = Vec::<Ashes>::new();
+        let engine = Engine::new(&self.needles, &self.registry);
+
+        for flow in reassemble(&self.packets) {
+            for (stream, source, destination, direction) in [
+                (
+                    &flow.client_to_server,
+                    flow.client,
+                    flow.server,
+                    Direction::ClientToServer,
+                ),
+                (
+                    &flow.server_to_client,
+                    flow.server,
+                    flow.client,
+                    Direction::ServerToClient,
+                ),
+            ] {
+                for run in &stream.runs {
+                    for mut ash in engine.burn(&run.data) {
+                        ash.spans = run.spans_for(ash.offset as usize, ash.variant.byte_len());
+                        ash.packet = ash.spans.first().map(|span| span.packet.clone());
+                        ash.offset += run.offset as u64;
+                        ash.layer = Some(Layer::TransportPayload);
+                        ash.flow = Some(FlowInfo::new(source, destination, direction));
+
+                        ash_pile.push(ash);
+                    }
+                }
+            }
+        }
+
+        ash_pile
+    }
+}
+
+fn is_fragment(sliced: &SlicedPacket) -> bool {
+    matches!(&sliced.net, Some(NetSlice::Ipv4(ipv4)) if ipv4.is_payload_fragmented())
+}
+
+/// Work out whether the data is a pcap or pcapng file, and read all the frames from it
+pub fn read_capture(data: &[u8]) -> Result<Vec<Packet>> {
+    if data.len() < 4 {
+        return Err(anyhow!("Not enough data for this to be a capture file!"));
+    }
+
+    let magic = u32::from_le_bytes(data[0..4].try_into()?);
+
+    if magic == PCAPNG_SECTION_HEADER {
+        read_pcapng(data)
+    } else if [PCAP_MAGIC_MICROS, PCAP_MAGIC_NANOS].contains(&magic)
+        || [PCAP_MAGIC_MICROS, PCAP_MAGIC_NANOS].contains(&magic.swap_bytes())
+    {
+        read_pcap(data)
+    } else {
+        Err(anyhow!("Unrecognised capture file magic: {:08x}", magic))
+    }
+}
+
+/// Reads a u16 or u32 in whichever byte order the capture file was written with
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn u16(&self, offset: usize) -> Result<u16> {
+        let bytes = self
+            .data
+            .get(offset..offset + 2)
+            .ok_or_else(|| anyhow!("Capture file truncated at offset {}", offset))?;
+
+        Ok(if self.big_endian {
+            u16::from_be_bytes(bytes.try_into()?)
+        } else {
+            u16::from_le_bytes(bytes.try_into()?)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32> {
+        let bytes = self
+            .data
+            .get(offset..offset + 4)
+            .ok_or_else(|| anyhow!("Capture file truncated at offset {}", offset))?;
+
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes.try_into()?)
+        } else {
+            u32::from_le_bytes(bytes.try_into()?)
+        })
+    }
+
+    fn bytes(&self, offset: usize, len: usize) -> Result<&'a [u8]> {
+        self.data
+            .get(offset..offset + len)
+            .ok_or_else(|| anyhow!("Capture file truncated at offset {}", offset))
+    }
+}
+
+fn read_pcap(data: &[u8]) -> Result<Vec<Packet>> {
+    let magic = u32::from_le_bytes(data[0..4].try_into()?);
+    let reader = Reader {
+        data,
+        big_endian: ![PCAP_MAGIC_MICROS, PCAP_MAGIC_NANOS].contains(&magic),
+    };
+
+    let nanos_per_tick = if reader.u32(0)? == PCAP_MAGIC_NANOS {
+        1
+    } else {
+        1_000
+    };
+
+    // The upper 16 bits of the link type field hold FCS information that we don't need
+    let linktype = LinkType(reader.u32(20)? as u16);
+
+    let mut packets = Vec::<Packet>::new();
+    let mut offset = 24;
+
+    while offset < data.len() {
+        let secs = reader.u32(offset)?;
+        let ticks = reader.u32(offset + 4)?;
+        let captured_len = reader.u32(offset + 8)? as usize;
+
+        let timestamp = OffsetDateTime::from_unix_timestamp_nanos(
+            secs as i128 * 1_000_000_000 + ticks as i128 * nanos_per_tick,
+        )?;
+        let frame = reader.bytes(offset + 16, captured_len)?;
+
+        packets.push(Packet::new(
+            packets.len(),
+            timestamp,
+            linktype,
+            frame.to_owned(),
+        ));
+
+        offset += 16 + captured_len;
+    }
+
+    Ok(packets)
+}
+
+/// Each pcapng interface has its own link type and timestamp resolution
+struct Interface {
+    linktype: LinkType,
+    snaplen: u32,
+    ticks_per_second: u64,
+}
+
+fn read_pcapng(data: &[u8]) -> Result<Vec<Packet>> {
+    let mut reader = Reader {
+        data,
+        big_endian: false,
+    };
+
+    let mut interfaces = Vec::<Interface>::new();
+    let mut packets = Vec::<Packet>::new();
+    let mut offset = 0;
+
+    while offset < data.len() {
+        // Every section header resets the byte order and the interfaces that packets refer to
+        if u32::from_le_bytes(reader.bytes(offset, 4)?.try_into()?) == PCAPNG_SECTION_HEADER {
+            reader.big_endian = false;
+            if reader.u32(offset + 8)? != PCAPNG_BYTE_ORDER_MAGIC {
+                reader.big_endian = true;
+            }
+            interfaces.clear();
+        }
+
+        let block_type = reader.u32(offset)?;
+        let block_len = reader.u32(offset + 4)? as usize;
+
+        if block_len < 12 || !block_len.is_multiple_of(4) {
+            return Err(anyhow!(
+                "Invalid pcapng block length {} at offset {}",
+                block_len,
+                offset
+            ));
+        }
+
+        let body = offset + 8;
+        let body_end = offset + block_len - 4;
+
+        // Fields are read from the block alone, so a block too short to hold them is an error rather than a read into the next one
+        let block = Reader {
+            data: data
+                .get(..body_end)
+                .ok_or_else(|| anyhow!("Capture file truncated at offset {}", offset))?,
+            big_endian: reader.big_endian,
+        };
+
+        match block_type {
+            PCAPNG_INTERFACE_DESCRIPTION => {
+                let linktype = LinkType(block.u16(body)?);
+                let snaplen = block.u32(body + 4)?;
+
+                // Timestamps are in microseconds unless the if_tsresol option says otherwise
+                let mut ticks_per_second = 1_000_000u64;
+                let mut option = body + 8;
+
+                while option + 4 <= body_end {
+                    let code = block.u16(option)?;
+                    let len = block.u16(option + 2)? as usize;
+
+                    if code == PCAPNG_OPTION_END {
+                        break;
+                    }
+
+                    if code == PCAPNG_OPTION_IF_TSRESOL && len == 1 {
+                        let tsresol = block.bytes(option + 4, 1)?[0];
+                        let exponent = (tsresol & 0x7f) as u32;
+                        ticks_per_second = if tsresol & 0x80 == 0 {
+                            10u64.checked_pow(exponent)
+                        } else {
+                            2u64.checked_pow(exponent)
+                        }
+                        .ok_or_else(|| anyhow!("Unsupported if_tsresol value: {}", tsresol))?;
+                    }
+
+                    option += 4 + len.div_ceil(4) * 4;
+                }
+
+                interfaces.push(Interface {
+                    linktype,
+                    snaplen,
+                    ticks_per_second,
+                });
+            }
+            PCAPNG_ENHANCED_PACKET => {
+                let interface_id = block.u32(body)? as usize;
+                let interface = interfaces.get(interface_id).ok_or_else(|| {
+                    anyhow!("Packet refers to unknown interface {}", interface_id)
+                })?;
+
+                let ticks = ((block.u32(body + 4)? as u64) << 32) | block.u32(body + 8)? as u64;
+                let captured_len = block.u32(body + 12)? as usize;
+
+                if body + 20 + captured_len > body_end {
+                    return Err(anyhow!(
+                        "Enhanced packet block at offset {} is too short for its {} captured bytes",
+                        offset,
+                        captured_len
+                    ));
+                }
+
+                let timestamp = OffsetDateTime::from_unix_timestamp_nanos(
+                    ticks as i128 * 1_000_000_000 / interface.ticks_per_second as i128,
+                )?;
+                let frame = block.bytes(body + 20, captured_len)?;
+
+                packets.push(Packet::new(
+                    packets.len(),
+                    timestamp,
+                    interface.linktype,
+                    frame.to_owned(),
+                ));
+            }
+            PCAPNG_SIMPLE_PACKET => {
+                // Simple packets always belong to the first interface, and have no timestamp
+                let interface = interfaces
+                    .first()
+                    .ok_or_else(|| anyhow!("Simple packet block without an interface"))?;
+
+                let original_len = block.u32(body)? as usize;
+                let captured_len = match interface.snaplen {
+                    0 => original_len,
+                    snaplen => original_len.min(snaplen as usize),
+                };
+
+                if body + 4 + captured_len > body_end {
+                    return Err(anyhow!(
+                        "Simple packet block at offset {} is too short for its {} captured bytes",
+                        offset,
+                        captured_len
+                    ));
+                }
+
+                let frame = block.bytes(body + 4, captured_len)?;
+
+                packets.push(Packet::new(
+                    packets.len(),
+                    OffsetDateTime::UNIX_EPOCH,
+                    interface.linktype,
+                    frame.to_owned(),
+                ));
+            }
+            _ => (), // Section headers and anything else we don't need are skipped
+        }
+
+        offset += block_len;
+    }
+
+    Ok(packets)
+}
+
+#[cfg(test)]
+mod tests {
+    use etherparse::PacketBuilder;
+
+    use crate::needle::{
+        ipaddr::variant::IPv4Variant, number::variants::IntegerVariant, variant::NeedleVariant,
+        Matches,
+    };
+
+    use super::*;
+
+    /// Build a minimal little endian, microsecond resolution pcap file containing the given (secs, micros, frame) records
+    fn build_pcap(frames: &[(u32, u32, Vec<u8>)]) -> Vec<u8> {
+        let mut file = Vec::<u8>::new();
+
+        file.extend_from_slice(&PCAP_MAGIC_MICROS.to_le_bytes());
+        file.extend_from_slice(&2u16.to_le_bytes()); // major version
+        file.extend_from_slice(&4u16.to_le_bytes()); // minor version
+        file.extend_from_slice(&0i32.to_le_bytes()); // timezone
+        file.extend_from_slice(&0u32.to_le_bytes()); // sigfigs
+        file.extend_from_slice(&65535u32.to_le_bytes()); // snaplen
+        file.extend_from_slice(&1u32.to_le_bytes()); // ethernet
+
+        for (secs, micros, frame) in frames {
+            file.extend_from_slice(&secs.to_le_bytes());
+            file.extend_from_slice(&micros.to_le_bytes());
+            file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            file.extend_from_slice(frame);
+        }
+
+        file
+    }
+
+    /// Build a minimal big endian pcapng file with a single nanosecond resolution ethernet interface
+    fn build_pcapng(frames: &[(u64, Vec<u8>)]) -> Vec<u8> {
+        let mut file = Vec::<u8>::new();
+
+        // Section header
+        file.extend_from_slice(&PCAPNG_SECTION_HEADER.to_be_bytes());
+        file.extend_from_slice(&28u32.to_be_bytes());
+        file.extend_from_slice(&PCAPNG_BYTE_ORDER_MAGIC.to_be_bytes());
+        file.extend_from_slice(&1u16.to_be_bytes());
+        file.extend_from_slice(&0u16.to_be_bytes());
+        file.extend_from_slice(&(-1i64).to_be_bytes());
+        file.extend_from_slice(&28u32.to_be_bytes());
+
+        // Interface description, with if_tsresol set to 9 (nanoseconds)
+        file.extend_from_slice(&PCAPNG_INTERFACE_DESCRIPTION.to_be_bytes());
+        file.extend_from_slice(&32u32.to_be_bytes());
+        file.extend_from_slice(&1u16.to_be_bytes());
+        file.extend_from_slice(&0u16.to_be_bytes());
+        file.extend_from_slice(&0u32.to_be_bytes());
+        file.extend_from_slice(&PCAPNG_OPTION_IF_TSRESOL.to_be_bytes());
+        file.extend_from_slice(&1u16.to_be_bytes());
+        file.extend_from_slice(&[9, 0, 0, 0]);
+        file.extend_from_slice(&PCAPNG_OPTION_END.to_be_bytes());
+        file.extend_from_slice(&0u16.to_be_bytes());
+        file.extend_from_slice(&32u32.to_be_bytes());
+
+        for (nanos, frame) in frames {
+            let padded_len = frame.len().div_ceil(4) * 4;
+            let block_len = (32 + padded_len) as u32;
+
+            file.extend_from_slice(&PCAPNG_ENHANCED_PACKET.to_be_bytes());
+            file.extend_from_slice(&block_len.to_be_bytes());
+            file.extend_from_slice(&0u32.to_be_bytes());
+            file.extend_from_slice(&((nanos >> 32) as u32).to_be_bytes());
+            file.extend_from_slice(&(*nanos as u32).to_be_bytes());
+            file.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+            file.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+            file.extend_from_slice(frame);
+            file.resize(file.len() + padded_len - frame.len(), 0);
+            file.extend_from_slice(&block_len.to_be_bytes());
+        }
+
+        file
+    }
+
+    fn udp_frame(payload: &[u8]) -> Vec<u8> {
+        let builder = PacketBuilder::ethernet2([1, 2, 3, 4, 5, 6], [7, 8, 9, 10, 11, 12])
+            .ipv4([10, 0, 0, 1], [10, 0, 0, 2], 64)
+            .udp(12345, 53);
+
+        let mut frame = Vec::<u8>::with_capacity(builder.size(payload.len()));
+        builder.write(&mut frame, payload).unwrap();
+        frame
+    }
+
+    #[test]
+    fn missing_file() {
+        assert!(Capture::from_file("/this/file/does/not/exist.pcap").is_err());
+    }
+
+    #[test]
+    fn not_a_capture() {
+        assert!(read_capture(&[0xde, 0xad, 0xbe, 0xef, 0x00, 0x00]).is_err());
+    }
+
+    #[test]
+    fn pcapng_test() {
+        let packets = read_capture(&build_pcapng(&[
+            (1_704_067_200_123_456_789, udp_frame(&[0x01, 0x02, 0x03])),
+            (1_704_067_201_000_000_001, udp_frame(&[0x04])),
+        ]))
+        .unwrap();
+
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].linktype, LinkType::ETHERNET);
+        assert_eq!(
+            packets[0].timestamp,
+            OffsetDateTime::from_unix_timestamp_nanos(1_704_067_200_123_456_789).unwrap()
+        );
+        assert_eq!(packets[1].index, 1);
+        assert_eq!(packets[1].data, udp_frame(&[0x04]));
+    }
+
+    #[test]
+    fn block_overrun_test() {
+        // An enhanced packet claiming more captured bytes than its block holds, which would otherwise run on into the next block
+        let mut file = build_pcapng(&[(0, udp_frame(&[0x01])), (0, udp_frame(&[0x02]))]);
+        let captured_len = udp_frame(&[0x01]).len() as u32 + 8;
+        file[80..84].copy_from_slice(&captured_len.to_be_bytes());
+        assert!(read_capture(&file).is_err());
+
+        // The same for a simple packet, whose captured length comes from its original length
+        let mut file = build_pcapng(&[]);
+        file.extend_from_slice(&PCAPNG_SIMPLE_PACKET.to_be_bytes());
+        file.extend_from_slice(&20u32.to_be_bytes());
+        file.extend_from_slice(&64u32.to_be_bytes());
+        file.extend_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        file.extend_from_slice(&20u32.to_be_bytes());
+        file.extend_from_slice(&build_pcapng(&[(0, udp_frame(&[0x03]))])[28..]);
+        assert!(read_capture(&file).is_err());
+    }
+
+    #[test]
+    fn truncated_interface_test() {
+        let file = build_pcapng(&[(0, udp_frame(&[0x01]))]);
+
+        // An interface description with room for its link type, but not its snap length or options
+        let mut truncated = file[..28].to_vec();
+        truncated.extend_from_slice(&PCAPNG_INTERFACE_DESCRIPTION.to_be_bytes());
+        truncated.extend_from_slice(&16u32.to_be_bytes());
+        truncated.extend_from_slice(&1u16.to_be_bytes());
+        truncated.extend_from_slice(&0u16.to_be_bytes());
+        truncated.extend_from_slice(&16u32.to_be_bytes());
+        truncated.extend_from_slice(&file[60..]);
+
+        assert!(read_capture(&file).is_ok());
+        assert!(read_capture(&truncated).is_err());
+    }
+
+    #[test]
+    fn pcap_test() {
+        let path =
+            std::env::temp_dir().join(format!("bth_capture_pcap_test_{}.pcap", std::process::id()));
+
+        // Three packets, the last of which contains an IP address (192.168.0.1 as Numeric(U32BE)) in its UDP payload
+        let file = build_pcap(&[
+            (1704067200, 0, udp_frame(&[0xde, 0xad, 0xbe, 0xef])),
+            (1704067201, 500000, udp_frame(&[0xca, 0xfe, 0xba, 0xbe])),
+            (
+                1704067202,
+                250,
+                udp_frame(&[0xff, 0xff, 0xc0, 0xa8, 0x00, 0x01, 0xff, 0xff]),
+            ),
+        ]);
+        std::fs::write(&path, file).unwrap();
+
+        let private_ip =
+            Needle::new_ip_address_with_tolerance("192.168.0.0".parse().unwrap(), 16).unwrap();
+        let needles = vec![private_ip];
+
+        let capture = Capture::from_file_with_needles(&path, needles.clone()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(capture.packets.len(), 3);
+
+        let results = capture.burn();
+
+        for result in &results {
+            println!("{:02x?}", result);
+        }
+
+        assert!(results.len() == 1); // There should be only one match
+        assert!(results[0].actual.matches(&needles[0]));
+        assert!(matches!(
+            results[0].variant,
+            NeedleVariant::IpAddr(IPv4Variant::Numeric(IntegerVariant::U32BE(_)))
+        ));
+
+        // The offset is relative to the start of the UDP payload, which is after 42 bytes of headers
+        assert_eq!(results[0].offset, 2);
+        assert_eq!(results[0].layer, Some(Layer::TransportPayload));
+        assert_eq!(results[0].frame_offset, Some(44));
+
+        let packet = results[0].packet.as_ref().unwrap();
+        assert_eq!(packet.index, 2);
+        assert_eq!(
+            packet.timestamp,
+            OffsetDateTime::from_unix_timestamp_nanos(1_704_067_202_000_250_000).unwrap()
+        );
+    }
+
+    #[test]
+    fn layer_test() {
+        // The IP address is only in the IP header, not in any payload
+        let builder = PacketBuilder::ethernet2([1, 2, 3, 4, 5, 6], [7, 8, 9, 10, 11, 12])
+            .ipv4([192, 168, 0, 1], [10, 0, 0, 2], 64)
+            .udp(12345, 53);
+        let mut frame = Vec::<u8>::new();
+        builder.write(&mut frame, &[0xff; 8]).unwrap();
+
+        let packets = vec![Packet::new(
+            0,
+            OffsetDateTime::from_unix_timestamp(1704067200).unwrap(),
+            LinkType::ETHERNET,
+            frame,
+        )];
+
+        let private_ip =
+            Needle::new_ip_address_with_tolerance("192.168.0.0".parse().unwrap(), 16).unwrap();
+        let needles = vec![private_ip];
+
+        for layer in [
+            Layer::NetworkPayload,
+            Layer::TransportSegment,
+            Layer::TransportPayload,
+        ] {
+            let capture = Capture::with_layer(packets.clone(), needles.clone(), layer);
+            assert!(capture.burn().is_empty());
+        }
+
+        for (layer, offset) in [(Layer::Frame, 26), (Layer::LinkPayload, 12)] {
+            let capture = Capture::with_layer(packets.clone(), needles.clone(), layer);
+            let results = capture.burn();
+
+            for result in &results {
+                println!("{:02x?}", result);
+            }
+
+            assert!(results.len() == 1);
+            assert!(results[0].actual.matches(&needles[0]));
+            assert_eq!(results[0].layer, Some(layer));
+            assert_eq!(results[0].offset, offset);
+            assert_eq!(results[0].frame_offset, Some(26));
+        }
+    }
+}
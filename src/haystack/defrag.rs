@@ -0,0 +1,290 @@
+use std::{collections::HashMap, net::Ipv4Addr};
+
+use etherparse::{IpNumber, NetSlice};
+
+use super::{
+    ashes::PacketInfo,
+    capture::Packet,
+    stream::{assemble_runs, StreamRun},
+};
+
+/// An IPv4 datagram rebuilt from its fragments
+#[derive(Clone, Debug, PartialEq)]
+pub struct Datagram {
+    pub source: Ipv4Addr,
+    pub destination: Ipv4Addr,
+    pub protocol: IpNumber,
+    pub identification: u16,
+    pub runs: Vec<StreamRun>,
+    pub complete: bool,
+}
+
+impl Datagram {
+    /// How many bytes at the start of the reassembled payload belong to the transport header
+    pub fn header_len(&self) -> usize {
+        match self.protocol {
+            IpNumber::UDP | IpNumber::ICMP => 8,
+            IpNumber::TCP => self
+                .runs
+                .first()
+                .filter(|run| run.offset == 0 && run.data.len() > 12)
+                .map(|run| ((run.data[12] >> 4) as usize) * 4)
+                .unwrap_or(20),
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct DatagramBuilder<'a> {
+    pieces: Vec<(usize, &'a [u8], PacketInfo)>,
+    total_len: Option<usize>,
+}
+
+/// Find every fragmented IPv4 datagram in a capture, and stitch the fragments back together
+///
+/// Fragments are grouped by (source, destination, protocol, identification), and datagrams that are missing fragments are returned with more than one run
+pub fn defragment(packets: &[Packet]) -> Vec<Datagram> {
+    let mut keys = Vec::<(Ipv4Addr, Ipv4Addr, IpNumber, u16)>::new();
+    let mut builders = HashMap::<(Ipv4Addr, Ipv4Addr, IpNumber, u16), DatagramBuilder>::new();
+
+    for packet in packets {
+        let Ok(sliced) = packet.slice() else {
+            continue;
+        };
+
+        let Some(NetSlice::Ipv4(ipv4)) = &sliced.net else {
+            continue;
+        };
+
+        if !ipv4.is_payload_fragmented() {
+            continue;
+        }
+
+        let header = ipv4.header();
+        let key = (
+            header.source_addr(),
+            header.destination_addr(),
+            header.protocol(),
+            header.identification(),
+        );
+
+        let builder = builders.entry(key).or_insert_with(|| {
+            keys.push(key);
+            DatagramBuilder::default()
+        });
+
+        // Fragment offsets are measured in 8 byte blocks
+        let offset = header.fragments_offset().value() as usize * 8;
+        let payload = ipv4.payload().payload;
+
+        if !header.more_fragments() {
+            builder.total_len = Some(offset + payload.len());
+        }
+
+        builder.pieces.push((offset, payload, packet.info()));
+    }
+
+    keys.into_iter()
+        .filter_map(|key| builders.remove(&key).map(|builder| (key, builder)))
+        .map(
+            |((source, destination, protocol, identification), builder)| {
+                let runs = assemble_runs(builder.pieces);
+                let complete = runs.len() == 1
+                    && runs[0].offset == 0
+                    && builder.total_len == Some(runs[0].data.len());
+
+                Datagram {
+                    source,
+                    destination,
+                    protocol,
+                    identification,
+                    runs,
+                    complete,
+                }
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use etherparse::{EtherType, Ethernet2Header, IpFragOffset, Ipv4Header, UdpHeader};
+    use time::OffsetDateTime;
+
+    use crate::{
+        haystack::capture::{Capture, LinkType},
+        needle::{
+            ipaddr::variant::IPv4Variant, number::variants::IntegerVariant, variant::NeedleVariant,
+            Matches, Needle,
+        },
+    };
+
+    use super::*;
+
+    /// Split a UDP datagram into IPv4 fragments carrying `chunk` bytes each (which must be a multiple of 8)
+    fn udp_fragments(identification: u16, payload: &[u8], chunk: usize) -> Vec<Vec<u8>> {
+        let udp = UdpHeader::with_ipv4_checksum(1234, 5678, &fake_ipv4(0, 0), payload).unwrap();
+
+        let mut datagram = Vec::<u8>::new();
+        udp.write(&mut datagram).unwrap();
+        datagram.extend_from_slice(payload);
+
+        datagram
+            .chunks(chunk)
+            .enumerate()
+            .map(|(i, fragment)| {
+                let mut ip = fake_ipv4(fragment.len(), identification);
+                ip.fragment_offset = IpFragOffset::try_new((i * chunk / 8) as u16).unwrap();
+                ip.more_fragments = (i + 1) * chunk < datagram.len();
+
+                let mut frame = Vec::<u8>::new();
+                Ethernet2Header {
+                    source: [1, 2, 3, 4, 5, 6],
+                    destination: [7, 8, 9, 10, 11, 12],
+                    ether_type: EtherType::IPV4,
+                }
+                .write(&mut frame)
+                .unwrap();
+                ip.write(&mut frame).unwrap();
+                frame.extend_from_slice(fragment);
+                frame
+            })
+            .collect()
+    }
+
+    fn fake_ipv4(payload_len: usize, identification: u16) -> Ipv4Header {
+        let mut ip = Ipv4Header::new(
+            payload_len as u16,
+            64,
+            IpNumber::UDP,
+            [10, 0, 0, 1],
+            [10, 0, 0, 2],
+        )
+        .unwrap();
+        ip.identification = identification;
+        ip
+    }
+
+    fn packets(frames: Vec<Vec<u8>>) -> Vec<Packet> {
+        frames
+            .into_iter()
+            .enumerate()
+            .map(|(index, frame)| {
+                Packet::new(
+                    index,
+                    OffsetDateTime::from_unix_timestamp(1704067200 + index as i64).unwrap(),
+                    LinkType::ETHERNET,
+                    frame,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn defragment_test() {
+        let payload = (0u8..40).collect::<Vec<_>>();
+
+        // Two interleaved datagrams, with the fragments of the first arriving out of order
+        let mut first = udp_fragments(1, &payload, 16);
+        first.reverse();
+        let second = udp_fragments(2, &payload, 24);
+
+        let frames = vec![
+            first[0].clone(),
+            second[0].clone(),
+            first[1].clone(),
+            first[2].clone(),
+            second[1].clone(),
+        ];
+
+        let datagrams = defragment(&packets(frames));
+        assert_eq!(datagrams.len(), 2);
+
+        for datagram in &datagrams {
+            assert!(datagram.complete);
+            assert_eq!(datagram.runs.len(), 1);
+            assert_eq!(datagram.header_len(), 8);
+            assert_eq!(datagram.runs[0].data[8..], payload);
+        }
+
+        assert_eq!(datagrams[0].identification, 1);
+        assert_eq!(datagrams[1].identification, 2);
+    }
+
+    #[test]
+    fn missing_fragment_test() {
+        let payload = (0u8..40).collect::<Vec<_>>();
+        let mut fragments = udp_fragments(1, &payload, 16);
+        fragments.remove(1);
+
+        let datagrams = defragment(&packets(fragments));
+        assert_eq!(datagrams.len(), 1);
+        assert!(!datagrams[0].complete);
+        assert_eq!(datagrams[0].runs.len(), 2);
+        assert_eq!(datagrams[0].runs[1].offset, 32);
+    }
+
+    #[test]
+    fn burn_fragments_test() {
+        // An IP address (192.168.0.1 as Numeric(U32BE)) straddling the first and second fragments
+        let mut payload = vec![0xffu8; 32];
+        payload[6..10].copy_from_slice(&[0xc0, 0xa8, 0x00, 0x01]);
+
+        let private_ip =
+            Needle::new_ip_address_with_tolerance("192.168.0.0".parse().unwrap(), 16).unwrap();
+        let needles = vec![private_ip];
+
+        let capture =
+            Capture::with_needles(packets(udp_fragments(7, &payload, 16)), needles.clone());
+        let results = capture.burn();
+
+        for result in &results {
+            println!("{:02x?}", result);
+        }
+
+        assert!(results.len() == 1);
+        assert!(results[0].actual.matches(&needles[0]));
+        assert!(matches!(
+            results[0].variant,
+            NeedleVariant::IpAddr(IPv4Variant::Numeric(IntegerVariant::U32BE(_)))
+        ));
+
+        // The offset is relative to the start of the reassembled UDP payload
+        assert_eq!(results[0].offset, 6);
+
+        // And the spans are relative to the IP payload of each fragment
+        assert_eq!(results[0].spans.len(), 2);
+        assert_eq!(
+            (results[0].spans[0].packet.index, results[0].spans[0].offset),
+            (0, 14)
+        );
+        assert_eq!(
+            (results[0].spans[1].packet.index, results[0].spans[1].offset),
+            (1, 0)
+        );
+        assert_eq!(results[0].packet.as_ref().unwrap().index, 0);
+    }
+
+    #[test]
+    fn capture_order_test() {
+        // The same IP address in a fragmented datagram, followed by an unfragmented one
+        let mut payload = vec![0xffu8; 32];
+        payload[6..10].copy_from_slice(&[0xc0, 0xa8, 0x00, 0x01]);
+
+        let mut frames = udp_fragments(7, &payload, 16);
+        frames.extend(udp_fragments(8, &payload, 40));
+
+        let private_ip =
+            Needle::new_ip_address_with_tolerance("192.168.0.0".parse().unwrap(), 16).unwrap();
+        let capture = Capture::with_needles(packets(frames), vec![private_ip]);
+        let results = capture.burn();
+
+        // The reassembled datagram's hit comes first, as its packets were captured first
+        let indices = results
+            .iter()
+            .map(|result| result.packet.as_ref().unwrap().index)
+            .collect::<Vec<_>>();
+        assert_eq!(indices, vec![0, 3]);
+    }
+}
@@ -0,0 +1,389 @@
+use std::{collections::HashSet, marker::PhantomData};
+
+use crate::needle::{
+    custom::CustomNeedle,
+    ipaddr::variant::{IPv4Variant, IPv6Variant},
+    location::variant::LocationVariant,
+    macaddr::variant::MACAddrVariant,
+    number::variants::{FloatVariant, IntegerVariant},
+    sockaddr::variant::SockAddrVariant,
+    string::variant::StringVariant,
+    text::TextEncoding,
+    timestamp::variants::TimestampVariant,
+    uuid::variant::UUIDVariant,
+    variant::{family, NeedleVariant},
+    Interpret, Matches, Needle,
+};
+
+use super::{ashes::Ashes, index::NeedleIndex};
+
+/// A family of variants that bytes in the haystack can be interpreted as
+pub trait Family: Send + Sync {
+    /// The name the family is registered, enabled and disabled under
+    fn name(&self) -> &'static str;
+
+    /// Every valid interpretation of the bytes at the start of the data
+    fn interpret(&self, data: &[u8]) -> Vec<NeedleVariant>;
+}
+
+/// One of the built-in variant families, interpreted with its Interpret implementation
+struct Builtin<V> {
+    name: &'static str,
+    wrap: fn(V) -> NeedleVariant,
+    variant: PhantomData<fn() -> V>,
+}
+
+impl<V: Interpret> Builtin<V> {
+    fn boxed(name: &'static str, wrap: fn(V) -> NeedleVariant) -> Box<dyn Family>
+    where
+        V: 'static,
+    {
+        Box::new(Self {
+            name,
+            wrap,
+            variant: PhantomData,
+        })
+    }
+}
+
+impl<V: Interpret> Family for Builtin<V> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn interpret(&self, data: &[u8]) -> Vec<NeedleVariant> {
+        V::interpret(data)
+            .map(|variants| variants.into_iter().map(self.wrap).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// The variant family of a custom needle, interpreted with its variant's Interpret implementation
+struct Custom<N>(PhantomData<fn() -> N>);
+
+impl<N: CustomNeedle> Family for Custom<N> {
+    fn name(&self) -> &'static str {
+        N::FAMILY
+    }
+
+    fn interpret(&self, data: &[u8]) -> Vec<NeedleVariant> {
+        <N::Variant as Interpret>::interpret(data)
+            .map(|variants| {
+                variants
+                    .into_iter()
+                    .map(NeedleVariant::custom::<N>)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Raw bytes, which have no meaning to interpret, so can only be found by searching for them
+struct Bytes;
+
+impl Family for Bytes {
+    fn name(&self) -> &'static str {
+        family::BYTES
+    }
+
+    fn interpret(&self, _data: &[u8]) -> Vec<NeedleVariant> {
+        Vec::new()
+    }
+}
+
+/// The variant families a burn interprets the haystack as, in the order they're tried
+///
+/// Families can be disabled for a run without unregistering them, in which case neither the index nor the interpretive scan will report any of their variants
+pub struct Registry {
+    families: Vec<Box<dyn Family>>,
+    disabled: HashSet<String>,
+}
+
+impl Default for Registry {
+    /// Every built-in family, all enabled
+    fn default() -> Self {
+        let mut registry = Self::empty();
+
+        registry.register(Builtin::<IntegerVariant>::boxed(
+            family::INTEGER,
+            NeedleVariant::Integer,
+        ));
+        registry.register(Builtin::<FloatVariant>::boxed(
+            family::FLOAT,
+            NeedleVariant::Float,
+        ));
+        registry.register(Builtin::<TimestampVariant>::boxed(
+            family::TIMESTAMP,
+            NeedleVariant::Timestamp,
+        ));
+        registry.register(Builtin::<LocationVariant>::boxed(
+            family::LOCATION,
+            NeedleVariant::Location,
+        ));
+        registry.register(Builtin::<IPv4Variant>::boxed(
+            family::IPADDR,
+            NeedleVariant::IpAddr,
+        ));
+        registry.register(Builtin::<IPv6Variant>::boxed(
+            family::IPV6ADDR,
+            NeedleVariant::Ipv6Addr,
+        ));
+        registry.register(Builtin::<MACAddrVariant>::boxed(
+            family::MACADDR,
+            NeedleVariant::MacAddr,
+        ));
+        registry.register(Builtin::<SockAddrVariant>::boxed(
+            family::SOCKADDR,
+            NeedleVariant::SockAddr,
+        ));
+        registry.register(Builtin::<StringVariant>::boxed(
+            family::STRING,
+            NeedleVariant::String,
+        ));
+        registry.register(Builtin::<UUIDVariant>::boxed(
+            family::UUID,
+            NeedleVariant::Uuid,
+        ));
+        registry.register(Box::new(Bytes));
+
+        registry
+    }
+}
+
+impl Registry {
+    /// A registry without any families, not even the built-in ones
+    pub fn empty() -> Self {
+        Self {
+            families: Vec::new(),
+            disabled: HashSet::new(),
+        }
+    }
+
+    /// Add a family, replacing any that's already registered under the same name
+    pub fn register(&mut self, family: Box<dyn Family>) {
+        match self
+            .families
+            .iter_mut()
+            .find(|registered| registered.name() == family.name())
+        {
+            Some(registered) => *registered = family,
+            None => self.families.push(family),
+        }
+    }
+
+    /// Add the variant family of a custom needle, so it can be found like any of the built-in ones
+    pub fn register_custom<N: CustomNeedle>(&mut self) {
+        self.register(Box::new(Custom::<N>(PhantomData)));
+    }
+
+    pub fn enable(&mut self, name: &str) {
+        self.disabled.remove(name);
+    }
+
+    pub fn disable(&mut self, name: &str) {
+        self.disabled.insert(name.to_string());
+    }
+
+    /// Whether a family is registered and hasn't been disabled
+    pub fn is_enabled(&self, name: &str) -> bool {
+        !self.disabled.contains(name) && self.families.iter().any(|family| family.name() == name)
+    }
+
+    /// The names of every registered family, whether it's enabled or not
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.families.iter().map(|family| family.name())
+    }
+
+    /// The families that will be tried, in the order they'll be tried in
+    pub fn enabled(&self) -> impl Iterator<Item = &dyn Family> {
+        self.families
+            .iter()
+            .map(|family| family.as_ref())
+            .filter(|family| !self.disabled.contains(family.name()))
+    }
+}
+
+/// The needles and families for a single burn, shared by every kind of haystack
+pub struct Engine<'n> {
+    index: NeedleIndex<'n>,
+    registry: &'n Registry,
+}
+
+impl<'n> Engine<'n> {
+    pub fn new(needles: &'n [Needle], registry: &'n Registry) -> Self {
+        Self {
+            index: NeedleIndex::new(needles, registry),
+            registry,
+        }
+    }
+
+    /// How far a chunk of the haystack needs to overlap the next one for nothing to be missed
+    pub fn width(&self) -> usize {
+        self.index.width()
+    }
+
+    /// How many bytes before a chunk of the haystack need burning along with it, so text at its start can tell whether it's the end of something longer
+    pub fn lead(&self) -> usize {
+        TextEncoding::MAX_UNIT_LEN
+    }
+
+    /// Search a slice of bytes for the indexed needles, then interpret it for the rest, and return the Ashes ordered by offset
+    pub fn burn(&self, data: &[u8]) -> Vec<Ashes<'n>> {
+        // Exact needles are discombobulated once and found with a single pass over the data...
+        let mut ash_pile = self.index.search(data);
+
+        // ...leaving only the needles that can't be indexed to be found by interpreting every offset
+        if !self.index.fallback().is_empty() {
+            ash_pile.append(&mut interpret_slice(
+                data,
+                self.index.fallback(),
+                self.registry,
+            ));
+            ash_pile.sort_by_key(|ash| ash.offset);
+        }
+
+        ash_pile
+    }
+
+    /// Burn one window of a larger haystack, given the `lead` bytes before it and as much of what follows it as there is
+    ///
+    /// Only the Ashes that start inside the window are kept, and their offsets are moved to be from the start of the whole haystack, which the window starts `start` bytes into
+    pub fn burn_window(
+        &self,
+        data: &[u8],
+        lead: usize,
+        window_len: usize,
+        start: u64,
+    ) -> Vec<Ashes<'n>> {
+        let mut ash_pile = self.burn(data);
+
+        ash_pile.retain(|ash| (lead as u64..(lead + window_len) as u64).contains(&ash.offset));
+        for ash in &mut ash_pile {
+            ash.offset = ash.offset - lead as u64 + start;
+        }
+
+        ash_pile
+    }
+}
+
+/// Try every enabled family at every offset in a slice of bytes, and return the Ashes of any that match the needles
+pub(crate) fn interpret_slice<'n>(
+    data: &[u8],
+    needles: &[&'n Needle],
+    registry: &Registry,
+) -> Vec<Ashes<'n>> {
+    let mut ash_pile = Vec::<Ashes>::new();
+
+    for i in 0..data.len() {
+        let window = &data[i..];
+
+        for family in registry.enabled() {
+            for variant in family.interpret(window) {
+                // Text can't tell from the window alone whether it's the end of something longer
+                let following = window.get(variant.byte_len()..).unwrap_or_default();
+                if !variant.is_complete(&data[..i], following) {
+                    continue;
+                }
+
+                for target in needles.iter().copied() {
+                    let Ok(putative) = variant.recombobulate_for(target) else {
+                        continue;
+                    };
+
+                    if putative.matches(target) {
+                        ash_pile.push(Ashes::new(target, putative.clone(), variant.clone(), i));
+                    }
+                }
+            }
+        }
+    }
+
+    ash_pile
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Duration;
+
+    use crate::haystack::{
+        fixtures::describe,
+        hay::{Hay, HayData},
+        Haystack,
+    };
+
+    use super::*;
+
+    #[test]
+    fn registry_test() {
+        let mut registry = Registry::default();
+        assert_eq!(registry.names().count(), 11);
+        assert!(registry.is_enabled(family::MACADDR));
+
+        registry.disable(family::MACADDR);
+        assert!(!registry.is_enabled(family::MACADDR));
+        assert_eq!(registry.enabled().count(), 10);
+
+        registry.enable(family::MACADDR);
+        assert!(registry.is_enabled(family::MACADDR));
+
+        // Registering under an existing name replaces the family rather than adding another
+        registry.register(Builtin::<IntegerVariant>::boxed(
+            family::MACADDR,
+            NeedleVariant::Integer,
+        ));
+        assert_eq!(registry.names().count(), 11);
+
+        assert!(!Registry::empty().is_enabled(family::INTEGER));
+    }
+
+    #[test]
+    fn disable_test() {
+        // A timestamp (2023-12-31 23:59:58 as EpochSecs(U32LE)) followed by an IP address (192.168.0.1 as Numeric(U32BE))
+        let data: Vec<u8> = vec![
+            0xde, 0xad, 0x7e, 0x00, 0x92, 0x65, 0xbe, 0xef, 0xc0, 0xa8, 0x00, 0x01, 0xff,
+        ];
+
+        let needles = vec![
+            Needle::new_timestamp("2023-12-31 23:59:58").unwrap(),
+            Needle::new_timestamp_with_tolerance("2023-12-31 00:00:00", Duration::days(1)).unwrap(),
+            Needle::new_ip_address("192.168.0.1".parse().unwrap()).unwrap(),
+        ];
+
+        let mut haystack = Haystack::with_needles(data, needles);
+        let all = haystack.burn();
+        assert!(all
+            .iter()
+            .any(|ash| ash.variant.family() == family::TIMESTAMP));
+
+        let addresses = all
+            .iter()
+            .filter(|ash| ash.variant.family() == family::IPADDR)
+            .count();
+        drop(all);
+
+        // Neither the exact nor the ranged timestamp needles should be found once timestamps are disabled
+        haystack.registry.disable(family::TIMESTAMP);
+        let ashes = haystack.burn();
+
+        assert!(!ashes.is_empty());
+        assert!(ashes
+            .iter()
+            .all(|ash| ash.variant.family() == family::IPADDR));
+        assert_eq!(ashes.len(), addresses);
+    }
+
+    #[test]
+    fn hay_macaddr_test() {
+        // A MAC address (E0:8F:4C:11:22:33 as BE)
+        let data: Vec<u8> = vec![0xff, 0xe0, 0x8f, 0x4c, 0x11, 0x22, 0x33, 0xff];
+        let needles = vec![Needle::new_mac_address("E0:8F:4C:11:22:33".parse().unwrap()).unwrap()];
+
+        let haystack = Haystack::with_needles(data.clone(), needles.clone());
+        let hay = Hay::with_needles(vec![HayData::Raw(data)], needles);
+
+        // Every kind of haystack is burned by the same engine, so hay finds MAC addresses too
+        let expected = describe(haystack.burn());
+        assert!(!expected.is_empty());
+        assert_eq!(expected, describe(hay.burn()));
+    }
+}
@@ -0,0 +1,43 @@
+use measurements::Distance;
+use time::Duration;
+
+use crate::needle::Needle;
+
+use super::ashes::Ashes;
+
+/// Where each of the Ashes was found and as what, in the order they were handed out
+pub fn describe<'a>(ashes: impl IntoIterator<Item = Ashes<'a>>) -> Vec<(u64, String)> {
+    ashes
+        .into_iter()
+        .map(|ash| (ash.offset, format!("{:?}", ash.variant)))
+        .collect()
+}
+
+/// Where each of the Ashes was found and as what, sorted so burns that find the same things in a different order compare equal
+pub fn describe_sorted<'a>(ashes: impl IntoIterator<Item = Ashes<'a>>) -> Vec<(u64, String)> {
+    let mut hits = describe(ashes);
+    hits.sort();
+    hits
+}
+
+/// A location, a timestamp, an IP address and an integer, repeated a few times so they straddle chunk and window boundaries
+pub fn data() -> Vec<u8> {
+    let data: Vec<u8> = vec![
+        0xde, 0xad, 0xbe, 0xef, 0x00, 0xa0, 0xef, 0xc4, 0x00, 0x38, 0xd9, 0x45, 0xca, 0xfe, 0xba,
+        0xbe, 0x80, 0xb0, 0xfb, 0xa2, 0xd1, 0x85, 0x88, 0xa6, 0x2f, 0x00, 0x00, 0x00, 0xc0, 0xa8,
+        0x00, 0x01, 0xff, 0xff, 0x39, 0x30, 0x00, 0x00, 0xff,
+    ];
+
+    data.repeat(5)
+}
+
+/// Needles for everything in data(), some exact and some with a tolerance
+pub fn needles() -> Vec<Needle> {
+    vec![
+        Needle::new_location_with_tolerance(-31.9525, 115.85, Distance::from_kilometres(5.0))
+            .unwrap(),
+        Needle::new_timestamp_with_tolerance("2023-12-15 00:00:00", Duration::days(30)).unwrap(),
+        Needle::new_ip_address("192.168.0.1".parse().unwrap()).unwrap(),
+        Needle::new_integer(12345).unwrap(),
+    ]
+}
@@ -1,19 +1,20 @@
-use itertools::Itertools;
-
-use crate::needle::{
-    ipaddr::variant::IPv4Variant,
-    location::variant::LocationVariant,
-    number::variants::{FloatVariant, IntegerVariant},
-    timestamp::variants::TimestampVariant,
-    variant::NeedleVariant,
-    Interpret, Matches, Needle, Recombobulate,
-};
+use std::ops::ControlFlow;
+
+use etherparse::{LinkSlice, NetSlice, SlicedPacket, TransportSlice};
 
-use super::ashes::Ashes;
+use crate::needle::Needle;
+
+use super::{
+    ashes::Ashes,
+    engine::{Engine, Registry},
+    parallel::parallel_map,
+};
 
 pub struct Hay<'a> {
     datas: Vec<HayData<'a>>,
     needles: Vec<Needle>,
+    layer: Layer,
+    registry: Registry,
 }
 
 impl<'a> Hay<'a> {
@@ -21,181 +22,175 @@ impl<'a> Hay<'a> {
         Self {
             datas,
             needles: Default::default(),
+            layer: Default::default(),
+            registry: Default::default(),
         }
     }
 
     pub fn with_needles(datas: Vec<HayData<'a>>, needles: Vec<Needle>) -> Self {
-        Self { datas, needles }
+        Self {
+            datas,
+            needles,
+            layer: Default::default(),
+            registry: Default::default(),
+        }
     }
 
-    pub fn burn(&self) -> Vec<Ashes> {
-        let mut ash_pile = Vec::<Ashes>::new();
-
-        for data in &self.datas {
-            for i in 0..data.len() {
-                let window = &data.as_slice()[i..];
-                //println!("Window: {:02x?}", &self.data.as_slice()[i..]);
-
-                // Try to interpret the bytes as all known variants
-
-                // Integer
-                if let Ok(variants) = IntegerVariant::interpret(window) {
-                    for variant in &variants {
-                        //println!("{:?}", &variant);
-
-                        if let Ok(putative) = variant.recombobulate() {
-                            //println!("{:?}", &needle);
-
-                            let hits = self
-                                .needles
-                                .iter()
-                                .filter(|target| putative.matches(target))
-                                .map(|target| {
-                                    //println!("It's a match!");
-                                    Ashes::new(
-                                        target,
-                                        putative.clone(),
-                                        NeedleVariant::Integer(variant.clone()),
-                                        i,
-                                    )
-                                })
-                                .collect_vec();
-
-                            for hit in hits {
-                                ash_pile.push(hit);
-                            }
-                        }
-                    }
-                }
-
-                // Float
-                if let Ok(variants) = FloatVariant::interpret(window) {
-                    for variant in &variants {
-                        //println!("{:?}", &variant);
-
-                        if let Ok(putative) = variant.recombobulate() {
-                            //println!("{:?}", &needle);
-
-                            let hits = self
-                                .needles
-                                .iter()
-                                .filter(|target| putative.matches(target))
-                                .map(|target| {
-                                    //println!("It's a match!");
-                                    Ashes::new(
-                                        target,
-                                        putative.clone(),
-                                        NeedleVariant::Float(variant.clone()),
-                                        i,
-                                    )
-                                })
-                                .collect_vec();
-
-                            for hit in hits {
-                                ash_pile.push(hit);
-                            }
-                        }
-                    }
-                }
-
-                // Timestamp
-                if let Ok(variants) = TimestampVariant::interpret(window) {
-                    for variant in &variants {
-                        //println!("{:?}", &variant);
-
-                        if let Ok(putative) = variant.recombobulate() {
-                            //println!("{:?}", &needle);
-
-                            let hits = self
-                                .needles
-                                .iter()
-                                .filter(|target| putative.matches(target))
-                                .map(|target| {
-                                    //println!("It's a match!");
-                                    Ashes::new(
-                                        target,
-                                        putative.clone(),
-                                        NeedleVariant::Timestamp(variant.clone()),
-                                        i,
-                                    )
-                                })
-                                .collect_vec();
-
-                            for hit in hits {
-                                ash_pile.push(hit);
-                            }
-                        }
-                    }
-                }
-
-                // Location
-                if let Ok(variants) = LocationVariant::interpret(window) {
-                    for variant in &variants {
-                        //println!("{:?}", &variant);
-
-                        if let Ok(putative) = variant.recombobulate() {
-                            //println!("{:?}", &needle);
-
-                            let hits = self
-                                .needles
-                                .iter()
-                                .filter(|target| putative.matches(target))
-                                .map(|target| {
-                                    //println!("It's a match!");
-                                    Ashes::new(
-                                        target,
-                                        putative.clone(),
-                                        NeedleVariant::Location(variant.clone()),
-                                        i,
-                                    )
-                                })
-                                .collect_vec();
-
-                            for hit in hits {
-                                ash_pile.push(hit);
-                            }
-                        }
-                    }
-                }
-
-                // IP Address
-                if let Ok(variants) = IPv4Variant::interpret(window) {
-                    for variant in &variants {
-                        //println!("{:?}", &variant);
-
-                        if let Ok(putative) = variant.recombobulate() {
-                            //println!("{:?}", &needle);
-
-                            let hits = self
-                                .needles
-                                .iter()
-                                .filter(|target| putative.matches(target))
-                                .map(|target| {
-                                    //println!("It's a match!");
-                                    Ashes::new(
-                                        target,
-                                        putative.clone(),
-                                        NeedleVariant::IpAddr(variant.clone()),
-                                        i,
-                                    )
-                                })
-                                .collect_vec();
-
-                            for hit in hits {
-                                ash_pile.push(hit);
-                            }
-                        }
+    pub fn with_layer(datas: Vec<HayData<'a>>, needles: Vec<Needle>, layer: Layer) -> Self {
+        Self {
+            datas,
+            needles,
+            layer,
+            registry: Default::default(),
+        }
+    }
+
+    /// The variant families the hay will be burned as, which can be enabled and disabled before burning
+    pub fn registry_mut(&mut self) -> &mut Registry {
+        &mut self.registry
+    }
+
+    pub fn burn(&self) -> Vec<Ashes<'_>> {
+        let engine = Engine::new(&self.needles, &self.registry);
+
+        self.datas
+            .iter()
+            .flat_map(|data| self.burn_data(data, &engine))
+            .collect()
+    }
+
+    /// Burn the hay lazily, a piece at a time, handing out Ashes in the same order burn() would
+    pub fn burn_iter(&self) -> impl Iterator<Item = Ashes<'_>> + '_ {
+        let engine = Engine::new(&self.needles, &self.registry);
+
+        self.datas
+            .iter()
+            .flat_map(move |data| self.burn_data(data, &engine))
+    }
+
+    /// Hand each of the Ashes to a visitor as they're found, stopping as soon as it breaks
+    pub fn visit<F>(&self, visitor: F) -> ControlFlow<()>
+    where
+        F: FnMut(Ashes<'_>) -> ControlFlow<()>,
+    {
+        self.burn_iter().try_for_each(visitor)
+    }
+
+    /// Burn each piece of hay concurrently, returning exactly what burn() would
+    pub fn burn_parallel(&self) -> Vec<Ashes<'_>> {
+        let engine = Engine::new(&self.needles, &self.registry);
+
+        parallel_map(&self.datas, |data| self.burn_data(data, &engine))
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    fn burn_data<'n>(&self, data: &HayData, engine: &Engine<'n>) -> Vec<Ashes<'n>> {
+        match data {
+            HayData::Capture(cap, _) => burn_layer(cap, data.frame(), self.layer, engine),
+            HayData::Raw(raw) => engine.burn(raw),
+        }
+    }
+}
+
+/// Which protocol layer of a captured packet to burn
+///
+/// If a packet doesn't have the requested layer, the closest layer above it is burned instead
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Layer {
+    Frame,
+    LinkPayload,
+    NetworkPayload,
+    TransportSegment, // Transport header and payload
+    #[default]
+    TransportPayload,
+}
+
+impl Layer {
+    /// Pick out the bytes for this layer of a sliced packet, along with the layer they actually came from
+    pub fn select<'a>(
+        self,
+        packet: &SlicedPacket<'a>,
+        frame: Option<&'a [u8]>,
+    ) -> Option<(Layer, &'a [u8])> {
+        match self {
+            Layer::Frame => frame
+                .map(|frame| (Layer::Frame, frame))
+                .or_else(|| Layer::LinkPayload.select(packet, frame)),
+            Layer::LinkPayload => match &packet.link {
+                Some(LinkSlice::Ethernet2(eth2)) => Some(eth2.payload_slice()),
+                Some(LinkSlice::LinuxSll(sll)) => Some(sll.payload_slice()),
+                Some(LinkSlice::EtherPayload(eth)) => Some(eth.payload),
+                Some(LinkSlice::LinuxSllPayload(sll)) => Some(sll.payload),
+                // Without a link layer header, the whole frame is the link payload
+                None => match frame {
+                    Some(frame) => Some(frame),
+                    // And without the frame either, the network payload is the closest layer left
+                    None if packet.net.is_some() => {
+                        return Layer::NetworkPayload.select(packet, frame)
                     }
-                }
+                    None => None,
+                },
+            }
+            .map(|data| (Layer::LinkPayload, data)),
+            Layer::NetworkPayload => match &packet.net {
+                Some(NetSlice::Ipv4(ipv4)) => Some((Layer::NetworkPayload, ipv4.payload().payload)),
+                Some(NetSlice::Ipv6(ipv6)) => Some((Layer::NetworkPayload, ipv6.payload().payload)),
+                None => Layer::LinkPayload.select(packet, frame),
+            },
+            Layer::TransportSegment => match &packet.transport {
+                Some(TransportSlice::Icmpv4(icmpv4)) => Some(icmpv4.slice()),
+                Some(TransportSlice::Icmpv6(icmpv6)) => Some(icmpv6.slice()),
+                Some(TransportSlice::Udp(udp)) => Some(udp.slice()),
+                Some(TransportSlice::Tcp(tcp)) => Some(tcp.slice()),
+                None => None,
+            }
+            .map(|data| (Layer::TransportSegment, data))
+            .or_else(|| Layer::NetworkPayload.select(packet, frame)),
+            Layer::TransportPayload => match &packet.transport {
+                Some(TransportSlice::Icmpv4(icmpv4)) => Some(icmpv4.payload()),
+                Some(TransportSlice::Icmpv6(icmpv6)) => Some(icmpv6.payload()),
+                Some(TransportSlice::Udp(udp)) => Some(udp.payload()),
+                Some(TransportSlice::Tcp(tcp)) => Some(tcp.payload()),
+                None => None,
             }
+            .map(|data| (Layer::TransportPayload, data))
+            .or_else(|| Layer::NetworkPayload.select(packet, frame)),
         }
+    }
+}
 
-        ash_pile
+/// Burn the selected layer of a sliced packet, recording the layer and where it sits in the frame
+pub(crate) fn burn_layer<'n>(
+    packet: &SlicedPacket,
+    frame: Option<&[u8]>,
+    layer: Layer,
+    engine: &Engine<'n>,
+) -> Vec<Ashes<'n>> {
+    let Some((layer, data)) = layer.select(packet, frame) else {
+        return Vec::new();
+    };
+
+    // Layers sliced out of the frame can be placed in it from their pointers, but a frame from some other buffer tells us nothing
+    let layer_offset = frame.and_then(|frame| {
+        (data.as_ptr() as usize)
+            .checked_sub(frame.as_ptr() as usize)
+            .filter(|offset| offset + data.len() <= frame.len())
+    });
+
+    let mut ashes = engine.burn(data);
+    for ash in &mut ashes {
+        ash.layer = Some(layer);
+        ash.frame_offset = layer_offset.map(|layer_offset| layer_offset as u64 + ash.offset);
     }
+
+    ashes
 }
 
 pub enum HayData<'a> {
-    Capture(etherparse::SlicedPacket<'a>),
+    /// A sliced packet, along with the frame it was sliced from if there is one
+    Capture(SlicedPacket<'a>, Option<&'a [u8]>),
     Raw(Vec<u8>),
 }
 
@@ -208,42 +203,50 @@ impl<'a> HayData<'a> {
         self.as_slice().is_empty()
     }
 
-    pub fn as_slice(&'a self) -> &'a [u8] {
+    /// The deepest payload available, which is what gets burned by default
+    pub fn as_slice(&self) -> &[u8] {
+        self.layer_slice(Layer::TransportPayload)
+    }
+
+    pub fn layer_slice(&self, layer: Layer) -> &[u8] {
         match &self {
-            HayData::Capture(cap) => {
-                if let Some(transport) = &cap.transport {
-                    match transport {
-                        etherparse::TransportSlice::Icmpv4(icmpv4) => icmpv4.payload(),
-                        etherparse::TransportSlice::Icmpv6(icmpv6) => icmpv6.payload(),
-                        etherparse::TransportSlice::Udp(udp) => udp.payload(),
-                        etherparse::TransportSlice::Tcp(tcp) => tcp.payload(),
-                    }
-                } else if let Some(net) = &cap.net {
-                    match net {
-                        etherparse::NetSlice::Ipv4(ipv4) => ipv4.payload().payload,
-                        etherparse::NetSlice::Ipv6(ipv6) => ipv6.payload().payload,
-                    }
-                } else if let Some(link) = &cap.link {
-                    match link {
-                        etherparse::LinkSlice::Ethernet2(eth2) => eth2.payload_slice(),
-                        etherparse::LinkSlice::LinuxSll(sll) => sll.payload_slice(),
-                        etherparse::LinkSlice::EtherPayload(eth) => eth.payload,
-                        etherparse::LinkSlice::LinuxSllPayload(sll) => sll.payload,
-                    }
-                } else {
-                    self.as_slice()
-                }
-            }
+            HayData::Capture(cap, _) => layer
+                .select(cap, self.frame())
+                .map(|(_, data)| data)
+                .unwrap_or_default(),
             HayData::Raw(raw) => raw.as_slice(),
         }
     }
+
+    /// The whole frame, if it was kept alongside the sliced packet or the packet has a link layer header to recover it from
+    pub fn frame(&self) -> Option<&'a [u8]> {
+        match &self {
+            HayData::Capture(_, Some(frame)) => Some(frame),
+            HayData::Capture(cap, None) => match &cap.link {
+                Some(LinkSlice::Ethernet2(eth2)) => Some(eth2.slice()),
+                Some(LinkSlice::LinuxSll(sll)) => Some(sll.slice()),
+                _ => None,
+            },
+            HayData::Raw(_) => None,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use etherparse::PacketBuilder;
     use measurements::Distance;
     use time::Duration;
 
+    use crate::needle::{
+        ipaddr::variant::IPv4Variant,
+        location::variant::LocationVariant,
+        number::variants::{FloatVariant, IntegerVariant},
+        timestamp::variants::TimestampVariant,
+        variant::NeedleVariant,
+        Matches,
+    };
+
     use super::*;
 
     #[test]
@@ -312,4 +315,75 @@ mod tests {
             NeedleVariant::IpAddr(IPv4Variant::Numeric(IntegerVariant::U32BE(_)))
         )); // The IP address variant that matched should have been built using a U32BE integer
     }
+
+    #[test]
+    fn raw_ip_frame_test() {
+        // A raw IP packet, with the IP address only in its header
+        let builder = PacketBuilder::ipv4([192, 168, 0, 1], [10, 0, 0, 2], 64).udp(12345, 53);
+        let mut packet = Vec::<u8>::new();
+        builder.write(&mut packet, &[0xff; 8]).unwrap();
+
+        let private_ip =
+            Needle::new_ip_address_with_tolerance("192.168.0.0".parse().unwrap(), 16).unwrap();
+        let needles = vec![private_ip];
+
+        // Without a link layer, the frame and link payload are the whole IP packet
+        for layer in [Layer::Frame, Layer::LinkPayload] {
+            let datas = vec![HayData::Capture(
+                SlicedPacket::from_ip(&packet).unwrap(),
+                Some(&packet),
+            )];
+            assert_eq!(datas[0].layer_slice(layer), packet.as_slice());
+
+            let hay = Hay::with_layer(datas, needles.clone(), layer);
+            let results = hay.burn();
+
+            assert!(results.len() == 1);
+            assert!(results[0].actual.matches(&needles[0]));
+            assert_eq!(results[0].layer, Some(layer));
+            assert_eq!(results[0].offset, 12);
+            assert_eq!(results[0].frame_offset, Some(12));
+        }
+
+        // But without the packet's bytes they fall back to the IP payload, which doesn't have the address in it
+        for layer in [Layer::Frame, Layer::LinkPayload] {
+            let datas = vec![HayData::Capture(
+                SlicedPacket::from_ip(&packet).unwrap(),
+                None,
+            )];
+            assert_eq!(datas[0].layer_slice(layer), &packet[20..]);
+
+            let hay = Hay::with_layer(datas, needles.clone(), layer);
+            assert!(hay.burn().is_empty());
+        }
+    }
+
+    #[test]
+    fn foreign_frame_test() {
+        // An IP address (192.168.0.1 as Numeric(U32BE)) in the UDP payload of a raw IP packet
+        let builder = PacketBuilder::ipv4([10, 0, 0, 1], [10, 0, 0, 2], 64).udp(12345, 53);
+        let mut packet = Vec::<u8>::new();
+        builder
+            .write(&mut packet, &[0xff, 0xc0, 0xa8, 0x00, 0x01, 0xff])
+            .unwrap();
+        let copy = packet.clone();
+
+        let private_ip =
+            Needle::new_ip_address_with_tolerance("192.168.0.0".parse().unwrap(), 16).unwrap();
+        let needles = vec![private_ip];
+
+        // The payload can be placed in the frame it was sliced from, but not in a copy of it
+        for (frame, frame_offset) in [(&packet, Some(29)), (&copy, None)] {
+            let datas = vec![HayData::Capture(
+                SlicedPacket::from_ip(&packet).unwrap(),
+                Some(frame),
+            )];
+            let hay = Hay::with_needles(datas, needles.clone());
+            let results = hay.burn();
+
+            assert!(results.len() == 1);
+            assert_eq!(results[0].offset, 1);
+            assert_eq!(results[0].frame_offset, frame_offset);
+        }
+    }
 }
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use aho_corasick::AhoCorasick;
+
+use crate::needle::{
+    ipaddr::variant::IPv4Variant, macaddr::variant::MACAddrVariant,
+    sockaddr::variant::SockAddrVariant, uuid::variant::UUIDVariant, variant::NeedleVariant,
+    Discombobulate, Matches, Needle,
+};
+
+use super::{ashes::Ashes, engine::Registry, pattern::PatternIndex, range::RangeIndex};
+
+/// The byte sequences to search for, and the variants of the needles each one could be
+#[derive(Default)]
+struct Sequences<'n> {
+    sequences: Vec<Vec<u8>>,
+    variants: Vec<Vec<(&'n Needle, NeedleVariant)>>,
+    lookup: HashMap<Vec<u8>, usize>,
+}
+
+impl<'n> Sequences<'n> {
+    fn insert(&mut self, needle: &'n Needle, variant: NeedleVariant) {
+        let sequence = variant.byte_sequence();
+        if sequence.is_empty() {
+            return;
+        }
+
+        // Lots of variants share a byte sequence (e.g. U64LE and I64LE), so each sequence only goes into the automaton once
+        let id = *self.lookup.entry(sequence.clone()).or_insert_with(|| {
+            self.sequences.push(sequence);
+            self.variants.push(Vec::new());
+            self.variants.len() - 1
+        });
+
+        self.variants[id].push((needle, variant));
+    }
+}
+
+/// An Aho-Corasick automaton, and the variants of the needles each of its patterns could be
+struct Automaton<'n> {
+    automaton: AhoCorasick,
+    variants: Vec<Vec<(&'n Needle, NeedleVariant)>>,
+    ascii_case_insensitive: bool,
+}
+
+/// Every byte sequence the exact needles could appear as, built once and matched against the haystack in a single pass
+///
+/// Needles with a tolerance can't be enumerated up front, so numeric ones are checked against ranges of raw values instead, and the rest are left for the interpretive scan
+pub struct NeedleIndex<'n> {
+    automata: Vec<Automaton<'n>>,
+    ranges: RangeIndex<'n>,
+    patterns: PatternIndex<'n>,
+    fallback: Vec<&'n Needle>,
+}
+
+impl<'n> NeedleIndex<'n> {
+    /// Index the needles, leaving out any variants from families the registry doesn't have enabled
+    pub fn new(needles: &'n [Needle], registry: &Registry) -> Self {
+        let mut sequences = Sequences::default();
+        // Case insensitive strings are searched for separately, so that nothing else loses its case
+        let mut folded = Sequences::default();
+        let mut ranges = RangeIndex::default();
+        let mut fallback = Vec::<&'n Needle>::new();
+
+        for needle in needles {
+            // Byte patterns have an index of their own
+            if let Needle::Pattern(_) = needle {
+                continue;
+            }
+
+            if !needle.is_exact() {
+                if !ranges.insert(needle, registry) {
+                    fallback.push(needle);
+                }
+                continue;
+            }
+
+            // MAC addresses can be the EUI-64 interface ID of any IPv6 address, which is too many to put in the automaton
+            if let Needle::MacAddr(_) = needle {
+                ranges.insert_eui64(needle, registry);
+            }
+
+            let sequences = match needle {
+                Needle::String(string) if string.case_insensitive => &mut folded,
+                _ => &mut sequences,
+            };
+
+            for variant in needle.discombobulate() {
+                if registry.is_enabled(variant.family()) {
+                    sequences.insert(needle, variant);
+                }
+            }
+        }
+
+        let mut automata = Vec::new();
+        for (sequences, ascii_case_insensitive) in [(sequences, false), (folded, true)] {
+            if sequences.sequences.is_empty() {
+                continue;
+            }
+
+            match AhoCorasick::builder()
+                .ascii_case_insensitive(ascii_case_insensitive)
+                .build(&sequences.sequences)
+            {
+                Ok(automaton) => automata.push(Automaton {
+                    automaton,
+                    variants: sequences.variants,
+                    ascii_case_insensitive,
+                }),
+                Err(_) => {
+                    // If the automaton can't be built, its needles have to be found the slow way
+                    fallback.extend(needles.iter().filter(|needle| {
+                        sequences
+                            .variants
+                            .iter()
+                            .flatten()
+                            .any(|(indexed, _)| std::ptr::eq(*indexed, *needle))
+                    }));
+                }
+            }
+        }
+
+        Self {
+            automata,
+            ranges,
+            patterns: PatternIndex::new(needles, registry),
+            fallback,
+        }
+    }
+
+    /// The most bytes any needle could take up in the haystack, which is how far a chunk of the haystack needs to overlap the next one
+    pub fn width(&self) -> usize {
+        let patterns = self
+            .automata
+            .iter()
+            .flat_map(|automaton| automaton.variants.iter().flatten())
+            // Text needs to see the character after it to know where it ends, which is up to two bytes
+            .map(|(_, variant)| variant.byte_len() + 2);
+
+        let fallback = self.fallback.iter().map(|needle| match needle {
+            // Two f64s
+            Needle::Location(_) => 16,
+            // The longest way of writing out an IPv4 address, in UTF-16
+            Needle::IpAddr(_) => IPv4Variant::MAX_TEXT_LEN,
+            // An IPv6 address
+            Needle::Ipv6Addr(_) => 16,
+            // The longest way of writing out a MAC address, in UTF-16
+            Needle::MacAddr(_) => MACAddrVariant::MAX_TEXT_LEN,
+            // The longest way of writing out an IPv6 address and port, in UTF-16
+            Needle::SockAddr(_) => SockAddrVariant::MAX_LEN,
+            Needle::String(string) => string.max_len(),
+            // The longest way of writing out a UUID, in UTF-16
+            Needle::Uuid(_) => UUIDVariant::MAX_TEXT_LEN,
+            Needle::Bytes(bytes) => bytes.len(),
+            Needle::Pattern(pattern) => pattern.max_len(),
+            Needle::Custom(custom) => custom.max_len(),
+            // Anything else is built on an integer or float, the widest of which is a 64 bit varint
+            _ => 10,
+        });
+
+        patterns
+            .chain(fallback)
+            .chain([self.ranges.width(), self.patterns.width()])
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The needles that weren't indexed, and still need every offset interpreted to find them
+    pub fn fallback(&self) -> &[&'n Needle] {
+        &self.fallback
+    }
+
+    /// Find every indexed needle in a slice of bytes, ordered by offset
+    pub fn search(&self, data: &[u8]) -> Vec<Ashes<'n>> {
+        let mut ash_pile = self.ranges.search(data);
+        ash_pile.append(&mut self.patterns.search(data));
+
+        for automaton in &self.automata {
+            for hit in automaton.automaton.find_overlapping_iter(data) {
+                for (target, variant) in &automaton.variants[hit.pattern().as_usize()] {
+                    // A case insensitive hit may not be the same bytes as the pattern, so it's the bytes that were found that get recombobulated
+                    let variant = match automaton.ascii_case_insensitive {
+                        true => variant.with_bytes(&data[hit.range()]),
+                        false => variant.clone(),
+                    };
+
+                    // Only report what the interpretive scan would have, i.e. complete variants that recombobulate to a match
+                    if !variant.is_complete(&data[..hit.start()], &data[hit.end()..]) {
+                        continue;
+                    }
+
+                    if let Ok(actual) = variant.recombobulate_for(target) {
+                        if actual.matches(target) {
+                            ash_pile.push(Ashes::new(target, actual, variant, hit.start()));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Overlapping hits come out in the order they end, rather than the order they start
+        ash_pile.sort_by_key(|ash| ash.offset);
+
+        ash_pile
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::needle::{
+        ipaddr::variant::IPv4Variant, number::variants::IntegerVariant,
+        timestamp::variants::TimestampVariant,
+    };
+
+    use crate::haystack::{engine::interpret_slice, fixtures::describe_sorted};
+
+    use super::*;
+
+    #[test]
+    fn index_test() {
+        // A timestamp (2023-12-31 23:59:58 as EpochSecs(U32LE)) followed by an IP address (192.168.0.1 as Numeric(U32BE))
+        let data: Vec<u8> = vec![
+            0xde, 0xad, 0x7e, 0x00, 0x92, 0x65, 0xbe, 0xef, 0xc0, 0xa8, 0x00, 0x01, 0xff,
+        ];
+
+        let needles = vec![
+            Needle::new_timestamp("2023-12-31 23:59:58").unwrap(),
+            Needle::new_ip_address("192.168.0.1".parse().unwrap()).unwrap(),
+            Needle::new_ip_address_with_tolerance("10.0.0.0".parse().unwrap(), 8).unwrap(),
+        ];
+
+        let registry = Registry::default();
+        let index = NeedleIndex::new(&needles, &registry);
+
+        // The needle with a tolerance is checked against a range instead
+        assert!(index.fallback().is_empty());
+
+        let results = index.search(&data);
+
+        for result in &results {
+            println!("{:02x?}", result);
+        }
+
+        assert!(results.len() == 3);
+
+        // The same four bytes are a valid timestamp whether they're read as signed or unsigned
+        for result in &results[..2] {
+            assert_eq!(result.offset, 2);
+            assert!(result.actual.matches(&needles[0]));
+        }
+        assert!(matches!(
+            results[0].variant,
+            NeedleVariant::Timestamp(TimestampVariant::EpochSecs(IntegerVariant::I32LE(_)))
+        ));
+        assert!(matches!(
+            results[1].variant,
+            NeedleVariant::Timestamp(TimestampVariant::EpochSecs(IntegerVariant::U32LE(_)))
+        ));
+
+        assert_eq!(results[2].offset, 8);
+        assert!(results[2].actual.matches(&needles[1]));
+        assert!(matches!(
+            results[2].variant,
+            NeedleVariant::IpAddr(IPv4Variant::Numeric(IntegerVariant::U32BE(_)))
+        ));
+    }
+
+    #[test]
+    fn interpretive_equivalence_test() {
+        // The index should find exactly what interpreting every offset finds
+        let data: Vec<u8> = vec![
+            0xde, 0xad, 0xbe, 0xef, 0x00, 0xa0, 0xef, 0xc4, 0x00, 0x38, 0xd9, 0x45, 0xca, 0xfe,
+            0xba, 0xbe, 0x80, 0xb0, 0xfb, 0xa2, 0xd1, 0x85, 0x88, 0xa6, 0x2f, 0x00, 0x00, 0x00,
+            0xc0, 0xa8, 0x00, 0x01, 0xff, 0xff, 0x39, 0x30, 0x00, 0x00, 0x7e, 0x00, 0x92, 0x65,
+        ];
+
+        let needles = vec![
+            Needle::new_integer(12345).unwrap(),
+            Needle::new_float(-1.0).unwrap(),
+            Needle::new_timestamp("2023-12-31 23:59:58").unwrap(),
+            Needle::new_ip_address("192.168.0.1".parse().unwrap()).unwrap(),
+        ];
+        let targets = needles.iter().collect::<Vec<_>>();
+
+        let registry = Registry::default();
+        let index = NeedleIndex::new(&needles, &registry);
+        assert!(index.fallback().is_empty());
+
+        let indexed = describe_sorted(index.search(&data));
+        let interpreted = describe_sorted(interpret_slice(&data, &targets, &registry));
+
+        assert!(!indexed.is_empty());
+        assert_eq!(indexed, interpreted);
+    }
+}
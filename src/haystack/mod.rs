@@ -1,23 +1,34 @@
-use itertools::Itertools;
-
-use crate::needle::{
-    ipaddr::variant::IPv4Variant,
-    location::variant::LocationVariant,
-    macaddr::variant::MACAddrVariant,
-    number::variants::{FloatVariant, IntegerVariant},
-    timestamp::variants::TimestampVariant,
-    variant::NeedleVariant,
-    Interpret, Matches, Needle, Recombobulate,
-};
+use std::ops::ControlFlow;
+
+use crate::needle::Needle;
 
-use self::ashes::Ashes;
+use self::{
+    ashes::Ashes,
+    engine::{Engine, Registry},
+    parallel::{burn_chunks, CHUNK_LEN},
+    visit::Embers,
+};
 
 pub mod ashes;
+pub mod capture;
+pub mod defrag;
+pub mod engine;
+#[cfg(test)]
+mod fixtures;
 pub mod hay;
+pub mod index;
+pub mod parallel;
+pub mod pattern;
+pub mod range;
+pub mod reader;
+pub mod stream;
+pub mod tcp;
+pub mod visit;
 
 pub struct Haystack {
     pub data: Vec<u8>,
     pub needles: Vec<Needle>,
+    pub registry: Registry,
 }
 
 impl Haystack {
@@ -25,230 +36,83 @@ impl Haystack {
         Self {
             data,
             needles: Default::default(),
+            registry: Default::default(),
         }
     }
 
     pub fn with_needles(data: Vec<u8>, needles: Vec<Needle>) -> Self {
-        Self { data, needles }
+        Self {
+            data,
+            needles,
+            registry: Default::default(),
+        }
     }
 
-    // pub fn from_pcap(path: &str) -> Option<Self> {
-    //     let file_path = std::path::PathBuf::from(path);
-    //     if !file_path.exists() {
-    //         return None;
-    //     }
-    // }
-
-    pub fn burn(&self) -> Vec<Ashes> {
-        let mut ash_pile = Vec::<Ashes>::new();
-
-        // TODO - should we just auto-discombobulate all needles on creation?
-        // let target_needle_variants = self
-        //     .needles
-        //     .iter()
-        //     .flat_map(|needle| needle.discombobulate())
-        //     .collect_vec();
-
-        for i in 0..self.data.len() {
-            let window = &self.data.as_slice()[i..];
-            //println!("Window: {:02x?}", &self.data.as_slice()[i..]);
-
-            // Try to interpret the bytes as all known variants
-
-            // Integer
-            if let Ok(variants) = IntegerVariant::interpret(window) {
-                for variant in &variants {
-                    //println!("{:?}", &variant);
-
-                    if let Ok(putative) = variant.recombobulate() {
-                        //println!("{:?}", &needle);
-
-                        let hits = self
-                            .needles
-                            .iter()
-                            .filter(|target| putative.matches(target))
-                            .map(|target| {
-                                //println!("It's a match!");
-                                Ashes::new(
-                                    target,
-                                    putative.clone(),
-                                    NeedleVariant::Integer(variant.clone()),
-                                    i,
-                                )
-                            })
-                            .collect_vec();
-
-                        for hit in hits {
-                            ash_pile.push(hit);
-                        }
-                    }
-                }
-            }
-
-            // Float
-            if let Ok(variants) = FloatVariant::interpret(window) {
-                for variant in &variants {
-                    //println!("{:?}", &variant);
-
-                    if let Ok(putative) = variant.recombobulate() {
-                        //println!("{:?}", &needle);
-
-                        let hits = self
-                            .needles
-                            .iter()
-                            .filter(|target| putative.matches(target))
-                            .map(|target| {
-                                //println!("It's a match!");
-                                Ashes::new(
-                                    target,
-                                    putative.clone(),
-                                    NeedleVariant::Float(variant.clone()),
-                                    i,
-                                )
-                            })
-                            .collect_vec();
-
-                        for hit in hits {
-                            ash_pile.push(hit);
-                        }
-                    }
-                }
-            }
-
-            // Timestamp
-            if let Ok(variants) = TimestampVariant::interpret(window) {
-                for variant in &variants {
-                    //println!("{:?}", &variant);
-
-                    if let Ok(putative) = variant.recombobulate() {
-                        //println!("{:?}", &needle);
-
-                        let hits = self
-                            .needles
-                            .iter()
-                            .filter(|target| putative.matches(target))
-                            .map(|target| {
-                                //println!("It's a match!");
-                                Ashes::new(
-                                    target,
-                                    putative.clone(),
-                                    NeedleVariant::Timestamp(variant.clone()),
-                                    i,
-                                )
-                            })
-                            .collect_vec();
-
-                        for hit in hits {
-                            ash_pile.push(hit);
-                        }
-                    }
-                }
-            }
-
-            // Location
-            if let Ok(variants) = LocationVariant::interpret(window) {
-                for variant in &variants {
-                    //println!("{:?}", &variant);
-
-                    if let Ok(putative) = variant.recombobulate() {
-                        //println!("{:?}", &needle);
-
-                        let hits = self
-                            .needles
-                            .iter()
-                            .filter(|target| putative.matches(target))
-                            .map(|target| {
-                                //println!("It's a match!");
-                                Ashes::new(
-                                    target,
-                                    putative.clone(),
-                                    NeedleVariant::Location(variant.clone()),
-                                    i,
-                                )
-                            })
-                            .collect_vec();
-
-                        for hit in hits {
-                            ash_pile.push(hit);
-                        }
-                    }
-                }
-            }
-
-            // IP Address
-            if let Ok(variants) = IPv4Variant::interpret(window) {
-                for variant in &variants {
-                    //println!("{:?}", &variant);
-
-                    if let Ok(putative) = variant.recombobulate() {
-                        //println!("{:?}", &needle);
-
-                        let hits = self
-                            .needles
-                            .iter()
-                            .filter(|target| putative.matches(target))
-                            .map(|target| {
-                                //println!("It's a match!");
-                                Ashes::new(
-                                    target,
-                                    putative.clone(),
-                                    NeedleVariant::IpAddr(variant.clone()),
-                                    i,
-                                )
-                            })
-                            .collect_vec();
-
-                        for hit in hits {
-                            ash_pile.push(hit);
-                        }
-                    }
-                }
-            }
-
-            // MAC Address
-            if let Ok(variants) = MACAddrVariant::interpret(window) {
-                for variant in &variants {
-                    //println!("{:?}", &variant);
-
-                    if let Ok(putative) = variant.recombobulate() {
-                        //println!("{:?}", &needle);
-
-                        let hits = self
-                            .needles
-                            .iter()
-                            .filter(|target| putative.matches(target))
-                            .map(|target| {
-                                //println!("It's a match!");
-                                Ashes::new(
-                                    target,
-                                    putative.clone(),
-                                    NeedleVariant::MacAddr(variant.clone()),
-                                    i,
-                                )
-                            })
-                            .collect_vec();
-
-                        for hit in hits {
-                            ash_pile.push(hit);
-                        }
-                    }
-                }
-            }
-        }
+    pub fn burn(&self) -> Vec<Ashes<'_>> {
+        let engine = Engine::new(&self.needles, &self.registry);
+
+        engine.burn(&self.data)
+    }
+
+    /// Burn the haystack in overlapping chunks across all available cores, returning exactly what burn() would
+    pub fn burn_parallel(&self) -> Vec<Ashes<'_>> {
+        let engine = Engine::new(&self.needles, &self.registry);
+
+        burn_chunks(&self.data, CHUNK_LEN, &engine)
+    }
 
-        ash_pile
+    /// Burn the haystack lazily, a window at a time, handing out Ashes in the same order burn() would
+    pub fn burn_iter(&self) -> Embers<'_, '_> {
+        Embers::new(&self.data, &self.needles, &self.registry)
+    }
+
+    /// Hand each of the Ashes to a visitor as they're found, stopping as soon as it breaks
+    pub fn visit<F>(&self, visitor: F) -> ControlFlow<()>
+    where
+        F: FnMut(Ashes<'_>) -> ControlFlow<()>,
+    {
+        self.burn_iter().try_for_each(visitor)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use measurements::Distance;
-    use time::Duration;
-
-    use crate::needle::number::Integer;
+    use time::{macros::datetime, Duration, UtcOffset};
+
+    use crate::haystack::fixtures::describe;
+    use crate::needle::{
+        ipaddr::{
+            text::IPv4Format,
+            variant::{IPv4Variant, IPv6Variant},
+        },
+        location::variant::LocationVariant,
+        macaddr::{text::MACFormat, variant::MACAddrVariant},
+        number::variants::{FloatVariant, IntegerVariant},
+        number::Integer,
+        sockaddr::variant::{FamilyField, SockAddrVariant},
+        string::variant::{LengthPrefix, StringEncoding},
+        text::TextEncoding,
+        timestamp::{variants::TimestampVariant, Timestamp},
+        uuid::{text::UUIDFormat, variant::UUIDVariant},
+        variant::NeedleVariant,
+        Matches,
+    };
 
     use super::*;
 
+    /// The index should find exactly what interpreting every offset does, whatever order it finds it in
+    fn assert_index_matches_scan(haystack: &Haystack, needles: &[Needle]) {
+        let indexed = Engine::new(needles, &haystack.registry).burn(&haystack.data);
+        let targets = needles.iter().collect::<Vec<_>>();
+        let interpreted = engine::interpret_slice(&haystack.data, &targets, &haystack.registry);
+
+        let (mut indexed, mut interpreted) = (describe(indexed), describe(interpreted));
+        indexed.sort();
+        interpreted.sort();
+        assert_eq!(indexed, interpreted);
+    }
+
     #[test]
     fn new_haystack() {
         let data: Vec<u8> = vec![0x00];
@@ -348,6 +212,119 @@ mod tests {
         )) // And the variant that matched should have been a DOSTime built using an unsigned 32bit little endian integer
     }
 
+    #[test]
+    fn timestamp_precision_test() {
+        // 2023-12-31 23:59:58.25 as epoch millis (I64LE), then 23:59:58.5 as an NTP timestamp
+        let data: Vec<u8> = vec![
+            0xde, 0xad, 0xbe, 0xef, 0x2a, 0xed, 0x51, 0xc2, 0x8c, 0x01, 0x00, 0x00, 0xe9, 0x3c,
+            0x7e, 0xfe, 0x80, 0x00, 0x00, 0x00,
+        ];
+
+        let needles = vec![
+            Needle::new_timestamp("2023-12-31 23:59:58.25").unwrap(),
+            Needle::new_timestamp_with_tolerance(
+                "2023-12-31 23:59:58.245",
+                Duration::milliseconds(5),
+            )
+            .unwrap(),
+            Needle::new_timestamp_with_tolerance(
+                "2023-12-31 23:59:58.245",
+                Duration::milliseconds(4),
+            )
+            .unwrap(),
+            Needle::new_timestamp_with_tolerance(
+                "2023-12-31 23:59:58.4",
+                Duration::milliseconds(100),
+            )
+            .unwrap(),
+        ];
+
+        let haystack = Haystack::with_needles(data, needles.clone());
+        let results = haystack.burn();
+
+        for result in &results {
+            println!("{:02x?}", result);
+        }
+
+        let offsets = |target: &Needle| {
+            let mut offsets = results
+                .iter()
+                .filter(|result| result.target == target)
+                .map(|result| result.offset)
+                .collect::<Vec<_>>();
+            offsets.dedup();
+            offsets
+        };
+
+        // Sub-second differences are no longer rounded away
+        assert_eq!(offsets(&needles[0]), vec![4]);
+        assert_eq!(offsets(&needles[1]), vec![4]);
+        assert_eq!(offsets(&needles[2]), Vec::<u64>::new());
+
+        // The middle of an NTP timestamp is the same time in the short format, which keeps enough of the fraction to still be within 100ms
+        assert_eq!(offsets(&needles[3]), vec![12, 14]);
+        assert!(results.iter().any(|result| result.offset == 14
+            && result.actual == Needle::new_timestamp("2023-12-31 23:59:58.5").unwrap()
+            && matches!(
+                result.variant,
+                NeedleVariant::Timestamp(TimestampVariant::NTPShort(_))
+            )));
+
+        assert!(results.iter().any(|result| result.target == &needles[0]
+            && matches!(
+                result.variant,
+                NeedleVariant::Timestamp(TimestampVariant::EpochMillis(IntegerVariant::I64LE(_)))
+            )));
+    }
+
+    #[test]
+    fn timestamp_offset_test() {
+        // 2023-12-31 23:59:58 as epoch seconds (U32LE), first as local time ten hours ahead, then in UTC
+        let data: Vec<u8> = vec![
+            0xde, 0xad, 0xbe, 0xef, 0x1e, 0x8d, 0x92, 0x65, 0xca, 0xfe, 0xba, 0xbe, 0x7e, 0x00,
+            0x92, 0x65,
+        ];
+
+        let needles = vec![
+            Needle::new_timestamp_with_offset(datetime!(2024-01-01 09:59:58 +10)).unwrap(),
+            Needle::new_timestamp("2023-12-31 23:59:58").unwrap(),
+            Needle::Timestamp(Timestamp {
+                tolerance: Some(Duration::minutes(1)),
+                ..Timestamp::with_offsets(
+                    datetime!(2023-12-31 23:59:30),
+                    &Timestamp::whole_hour_offsets(),
+                )
+            }),
+        ];
+
+        let haystack = Haystack::with_needles(data, needles.clone());
+        let results = haystack.burn();
+
+        for result in &results {
+            println!("{:02x?}", result);
+        }
+
+        let offsets = |target: &Needle| {
+            let mut offsets = results
+                .iter()
+                .filter(|result| result.target == target)
+                .map(|result| (result.offset, result.utc_offset()))
+                .collect::<Vec<_>>();
+            offsets.dedup();
+            offsets
+        };
+
+        let utc = Some(UtcOffset::UTC);
+        let aest = UtcOffset::from_hms(10, 0, 0).ok();
+
+        // The local time is only found by needles that allow for its offset, which they report
+        assert_eq!(offsets(&needles[0]), vec![(4, aest), (12, utc)]);
+        assert_eq!(offsets(&needles[1]), vec![(12, utc)]);
+        assert_eq!(offsets(&needles[2]), vec![(4, aest), (12, utc)]);
+
+        assert!(Needle::new_timestamp_with_offsets("2023-12-31 23:59:58", &[]).is_err());
+    }
+
     #[test]
     fn location_needles_test() {
         // Some random bytes with an set of coordinates in the middle: -31.95, 115.85 DecimalMinutesLatLon(F32LE)
@@ -390,7 +367,7 @@ mod tests {
         // 1) a set of coordinates in the middle (-31.95, 115.85 as DecimalMinutesLatLon(F32LE))
         // 2) a timestamp (2023-12-31 23:59:58 as EpochNanos(I64Varint))
         // 3) an IP address (192.168.0.1 as Numeric(U32BE))
-        // 4) a MAC address (14:7D:DA:AB:CD:EF as Numeric(U48LE))
+        // 4) a MAC address (14:7D:DA:AB:CD:EF reversed)
         let data: Vec<u8> = vec![
             0xde, 0xad, 0xbe, 0xef, 0x00, 0xa0, 0xef, 0xc4, 0x00, 0x38, 0xd9, 0x45, 0xca, 0xfe,
             0xba, 0xbe, 0x80, 0xb0, 0xfb, 0xa2, 0xd1, 0x85, 0x88, 0xa6, 0x2f, 0x00, 0x00, 0x00,
@@ -457,4 +434,449 @@ mod tests {
             NeedleVariant::IpAddr(IPv4Variant::Numeric(IntegerVariant::U32BE(_)))
         )); // The IP address variant that matched should have been built using a U32BE integer
     }
+
+    #[test]
+    fn ipv6_test() {
+        // Some random bytes with:
+        // 1) an IPv4-mapped address (::ffff:192.168.0.1 as BE)
+        // 2) an IPv6 address (2001:db8::8a2e:370:7334 as WordLE)
+        // 3) a link local address with an EUI-64 interface ID (fe80::167d:daff:feab:cdef as BE)
+        let data: Vec<u8> = vec![
+            0xde, 0xad, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff,
+            0xc0, 0xa8, 0x00, 0x01, 0xbe, 0xef, 0x01, 0x20, 0xb8, 0x0d, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x2e, 0x8a, 0x70, 0x03, 0x34, 0x73, 0xca, 0xfe, 0xfe, 0x80, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x16, 0x7d, 0xda, 0xff, 0xfe, 0xab, 0xcd, 0xef, 0xff,
+        ];
+
+        let needles = vec![
+            Needle::new_ip_address("192.168.0.1".parse().unwrap()).unwrap(),
+            Needle::new_ip_address_with_tolerance("192.168.0.0".parse().unwrap(), 16).unwrap(),
+            Needle::new_ipv6_address_with_tolerance("2001:db8::".parse().unwrap(), 32).unwrap(),
+            Needle::new_mac_address("14:7D:DA:AB:CD:EF".parse().unwrap()).unwrap(),
+        ];
+
+        let haystack = Haystack::with_needles(data, needles.clone());
+        let results = haystack.burn();
+
+        for result in &results {
+            println!("{:02x?}", result);
+        }
+
+        let found = |target: &Needle| {
+            results
+                .iter()
+                .filter(|result| result.target == target)
+                .map(|result| (result.offset, result.variant.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        // Both IPv4 needles should find the mapped address, as well as the raw one at the end of it
+        for needle in &needles[..2] {
+            let hits = found(needle);
+            assert!(hits.iter().any(|(offset, variant)| *offset == 2
+                && matches!(variant, NeedleVariant::Ipv6Addr(IPv6Variant::BE(_)))));
+            assert!(hits
+                .iter()
+                .any(|(offset, variant)| *offset == 14
+                    && matches!(variant, NeedleVariant::IpAddr(_))));
+        }
+
+        assert!(found(&needles[2])
+            .iter()
+            .any(|(offset, variant)| *offset == 20
+                && matches!(variant, NeedleVariant::Ipv6Addr(IPv6Variant::WordLE(_)))));
+        assert!(found(&needles[3])
+            .iter()
+            .any(|(offset, variant)| *offset == 38
+                && matches!(variant, NeedleVariant::Ipv6Addr(IPv6Variant::BE(_)))));
+
+        assert_index_matches_scan(&haystack, &needles);
+    }
+
+    #[test]
+    fn ipv4_text_test() {
+        // 192.168.0.1 written out as text in a few different ways, followed by addresses and hex it's only the start or end of
+        let mut data: Vec<u8> = b"host=192.168.0.1;".to_vec();
+        data.extend(TextEncoding::UTF16LE.encode("192.168.000.001"));
+        data.extend(b" C0A80001 1.0.168.192.in-addr.arpa 192.168.0.123");
+        data.extend(b" 2192.168.0.1 10.192.168.0.1 ABC0A80001");
+
+        let needles = vec![
+            Needle::new_ip_address("192.168.0.1".parse().unwrap()).unwrap(),
+            Needle::new_ip_address_with_tolerance("192.168.0.0".parse().unwrap(), 16).unwrap(),
+        ];
+
+        let haystack = Haystack::with_needles(data, needles.clone());
+        let results = haystack.burn();
+
+        for result in &results {
+            println!("{:?}", result);
+        }
+
+        let found = |target: &Needle| {
+            results
+                .iter()
+                .filter(|result| result.target == target)
+                .filter_map(|result| match &result.variant {
+                    NeedleVariant::IpAddr(IPv4Variant::Text(format, encoding, _)) => {
+                        Some((result.offset, *format, *encoding))
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let expected = vec![
+            (5, IPv4Format::DottedQuad, TextEncoding::Ascii),
+            (17, IPv4Format::ZeroPadded, TextEncoding::UTF16LE),
+            (48, IPv4Format::Hex, TextEncoding::Ascii),
+            (57, IPv4Format::ReverseDNS, TextEncoding::Ascii),
+        ];
+        assert_eq!(found(&needles[0]), expected);
+
+        // The ranged needle also finds the address at the end
+        let mut expected = expected;
+        expected.push((82, IPv4Format::DottedQuad, TextEncoding::Ascii));
+        assert_eq!(found(&needles[1]), expected);
+
+        // Text at the start of a window still sees what comes before it
+        let windowed = Embers::with_window(&haystack.data, &needles, &haystack.registry, 1);
+        assert_eq!(windowed.count(), results.len());
+
+        assert_index_matches_scan(&haystack, &needles);
+    }
+
+    #[test]
+    fn sockaddr_test() {
+        // Some random bytes with:
+        // 1) a sockaddr_in from x86 Linux (192.168.0.1:443)
+        // 2) a sockaddr_in6 from macOS ([2001:db8::1]:8080)
+        // 3) a packed address and port (10.0.0.5:8080)
+        // 4) some text, including an address whose port only starts with 443, and addresses that only end with 10.0.0.1:80 and 10.0.0.7:8081
+        let mut data: Vec<u8> = vec![0xde, 0xad];
+        data.extend([0x02, 0x00, 0x01, 0xbb, 0xc0, 0xa8, 0x00, 0x01]);
+        data.extend([0x00; 8]);
+        data.extend([0x1c, 0x1e, 0x1f, 0x90, 0x00, 0x00, 0x00, 0x00]);
+        data.extend(
+            "2001:db8::1"
+                .parse::<std::net::Ipv6Addr>()
+                .unwrap()
+                .octets(),
+        );
+        data.extend([0x00; 4]);
+        data.extend([0x0a, 0x00, 0x00, 0x05, 0x1f, 0x90]);
+        data.extend(b" 10.0.0.7:8081 192.168.0.1:4433 110.0.0.1:80 110.0.0.7:8081");
+
+        let needles = vec![
+            Needle::new_socket_address("192.168.0.1:443".parse().unwrap()).unwrap(),
+            Needle::new_socket_address("[2001:db8::1]:8080".parse().unwrap()).unwrap(),
+            Needle::new_socket_address_with_tolerance(
+                "10.0.0.0:0".parse().unwrap(),
+                8,
+                8000..=8999,
+            )
+            .unwrap(),
+            Needle::new_socket_address("10.0.0.1:80".parse().unwrap()).unwrap(),
+        ];
+
+        let haystack = Haystack::with_needles(data, needles.clone());
+        let results = haystack.burn();
+
+        for result in &results {
+            println!("{:02x?}", result);
+        }
+
+        let found = |target: &Needle| {
+            results
+                .iter()
+                .filter(|result| result.target == target)
+                .map(|result| (result.offset, result.variant.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let hits = found(&needles[0]);
+        assert_eq!(hits.len(), 1);
+        assert!(matches!(
+            hits[0],
+            (
+                2,
+                NeedleVariant::SockAddr(SockAddrVariant::In(FamilyField::LE, _))
+            )
+        ));
+
+        let hits = found(&needles[1]);
+        assert_eq!(hits.len(), 1);
+        assert!(matches!(
+            hits[0],
+            (
+                18,
+                NeedleVariant::SockAddr(SockAddrVariant::In6(FamilyField::BSD, _))
+            )
+        ));
+
+        let hits = found(&needles[2]);
+        assert_eq!(hits.len(), 2);
+        assert!(matches!(
+            hits[0],
+            (46, NeedleVariant::SockAddr(SockAddrVariant::Packed(_)))
+        ));
+        assert!(matches!(
+            hits[1],
+            (
+                53,
+                NeedleVariant::SockAddr(SockAddrVariant::Text(TextEncoding::Ascii, _))
+            )
+        ));
+
+        // Neither is found inside an address with a leading digit
+        assert!(found(&needles[3]).is_empty());
+
+        assert_index_matches_scan(&haystack, &needles);
+    }
+
+    #[test]
+    fn macaddr_test() {
+        // E0:8F:4C:11:22:33 reversed, then written out as text in a few different ways, followed by hex and addresses it's only the start or end of
+        let mut data: Vec<u8> = vec![0xde, 0xad, 0x33, 0x22, 0x11, 0x4c, 0x8f, 0xe0];
+        data.extend(b" mac=e0:8f:4c:11:22:33 ");
+        data.extend(TextEncoding::UTF16LE.encode("E0-8F-4C-11-22-33"));
+        data.extend(b" e08f.4c11.2233 e08f4c112233ab 5fe08f4c112233 00:e0:8f:4c:11:22:33");
+
+        let needles = vec![Needle::new_mac_address("E0:8F:4C:11:22:33".parse().unwrap()).unwrap()];
+
+        let haystack = Haystack::with_needles(data, needles.clone());
+        let results = haystack.burn();
+
+        for result in &results {
+            println!("{:02x?}", result);
+        }
+
+        let found = results
+            .iter()
+            .map(|result| match &result.variant {
+                NeedleVariant::MacAddr(MACAddrVariant::Text(format, encoding, _)) => {
+                    (result.offset, Some((*format, *encoding)))
+                }
+                _ => (result.offset, None),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            found,
+            vec![
+                (2, None),
+                (13, Some((MACFormat::Colon, TextEncoding::Ascii))),
+                (31, Some((MACFormat::Hyphen, TextEncoding::UTF16LE))),
+                (66, Some((MACFormat::Cisco, TextEncoding::Ascii))),
+            ]
+        );
+        assert!(matches!(
+            results[0].variant,
+            NeedleVariant::MacAddr(MACAddrVariant::LE(_))
+        ));
+        assert_eq!(
+            results[0].vendor().map(|vendor| vendor.name),
+            Some("Intel Corp".to_string())
+        );
+
+        assert_index_matches_scan(&haystack, &needles);
+    }
+
+    #[test]
+    fn string_test() {
+        // Some random bytes with:
+        // 1) a .NET BinaryWriter string ("HELLO world"), whose length prefix is the same as a Pascal string's
+        // 2) a null terminated UTF-16BE string ("Grüße")
+        let mut data: Vec<u8> = vec![0xde, 0xad, 0x0b];
+        data.extend(b"HELLO world\xff");
+        data.extend(StringEncoding::UTF16BE.encode("Grüße"));
+        data.extend([0x00, 0x00, 0xff]);
+
+        let needles = vec![
+            Needle::new_string_with_options("hello world", true, false).unwrap(),
+            Needle::new_string_with_options("Grüße", false, true).unwrap(),
+        ];
+
+        let haystack = Haystack::with_needles(data, needles.clone());
+        let results = haystack.burn();
+
+        for result in &results {
+            println!("{:02x?}", result);
+        }
+
+        let found = results
+            .iter()
+            .map(|result| {
+                (
+                    result.offset,
+                    result.string_encoding().unwrap(),
+                    result.length_prefix(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            found,
+            vec![
+                (2, StringEncoding::Ascii, Some(LengthPrefix::U8)),
+                (2, StringEncoding::Ascii, Some(LengthPrefix::Varint)),
+                (3, StringEncoding::Ascii, None),
+                (15, StringEncoding::UTF16BE, None),
+            ]
+        );
+        assert!(results[..3]
+            .iter()
+            .all(|result| result.target == &needles[0]
+                && result.actual == Needle::new_string("HELLO world").unwrap()));
+
+        // The strings are delimited by bytes that aren't text, so interpreting every offset reads the same ones the index finds
+        assert_index_matches_scan(&haystack, &needles);
+    }
+
+    #[test]
+    fn case_insensitive_string_test() {
+        // Grüße in mixed case, with its ß uppercased to SS, and in upper case in UTF-16LE, each delimited by bytes that aren't text
+        let mut data: Vec<u8> = vec![0xff];
+        data.extend("GrÜße".as_bytes());
+        data.push(0xff);
+        data.extend("GRÜSSE".as_bytes());
+        data.push(0xff);
+        data.extend(StringEncoding::UTF16LE.encode("GRÜßE"));
+        data.push(0xff);
+
+        let needles = vec![Needle::new_string_with_options("grüße", true, false).unwrap()];
+
+        let haystack = Haystack::with_needles(data, needles.clone());
+        let results = haystack.burn();
+
+        for result in &results {
+            println!("{:02x?}", result);
+        }
+
+        // ß has no single character upper case form, so SS isn't a match
+        let found = results
+            .iter()
+            .map(|result| (result.offset, result.string_encoding().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![(1, StringEncoding::UTF8), (17, StringEncoding::UTF16LE)]
+        );
+
+        assert_index_matches_scan(&haystack, &needles);
+    }
+
+    #[test]
+    fn pattern_test() {
+        // An MZ header with its e_lfanew pointing a few bytes on to a PE header, then some bytes that only start the same way
+        let mut data: Vec<u8> = vec![0xde, 0xad, 0x4d, 0x5a, 0x90, 0x00, 0x06, 0x00];
+        data.extend([0xff, 0xff, 0x50, 0x45, 0x00, 0x00]);
+        data.extend([0x4d, 0x5a, 0x90, 0x00, 0xcc]);
+
+        let needles = vec![
+            Needle::new_pattern("4D 5A ?? 00 [2-6] (50 45 | 4E 45) 00 00").unwrap(),
+            Needle::new_bytes(&[0xff, 0xff]).unwrap(),
+        ];
+
+        let haystack = Haystack::with_needles(data, needles.clone());
+        let results = haystack.burn();
+
+        for result in &results {
+            println!("{:02x?}", result);
+        }
+
+        let found = results
+            .iter()
+            .map(|result| (result.target == &needles[0], result.span()))
+            .collect::<Vec<_>>();
+        assert_eq!(found, vec![(true, 2..14), (false, 8..10)]);
+        assert_eq!(
+            results[0].actual,
+            Needle::Bytes(haystack.data[2..14].to_vec())
+        );
+
+        // Chunks overlap by the longest a pattern could match, so burning in parallel finds the same
+        assert_eq!(describe(haystack.burn_parallel()), describe(results));
+    }
+
+    #[test]
+    fn uuid_test() {
+        // The DNS namespace UUID as a GUID, then braced in UTF-16LE, then without dashes but followed by more hex
+        let dns: ::uuid::Uuid = "6ba7b810-9dad-11d1-80b4-00c04fd430c8".parse().unwrap();
+        let mut data: Vec<u8> = vec![0xde, 0xad];
+        data.extend(dns.to_bytes_le());
+        data.extend(b" ");
+        data.extend(TextEncoding::UTF16LE.encode(&dns.braced().to_string().to_uppercase()));
+        data.extend(b" 6ba7b8109dad11d180b400c04fd430c8ff");
+
+        let needles = vec![
+            Needle::new_uuid(dns).unwrap(),
+            Needle::any_uuid(Some(1), Some(::uuid::Variant::RFC4122)).unwrap(),
+        ];
+
+        let haystack = Haystack::with_needles(data, needles.clone());
+        let results = haystack.burn();
+
+        for result in &results {
+            println!("{:02x?}", result);
+        }
+
+        let found = |target: &Needle| {
+            results
+                .iter()
+                .filter(|result| result.target == target)
+                .map(|result| (result.offset, result.variant.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        // The braces have a hyphenated UUID inside them too
+        let hits = found(&needles[0]);
+        assert_eq!(hits.len(), 3);
+        assert!(matches!(
+            hits[0],
+            (2, NeedleVariant::Uuid(UUIDVariant::Mixed(_)))
+        ));
+        assert!(matches!(
+            hits[1],
+            (
+                19,
+                NeedleVariant::Uuid(UUIDVariant::Text(
+                    UUIDFormat::Braced,
+                    TextEncoding::UTF16LE,
+                    _
+                ))
+            )
+        ));
+        assert!(matches!(
+            hits[2],
+            (
+                21,
+                NeedleVariant::Uuid(UUIDVariant::Text(
+                    UUIDFormat::Hyphenated,
+                    TextEncoding::UTF16LE,
+                    _
+                ))
+            )
+        ));
+
+        // Any v1 UUID finds the same ones, which have a timestamp and node to recombobulate
+        let v1 = results
+            .iter()
+            .filter(|result| result.target == &needles[1])
+            .filter(|result| result.offset == 2 || result.offset == 19)
+            .collect::<Vec<_>>();
+        assert_eq!(v1.len(), 2);
+
+        let Needle::Uuid(uuid) = &v1[0].actual else {
+            panic!("{:?} isn't a UUID", v1[0].actual);
+        };
+        assert_eq!(uuid.timestamp().unwrap().value.year(), 1998);
+        assert_eq!(
+            uuid.node().and_then(|node| node.value),
+            Some("00:C0:4F:D4:30:C8".parse().unwrap())
+        );
+        assert!(v1[0].vendor().is_some());
+
+        assert_index_matches_scan(&haystack, &needles[..1]);
+    }
 }
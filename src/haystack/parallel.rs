@@ -0,0 +1,146 @@
+use std::{
+    num::NonZeroUsize,
+    panic,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use super::{ashes::Ashes, engine::Engine};
+
+/// How many bytes of a single buffer each thread burns at a time
+pub const CHUNK_LEN: usize = 1024 * 1024;
+
+/// Run a function over every item on all available cores, returning the results in the same order as the items
+pub(crate) fn parallel_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let threads = thread::available_parallelism()
+        .map(NonZeroUsize::get)
+        .unwrap_or(1)
+        .min(items.len());
+
+    if threads <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    // Items can take wildly different amounts of time to burn, so threads take the next one as soon as they're free
+    let next = AtomicUsize::new(0);
+
+    let mut results = thread::scope(|scope| {
+        let workers = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::<(usize, R)>::new();
+
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(i) else {
+                            break;
+                        };
+
+                        results.push((i, f(item)));
+                    }
+
+                    results
+                })
+            })
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|err| panic::resume_unwind(err))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Burn a single buffer in chunks, spread across all available cores
+///
+/// Each chunk is burned as a window, extended by the engine's width so anything starting inside it can be read in full and by its lead so text can see what precedes it, so the result is identical to burning the whole buffer in one go
+pub(crate) fn burn_chunks<'n>(
+    data: &[u8],
+    chunk_len: usize,
+    engine: &Engine<'n>,
+) -> Vec<Ashes<'n>> {
+    let starts = (0..data.len())
+        .step_by(chunk_len.max(1))
+        .collect::<Vec<_>>();
+
+    parallel_map(&starts, |&start| {
+        let end = (start + chunk_len).min(data.len());
+        let lead = engine.lead().min(start);
+        let overlap = (end + engine.width()).min(data.len());
+
+        engine.burn_window(
+            &data[start - lead..overlap],
+            lead,
+            end - start,
+            start as u64,
+        )
+    })
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::haystack::{
+        engine::{Engine, Registry},
+        fixtures::{data, describe, needles},
+        hay::{Hay, HayData},
+        Haystack,
+    };
+
+    use super::*;
+
+    #[test]
+    fn chunks_test() {
+        let data = data();
+        let needles = needles();
+        let registry = Registry::default();
+        let engine = Engine::new(&needles, &registry);
+
+        let serial = describe(engine.burn(&data));
+        assert!(!serial.is_empty());
+
+        // However small the chunks, the result should be exactly the same as burning it in one go
+        for chunk_len in [1, 3, 7, 16, 64, 1024] {
+            let parallel = burn_chunks(&data, chunk_len, &engine);
+
+            assert_eq!(serial, describe(parallel));
+        }
+    }
+
+    #[test]
+    fn haystack_test() {
+        let haystack = Haystack::with_needles(data(), needles());
+
+        assert_eq!(
+            describe(haystack.burn()),
+            describe(haystack.burn_parallel())
+        );
+    }
+
+    #[test]
+    fn hay_test() {
+        let datas = data()
+            .chunks(13)
+            .map(|chunk| HayData::Raw(chunk.to_vec()))
+            .collect();
+        let hay = Hay::with_needles(datas, needles());
+
+        let serial = describe(hay.burn());
+        assert!(!serial.is_empty());
+        assert_eq!(serial, describe(hay.burn_parallel()));
+    }
+}
@@ -0,0 +1,173 @@
+use aho_corasick::AhoCorasick;
+
+use crate::needle::{
+    pattern::BytePattern,
+    variant::{family, NeedleVariant},
+    Matches, Needle, Recombobulate,
+};
+
+use super::{ashes::Ashes, engine::Registry};
+
+/// A byte pattern, and where its atom is from the start of a match
+struct Anchored<'n> {
+    target: &'n Needle,
+    pattern: &'n BytePattern,
+    offset: usize,
+}
+
+/// The byte pattern needles, which are matched directly against the haystack rather than being discombobulated
+///
+/// Each pattern's atom is searched for first, so the whole pattern only needs trying where one's found. Patterns without an atom have to be tried at every offset
+#[derive(Default)]
+pub struct PatternIndex<'n> {
+    automaton: Option<AhoCorasick>,
+    anchored: Vec<Anchored<'n>>,
+    unanchored: Vec<(&'n Needle, &'n BytePattern)>,
+}
+
+impl<'n> PatternIndex<'n> {
+    /// Index the pattern needles, unless raw bytes are disabled
+    pub fn new(needles: &'n [Needle], registry: &Registry) -> Self {
+        let mut index = Self::default();
+        if !registry.is_enabled(family::BYTES) {
+            return index;
+        }
+
+        let mut atoms = Vec::<Vec<u8>>::new();
+
+        for needle in needles {
+            let Needle::Pattern(pattern) = needle else {
+                continue;
+            };
+
+            match pattern.atom() {
+                Some((offset, atom)) => {
+                    atoms.push(atom);
+                    index.anchored.push(Anchored {
+                        target: needle,
+                        pattern,
+                        offset,
+                    });
+                }
+                None => index.unanchored.push((needle, pattern)),
+            }
+        }
+
+        if !atoms.is_empty() {
+            match AhoCorasick::new(&atoms) {
+                Ok(automaton) => index.automaton = Some(automaton),
+                Err(_) => {
+                    // If the automaton can't be built, every pattern has to be tried everywhere
+                    let anchored = std::mem::take(&mut index.anchored);
+                    index.unanchored.extend(
+                        anchored
+                            .into_iter()
+                            .map(|anchored| (anchored.target, anchored.pattern)),
+                    );
+                }
+            }
+        }
+
+        index
+    }
+
+    /// The most bytes any pattern could match
+    pub fn width(&self) -> usize {
+        self.anchored
+            .iter()
+            .map(|anchored| anchored.pattern)
+            .chain(self.unanchored.iter().map(|(_, pattern)| *pattern))
+            .map(BytePattern::max_len)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Find every pattern in a slice of bytes, reporting the bytes each one matched
+    pub fn search(&self, data: &[u8]) -> Vec<Ashes<'n>> {
+        let mut ash_pile = Vec::new();
+
+        if let Some(automaton) = &self.automaton {
+            for hit in automaton.find_overlapping_iter(data) {
+                let anchored = &self.anchored[hit.pattern().as_usize()];
+                if let Some(start) = hit.start().checked_sub(anchored.offset) {
+                    burn(
+                        &mut ash_pile,
+                        anchored.target,
+                        anchored.pattern,
+                        data,
+                        start,
+                    );
+                }
+            }
+        }
+
+        for start in 0..data.len() {
+            for (target, pattern) in &self.unanchored {
+                burn(&mut ash_pile, target, pattern, data, start);
+            }
+        }
+
+        ash_pile
+    }
+}
+
+/// Report a pattern if it matches from the start of the data
+fn burn<'n>(
+    ash_pile: &mut Vec<Ashes<'n>>,
+    target: &'n Needle,
+    pattern: &BytePattern,
+    data: &[u8],
+    start: usize,
+) {
+    let Some(len) = pattern.match_len(&data[start..]) else {
+        return;
+    };
+
+    let variant = NeedleVariant::Bytes(data[start..start + len].to_vec());
+    if let Ok(actual) = variant.recombobulate() {
+        if actual.matches(target) {
+            ash_pile.push(Ashes::new(target, actual, variant, start));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern_index_test() {
+        let needles = vec![
+            Needle::new_pattern("4D 5A [1-2] ?0").unwrap(),
+            // No exact bytes at all
+            Needle::new_pattern("?F ~FF").unwrap(),
+        ];
+
+        let data = [0x4d, 0x5a, 0x4d, 0x5a, 0x10, 0x20, 0x3f, 0x00, 0x4d];
+
+        let registry = Registry::default();
+        let index = PatternIndex::new(&needles, &registry);
+        assert_eq!(index.width(), 5);
+
+        let mut found = index
+            .search(&data)
+            .into_iter()
+            .map(|ash| {
+                (
+                    ash.offset,
+                    ash.variant.byte_len(),
+                    ash.target == &needles[0],
+                )
+            })
+            .collect::<Vec<_>>();
+        found.sort();
+
+        assert_eq!(found, vec![(0, 5, true), (2, 4, true), (6, 2, false)]);
+
+        let mut registry = Registry::default();
+        registry.disable(family::BYTES);
+        assert!(PatternIndex::new(&needles, &registry)
+            .search(&data)
+            .is_empty());
+    }
+}
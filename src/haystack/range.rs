@@ -0,0 +1,712 @@
+use std::ops::RangeInclusive;
+
+use time::{Duration, PrimitiveDateTime};
+
+use crate::needle::{
+    ipaddr::{
+        variant::{IPv4Variant, IPv6Variant},
+        IPv6,
+    },
+    number::{
+        encoding::{FloatEncoding, IntegerEncoding},
+        variants::{FloatVariant, IntegerVariant},
+    },
+    timestamp::{epoch::Epoch, variants::TimestampVariant, Timestamp, SECS_FROM_1601_TO_EPOCH},
+    variant::{family, NeedleVariant},
+    Interpret, Matches, Needle, Recombobulate,
+};
+
+use super::{ashes::Ashes, engine::Registry};
+
+/// What a raw integer in the haystack would represent, and so how to wrap it back up into a NeedleVariant
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum IntegerKind {
+    Integer,
+    EpochSecs,
+    EpochMillis,
+    EpochMicros,
+    EpochNanos,
+    DOSTime,
+    FileTime,
+    WebKit,
+    Offset(Epoch),
+    IPv4,
+}
+
+impl IntegerKind {
+    /// The encodings the interpretive scan would try for this kind of value
+    fn encodings(self) -> &'static [IntegerEncoding] {
+        match self {
+            IntegerKind::DOSTime | IntegerKind::IPv4 => &[
+                IntegerEncoding::U32LE,
+                IntegerEncoding::U32BE,
+                IntegerEncoding::U32Varint,
+            ],
+            IntegerKind::FileTime | IntegerKind::WebKit => &[
+                IntegerEncoding::U64LE,
+                IntegerEncoding::U64BE,
+                IntegerEncoding::U64Varint,
+                IntegerEncoding::I64LE,
+                IntegerEncoding::I64BE,
+                IntegerEncoding::I64Varint,
+            ],
+            IntegerKind::Offset(epoch) => epoch.integer_encodings(),
+            _ => &IntegerEncoding::ALL,
+        }
+    }
+
+    fn family(self) -> &'static str {
+        match self {
+            IntegerKind::Integer => family::INTEGER,
+            IntegerKind::IPv4 => family::IPADDR,
+            _ => family::TIMESTAMP,
+        }
+    }
+
+    fn wrap(self, variant: IntegerVariant) -> NeedleVariant {
+        match self {
+            IntegerKind::Integer => NeedleVariant::Integer(variant),
+            IntegerKind::EpochSecs => {
+                NeedleVariant::Timestamp(TimestampVariant::EpochSecs(variant))
+            }
+            IntegerKind::EpochMillis => {
+                NeedleVariant::Timestamp(TimestampVariant::EpochMillis(variant))
+            }
+            IntegerKind::EpochMicros => {
+                NeedleVariant::Timestamp(TimestampVariant::EpochMicros(variant))
+            }
+            IntegerKind::EpochNanos => {
+                NeedleVariant::Timestamp(TimestampVariant::EpochNanos(variant))
+            }
+            IntegerKind::DOSTime => NeedleVariant::Timestamp(TimestampVariant::DOSTime(variant)),
+            IntegerKind::FileTime => NeedleVariant::Timestamp(TimestampVariant::FileTime(variant)),
+            IntegerKind::WebKit => NeedleVariant::Timestamp(TimestampVariant::WebKit(variant)),
+            IntegerKind::Offset(epoch) => {
+                NeedleVariant::Timestamp(TimestampVariant::Offset(epoch, variant))
+            }
+            IntegerKind::IPv4 => NeedleVariant::IpAddr(IPv4Variant::Numeric(variant)),
+        }
+    }
+}
+
+struct IntegerRange<'n> {
+    target: &'n Needle,
+    kind: IntegerKind,
+    values: RangeInclusive<i64>,
+}
+
+/// What a raw float in the haystack would represent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FloatKind {
+    Float,
+    Offset(Epoch),
+}
+
+impl FloatKind {
+    fn encodings(self) -> &'static [FloatEncoding] {
+        match self {
+            FloatKind::Float => &FloatEncoding::ALL,
+            FloatKind::Offset(epoch) => epoch.float_encodings(),
+        }
+    }
+
+    fn family(self) -> &'static str {
+        match self {
+            FloatKind::Float => family::FLOAT,
+            FloatKind::Offset(_) => family::TIMESTAMP,
+        }
+    }
+
+    fn wrap(self, variant: FloatVariant) -> NeedleVariant {
+        match self {
+            FloatKind::Float => NeedleVariant::Float(variant),
+            FloatKind::Offset(epoch) => {
+                NeedleVariant::Timestamp(TimestampVariant::OffsetFloat(epoch, variant))
+            }
+        }
+    }
+}
+
+struct FloatRange<'n> {
+    target: &'n Needle,
+    kind: FloatKind,
+    values: RangeInclusive<f64>,
+}
+
+/// Timestamps laid out as more than one value, which are easier to compare once they've been read back
+struct TimestampRange<'n> {
+    target: &'n Needle,
+    values: RangeInclusive<PrimitiveDateTime>,
+}
+
+/// IPv4 addresses, which aren't only stored as integers
+struct AddressRange<'n> {
+    target: &'n Needle,
+    values: RangeInclusive<u32>,
+}
+
+/// The raw values that numeric needles with a tolerance could take, for each encoding they could appear as
+///
+/// The ranges are allowed to be a little generous, as anything that falls within one is recombobulated and checked properly before it's reported
+#[derive(Default)]
+pub struct RangeIndex<'n> {
+    integers: Vec<(IntegerEncoding, Vec<IntegerRange<'n>>)>,
+    floats: Vec<FloatRange<'n>>,
+    embedded: Vec<AddressRange<'n>>,
+    text: Vec<AddressRange<'n>>,
+    eui64: Vec<&'n Needle>,
+    structured: Vec<TimestampRange<'n>>,
+}
+
+impl<'n> RangeIndex<'n> {
+    /// Add a needle to the index, returning false if its tolerance can't be expressed as ranges of raw values
+    ///
+    /// Needles from families the registry doesn't have enabled are accepted, but never found
+    pub fn insert(&mut self, needle: &'n Needle, registry: &Registry) -> bool {
+        match needle {
+            Needle::Integer(integer) => {
+                let Some(tolerance) = integer.tolerance else {
+                    return false;
+                };
+                let tolerance = tolerance.saturating_abs();
+
+                self.insert_integer(
+                    registry,
+                    needle,
+                    IntegerKind::Integer,
+                    integer.value.saturating_sub(tolerance)
+                        ..=integer.value.saturating_add(tolerance),
+                );
+            }
+            Needle::Float(float) => {
+                let Some(tolerance) = float.tolerance else {
+                    return false;
+                };
+                let tolerance = tolerance.abs();
+
+                self.insert_float(
+                    registry,
+                    needle,
+                    FloatKind::Float,
+                    (float.value - tolerance)..=(float.value + tolerance),
+                );
+            }
+            Needle::Timestamp(timestamp) => {
+                let Some(tolerance) = timestamp.tolerance else {
+                    return false;
+                };
+
+                // The ranges are in whole seconds, so round the tolerance out to the next one either side
+                let slack = tolerance.whole_seconds().saturating_abs().saturating_add(1);
+
+                // Local times close enough for their ranges to overlap are indexed as one span, so nothing is found twice
+                let mut locals: Vec<_> = timestamp
+                    .local_times()
+                    .into_iter()
+                    .map(|(_, local)| local)
+                    .collect();
+                locals.sort();
+
+                let mut spans = Vec::<(PrimitiveDateTime, PrimitiveDateTime)>::new();
+                for local in locals {
+                    match spans.last_mut() {
+                        Some((_, latest))
+                            if local - *latest <= Duration::seconds(slack.saturating_mul(2)) =>
+                        {
+                            *latest = local
+                        }
+                        _ => spans.push((local, local)),
+                    }
+                }
+
+                for (earliest, latest) in spans {
+                    self.insert_timestamp(registry, needle, earliest, latest, slack);
+                }
+            }
+            Needle::IpAddr(ipaddr) => {
+                let Some(tolerance) = ipaddr.tolerance else {
+                    return false;
+                };
+                let values = tolerance.network().to_bits()..=tolerance.broadcast().to_bits();
+
+                self.insert_integer(
+                    registry,
+                    needle,
+                    IntegerKind::IPv4,
+                    *values.start() as i64..=*values.end() as i64,
+                );
+
+                if registry.is_enabled(family::IPADDR) {
+                    self.text.push(AddressRange {
+                        target: needle,
+                        values: values.clone(),
+                    });
+                }
+
+                // IPv4-mapped and IPv4-compatible IPv6 addresses share a prefix, so only the last four bytes need checking
+                if registry.is_enabled(family::IPV6ADDR) {
+                    self.embedded.push(AddressRange {
+                        target: needle,
+                        values,
+                    });
+                }
+            }
+            Needle::Location(_)
+            | Needle::Ipv6Addr(_)
+            | Needle::MacAddr(_)
+            | Needle::SockAddr(_)
+            | Needle::String(_)
+            | Needle::Uuid(_)
+            | Needle::Bytes(_)
+            | Needle::Pattern(_)
+            | Needle::Custom(_) => return false,
+        }
+
+        true
+    }
+
+    /// Add an exact MAC address needle, to be found as the EUI-64 interface ID of IPv6 addresses
+    pub fn insert_eui64(&mut self, needle: &'n Needle, registry: &Registry) {
+        if registry.is_enabled(family::IPV6ADDR) {
+            self.eui64.push(needle);
+        }
+    }
+
+    /// Add the ranges a timestamp needle could take between two times, give or take some seconds
+    fn insert_timestamp(
+        &mut self,
+        registry: &Registry,
+        needle: &'n Needle,
+        earliest: PrimitiveDateTime,
+        latest: PrimitiveDateTime,
+        slack: i64,
+    ) {
+        let (start, end) = (
+            earliest.assume_utc().unix_timestamp().saturating_sub(slack),
+            latest.assume_utc().unix_timestamp().saturating_add(slack),
+        );
+
+        for (kind, scale) in [
+            (IntegerKind::EpochSecs, 1),
+            (IntegerKind::EpochMillis, 1_000),
+            (IntegerKind::EpochMicros, 1_000_000),
+            (IntegerKind::EpochNanos, 1_000_000_000),
+        ] {
+            self.insert_integer(
+                registry,
+                needle,
+                kind,
+                scaled(start, scale)..=scaled(end, scale),
+            );
+        }
+
+        // FILETIME and WebKit timestamps count from 1601
+        for (kind, scale) in [
+            (IntegerKind::FileTime, 10_000_000),
+            (IntegerKind::WebKit, 1_000_000),
+        ] {
+            self.insert_integer(
+                registry,
+                needle,
+                kind,
+                scaled(start.saturating_add(SECS_FROM_1601_TO_EPOCH), scale)
+                    ..=scaled(end.saturating_add(SECS_FROM_1601_TO_EPOCH), scale),
+            );
+        }
+
+        let slack = Duration::seconds(slack);
+        if registry.is_enabled(family::TIMESTAMP) {
+            self.structured.push(TimestampRange {
+                target: needle,
+                values: earliest
+                    .checked_sub(slack)
+                    .unwrap_or(PrimitiveDateTime::MIN)
+                    ..=latest.checked_add(slack).unwrap_or(PrimitiveDateTime::MAX),
+            });
+        }
+
+        // Timestamps counting from other epochs, as whole and fractional numbers of units
+        for epoch in Epoch::ALL {
+            self.insert_integer(
+                registry,
+                needle,
+                IntegerKind::Offset(epoch),
+                epoch.raw_integer(start)..=epoch.raw_integer(end).saturating_add(1),
+            );
+            self.insert_float(
+                registry,
+                needle,
+                FloatKind::Offset(epoch),
+                epoch.raw_float(start)..=epoch.raw_float(end),
+            );
+        }
+
+        // The DOS time bitfields are ordered from year down to seconds, so they sort the same way as the times they represent
+        let slack = slack.saturating_add(Duration::seconds(2));
+        self.insert_integer(
+            registry,
+            needle,
+            IntegerKind::DOSTime,
+            dos_time(earliest.checked_sub(slack), 0)
+                ..=dos_time(latest.checked_add(slack), u32::MAX),
+        );
+    }
+
+    fn insert_integer(
+        &mut self,
+        registry: &Registry,
+        target: &'n Needle,
+        kind: IntegerKind,
+        values: RangeInclusive<i64>,
+    ) {
+        if !registry.is_enabled(kind.family()) {
+            return;
+        }
+
+        // Ranges are grouped by encoding, so each encoding only needs decoding once per offset
+        for encoding in kind.encodings() {
+            let range = IntegerRange {
+                target,
+                kind,
+                values: values.clone(),
+            };
+
+            match self.integers.iter_mut().find(|(e, _)| e == encoding) {
+                Some((_, ranges)) => ranges.push(range),
+                None => self.integers.push((*encoding, vec![range])),
+            }
+        }
+    }
+
+    fn insert_float(
+        &mut self,
+        registry: &Registry,
+        target: &'n Needle,
+        kind: FloatKind,
+        values: RangeInclusive<f64>,
+    ) {
+        if registry.is_enabled(kind.family()) && !kind.encodings().is_empty() {
+            self.floats.push(FloatRange {
+                target,
+                kind,
+                values,
+            });
+        }
+    }
+
+    /// The most bytes any of the indexed encodings can take up
+    pub fn width(&self) -> usize {
+        let integers = self.integers.iter().map(|(encoding, _)| encoding.max_len());
+        let floats = self
+            .floats
+            .iter()
+            .flat_map(|range| range.kind.encodings())
+            .map(|encoding| encoding.byte_len());
+
+        let embedded = (!self.embedded.is_empty() || !self.eui64.is_empty()).then_some(16);
+        let text = self.text.iter().map(|_| IPv4Variant::MAX_TEXT_LEN);
+
+        integers
+            .chain(floats)
+            .chain(embedded)
+            .chain(text)
+            .chain((!self.structured.is_empty()).then_some(TimestampVariant::MAX_STRUCTURED_LEN))
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.integers.is_empty()
+            && self.floats.is_empty()
+            && self.embedded.is_empty()
+            && self.text.is_empty()
+            && self.eui64.is_empty()
+            && self.structured.is_empty()
+    }
+
+    /// Decode the raw value for every encoding at every offset, and return the Ashes of any that fall within a needle's tolerance
+    pub fn search(&self, data: &[u8]) -> Vec<Ashes<'n>> {
+        let mut ash_pile = Vec::<Ashes>::new();
+
+        if self.is_empty() {
+            return ash_pile;
+        }
+
+        for offset in 0..data.len() {
+            let window = &data[offset..];
+
+            for (encoding, ranges) in &self.integers {
+                let Some(value) = encoding.decode(window) else {
+                    continue;
+                };
+
+                for range in ranges.iter().filter(|range| range.values.contains(&value)) {
+                    if let Ok(variant) = encoding.variant(window) {
+                        let variant = range.kind.wrap(variant);
+                        push_if_matches(&mut ash_pile, range.target, variant, offset);
+                    }
+                }
+            }
+
+            if !self.structured.is_empty() {
+                self.search_structured(&mut ash_pile, window, offset);
+            }
+
+            if !self.embedded.is_empty() || !self.eui64.is_empty() {
+                self.search_ipv6(&mut ash_pile, window, offset);
+            }
+
+            if !self.text.is_empty() {
+                self.search_text(&mut ash_pile, data, offset);
+            }
+
+            if self.floats.is_empty() {
+                continue;
+            }
+
+            for encoding in FloatEncoding::ALL {
+                let Some(value) = encoding.decode(window) else {
+                    continue;
+                };
+
+                for range in self.floats.iter().filter(|range| {
+                    range.kind.encodings().contains(&encoding) && range.values.contains(&value)
+                }) {
+                    if let Ok(variant) = encoding.variant(window) {
+                        let variant = range.kind.wrap(variant);
+                        push_if_matches(&mut ash_pile, range.target, variant, offset);
+                    }
+                }
+            }
+        }
+
+        ash_pile
+    }
+
+    /// Timestamps made up of more than one value are read back once per offset, however many needles there are
+    fn search_structured(&self, ash_pile: &mut Vec<Ashes<'n>>, window: &[u8], offset: usize) {
+        for variant in TimestampVariant::interpret_structured(window) {
+            let variant = NeedleVariant::Timestamp(variant);
+
+            // Short NTP values are read as whichever time nearest each needle they could be, so have to be read back for every one
+            let timestamp = match &variant {
+                NeedleVariant::Timestamp(TimestampVariant::NTPShort(_)) => None,
+                _ => match variant.recombobulate() {
+                    Ok(Needle::Timestamp(timestamp)) => Some(timestamp),
+                    _ => continue,
+                },
+            };
+
+            for range in &self.structured {
+                let in_range = match &timestamp {
+                    Some(timestamp) => range.values.contains(&timestamp.value),
+                    None => matches!(
+                        variant.recombobulate_for(range.target),
+                        Ok(Needle::Timestamp(timestamp)) if range.values.contains(&timestamp.value)
+                    ),
+                };
+
+                if in_range {
+                    push_if_matches(ash_pile, range.target, variant.clone(), offset);
+                }
+            }
+        }
+    }
+
+    /// IPv6 addresses can have IPv4 addresses embedded in them, or MAC addresses as their interface ID
+    fn search_ipv6(&self, ash_pile: &mut Vec<Ashes<'n>>, window: &[u8], offset: usize) {
+        let Ok(variants) = IPv6Variant::interpret(window) else {
+            return;
+        };
+
+        for variant in variants {
+            let ipv6 = IPv6 {
+                value: variant.value(),
+                tolerance: None,
+            };
+
+            if let Some(ipv4) = ipv6.embedded_ipv4() {
+                for range in self
+                    .embedded
+                    .iter()
+                    .filter(|range| range.values.contains(&ipv4.to_bits()))
+                {
+                    let variant = NeedleVariant::Ipv6Addr(variant.clone());
+                    push_if_matches(ash_pile, range.target, variant, offset);
+                }
+            }
+
+            // Anything that isn't an EUI-64 interface ID can't match, so there's no need to check each needle
+            if ipv6.eui64_macaddr().is_some() {
+                for target in self.eui64.iter().copied() {
+                    let variant = NeedleVariant::Ipv6Addr(variant.clone());
+                    push_if_matches(ash_pile, target, variant, offset);
+                }
+            }
+        }
+    }
+
+    /// Text has to be parsed to get at the address, but that's only done once per offset however many needles there are
+    fn search_text(&self, ash_pile: &mut Vec<Ashes<'n>>, data: &[u8], offset: usize) {
+        for variant in IPv4Variant::interpret_text(&data[offset..]) {
+            // Only the text after the address has been looked at, so it may still be the end of something longer
+            if !variant.is_complete(&data[..offset], &[]) {
+                continue;
+            }
+
+            let Ok(Needle::IpAddr(ipv4)) = variant.recombobulate() else {
+                continue;
+            };
+
+            for range in self
+                .text
+                .iter()
+                .filter(|range| range.values.contains(&ipv4.value.to_bits()))
+            {
+                let variant = NeedleVariant::IpAddr(variant.clone());
+                push_if_matches(ash_pile, range.target, variant, offset);
+            }
+        }
+    }
+}
+
+fn push_if_matches<'n>(
+    ash_pile: &mut Vec<Ashes<'n>>,
+    target: &'n Needle,
+    variant: NeedleVariant,
+    offset: usize,
+) {
+    if let Ok(actual) = variant.recombobulate_for(target) {
+        if actual.matches(target) {
+            ash_pile.push(Ashes::new(target, actual, variant, offset));
+        }
+    }
+}
+
+fn scaled(secs: i64, scale: i64) -> i64 {
+    (secs as i128 * scale as i128).clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// The raw DOS time for a date, or the given bound if it's out of the range DOS times can represent
+fn dos_time(value: Option<time::PrimitiveDateTime>, bound: u32) -> i64 {
+    match value {
+        Some(value) if (1980..=2107).contains(&value.year()) => {
+            Timestamp::new(value).to_dos_time() as i64
+        }
+        Some(value) if value.year() < 1980 => 0,
+        Some(_) => u32::MAX as i64,
+        None => bound as i64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use crate::haystack::{engine::interpret_slice, fixtures::describe_sorted};
+
+    use super::*;
+
+    #[test]
+    fn range_test() {
+        // First 16 bytes of a ZIP file which contains a DOS timestamp
+        let data: Vec<u8> = vec![
+            0x50, 0x4b, 0x03, 0x04, 0x14, 0x00, 0x08, 0x00, 0x08, 0x00, 0x8e, 0x72, 0x22, 0x58,
+            0x00, 0x00,
+        ];
+
+        let needles = [
+            Needle::new_timestamp_with_tolerance("2024-01-02 12:00:00", Duration::days(1)).unwrap(),
+            Needle::new_location(40.73, -74.03).unwrap(),
+        ];
+
+        let registry = Registry::default();
+        let mut index = RangeIndex::default();
+        assert!(index.insert(&needles[0], &registry));
+        assert!(!index.insert(&needles[1], &registry));
+
+        let results = index.search(&data);
+
+        for result in &results {
+            println!("{:02x?}", result);
+        }
+
+        assert!(results.len() == 1);
+        assert_eq!(results[0].offset, 10);
+        assert!(matches!(
+            results[0].variant,
+            NeedleVariant::Timestamp(TimestampVariant::DOSTime(IntegerVariant::U32LE(_)))
+        ));
+    }
+
+    #[test]
+    fn interpretive_equivalence_test() {
+        // The ranges should find exactly what interpreting every offset finds
+        let data: Vec<u8> = vec![
+            0xde, 0xad, 0xbe, 0xef, 0x00, 0xa0, 0xef, 0xc4, 0x00, 0x38, 0xd9, 0x45, 0xca, 0xfe,
+            0xba, 0xbe, 0x80, 0xb0, 0xfb, 0xa2, 0xd1, 0x85, 0x88, 0xa6, 0x2f, 0x00, 0x00, 0x00,
+            0xc0, 0xa8, 0x00, 0x01, 0xff, 0xff, 0x39, 0x30, 0x00, 0x00, 0x7e, 0x00, 0x92, 0x65,
+            0x8e, 0x72, 0x22, 0x58, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0, 0xbf, 0x00, 0x93,
+            0x58, 0x75, 0x45, 0x3c, 0xda, 0x01, 0x75, 0x58, 0x93, 0x00, 0x01, 0xda, 0x3c, 0x45,
+            0x80, 0xdb, 0x88, 0x58, 0x6d, 0x6c, 0x2f, 0x00, 0x00, 0x00, 0x00, 0xff, 0x1b, 0xa1,
+            0xc5, 0x41, 0xe1, 0xb7, 0xb0, 0xfe, 0x6f, 0x74, 0xcf, 0xff, 0x7f, 0x1d, 0xe6, 0x40,
+            0xe9, 0x3c, 0x7e, 0xfe, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x65, 0x92, 0x00, 0x7e,
+            0x1d, 0xcd, 0x65, 0x00,
+        ];
+
+        let needles = vec![
+            Needle::new_integer_with_tolerance(12340, 10).unwrap(),
+            Needle::new_integer_with_tolerance(-100, 3).unwrap(),
+            Needle::new_float_with_tolerance(-1.0, 0.5).unwrap(),
+            Needle::new_timestamp_with_tolerance("2023-12-15 00:00:00", Duration::days(30))
+                .unwrap(),
+            Needle::new_ip_address_with_tolerance("192.168.0.0".parse().unwrap(), 16).unwrap(),
+            Needle::Timestamp(Timestamp {
+                tolerance: Some(Duration::minutes(30)),
+                ..Timestamp::with_offsets(
+                    datetime!(2023-12-31 14:00:00),
+                    &Timestamp::whole_hour_offsets(),
+                )
+            }),
+        ];
+        let targets = needles.iter().collect::<Vec<_>>();
+
+        let registry = Registry::default();
+        let mut index = RangeIndex::default();
+        for needle in &needles {
+            assert!(index.insert(needle, &registry));
+        }
+
+        let ranged = describe_sorted(index.search(&data));
+        let interpreted = describe_sorted(interpret_slice(&data, &targets, &registry));
+
+        for hit in &ranged {
+            println!("{:?}", hit);
+        }
+
+        // -1.0 as an F64LE
+        assert!(ranged
+            .iter()
+            .any(|(offset, variant)| *offset == 46 && variant.starts_with("Float(F64LE")));
+
+        // 2023-12-31 23:59:58 as a FILETIME, a big endian FILETIME struct, a WebKit timestamp, Cocoa, HFS+ and OLE timestamps, and half a second later as NTP and PTP timestamps
+        for (expected_offset, prefix) in [
+            (54, "Timestamp(FileTime(U64LE"),
+            (62, "Timestamp(FileTimeHalves("),
+            (70, "Timestamp(WebKit(U64LE"),
+            (78, "Timestamp(OffsetFloat(Cocoa, F64LE"),
+            (86, "Timestamp(Offset(HFSPlus, U32BE"),
+            (90, "Timestamp(OffsetFloat(OLE, F64LE"),
+            (98, "Timestamp(NTP("),
+            (106, "Timestamp(PTP("),
+        ] {
+            assert!(
+                ranged
+                    .iter()
+                    .any(|(offset, variant)| *offset == expected_offset
+                        && variant.starts_with(prefix))
+            );
+        }
+        assert_eq!(ranged, interpreted);
+    }
+}
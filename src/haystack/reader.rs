@@ -0,0 +1,361 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{ErrorKind, Read},
+    ops::ControlFlow,
+    path::Path,
+};
+
+use anyhow::{anyhow, Result};
+use memmap2::Mmap;
+
+use crate::needle::Needle;
+
+use super::{
+    ashes::Ashes,
+    engine::{Engine, Registry},
+    parallel::{burn_chunks, CHUNK_LEN},
+    visit::{Embers, MaxHits},
+};
+
+/// A haystack that's read as it's burned, so only a window of it is ever held in memory
+///
+/// Each window is burned along with enough of the next one to read the widest variant in full, and only the Ashes that start inside the window are kept, so the result is identical to burning everything in one go
+pub struct StreamingHaystack<R> {
+    reader: R,
+    pub needles: Vec<Needle>,
+    pub registry: Registry,
+    window_len: usize,
+}
+
+impl<R: Read> StreamingHaystack<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            needles: Default::default(),
+            registry: Default::default(),
+            window_len: CHUNK_LEN,
+        }
+    }
+
+    pub fn with_needles(reader: R, needles: Vec<Needle>) -> Self {
+        Self {
+            reader,
+            needles,
+            registry: Default::default(),
+            window_len: CHUNK_LEN,
+        }
+    }
+
+    pub fn with_window(reader: R, needles: Vec<Needle>, window_len: usize) -> Self {
+        Self {
+            reader,
+            needles,
+            registry: Default::default(),
+            window_len: window_len.max(1),
+        }
+    }
+
+    /// Read everything that's left and burn it, returning Ashes with offsets from where the reader started
+    pub fn burn(&mut self) -> Result<Vec<Ashes<'_>>> {
+        self.burn_iter().collect()
+    }
+
+    /// Read and burn lazily, a window at a time, handing out Ashes in the same order burn() would
+    pub fn burn_iter(&mut self) -> StreamingEmbers<'_, R> {
+        let Self {
+            reader,
+            needles,
+            registry,
+            window_len,
+        } = self;
+
+        let engine = Engine::new(needles, registry);
+        let buffer_len = engine.lead() + *window_len + engine.width();
+
+        StreamingEmbers {
+            reader,
+            needles,
+            engine,
+            window_len: *window_len,
+            buffer: Vec::with_capacity(buffer_len),
+            lead: 0,
+            start: 0,
+            finished: false,
+            pending: Default::default(),
+        }
+    }
+
+    /// Hand each of the Ashes to a visitor as they're found, stopping reading as soon as it breaks
+    pub fn visit<F>(&mut self, mut visitor: F) -> Result<ControlFlow<()>>
+    where
+        F: FnMut(Ashes<'_>) -> ControlFlow<()>,
+    {
+        for ash in self.burn_iter() {
+            if visitor(ash?).is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+        }
+
+        Ok(ControlFlow::Continue(()))
+    }
+}
+
+/// Ashes that are read and burned as they're asked for, from a streaming haystack
+///
+/// Reading can fail part way through, so each of the Ashes comes wrapped in a Result, and nothing more is handed out after an error
+pub struct StreamingEmbers<'s, R> {
+    reader: &'s mut R,
+    needles: &'s [Needle],
+    engine: Engine<'s>,
+    window_len: usize,
+    buffer: Vec<u8>,
+    // How much of the end of the last window is kept at the start of the buffer, for text to look back at
+    lead: usize,
+    start: u64,
+    finished: bool,
+    pending: VecDeque<Ashes<'s>>,
+}
+
+impl<'s, R: Read> StreamingEmbers<'s, R> {
+    /// Stop handing out Ashes for a needle once it's been found `max` times
+    pub fn max_hits(self, max: usize) -> MaxHits<'s, Self> {
+        let needles = self.needles;
+        MaxHits::new(self, needles, max)
+    }
+
+    fn burn_next(&mut self) -> Result<()> {
+        let buffer_len = self.lead + self.window_len + self.engine.width();
+
+        // Once the reader runs dry, whatever is left is the last window
+        self.finished = fill(self.reader, &mut self.buffer, buffer_len)?;
+        let window_len = match self.finished {
+            true => self.buffer.len() - self.lead,
+            false => self.window_len,
+        };
+
+        self.pending.extend(self.engine.burn_window(
+            &self.buffer,
+            self.lead,
+            window_len,
+            self.start,
+        ));
+
+        let next_lead = self.engine.lead().min(self.lead + window_len);
+        self.buffer.drain(..self.lead + window_len - next_lead);
+        self.lead = next_lead;
+        self.start += window_len as u64;
+
+        Ok(())
+    }
+}
+
+impl<'s, R: Read> Iterator for StreamingEmbers<'s, R> {
+    type Item = Result<Ashes<'s>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            if self.finished {
+                return None;
+            }
+
+            if let Err(err) = self.burn_next() {
+                self.finished = true;
+                return Some(Err(err));
+            }
+        }
+
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+/// Read until the buffer holds `len` bytes, returning true if the reader ran out first
+fn fill<R: Read>(reader: &mut R, buffer: &mut Vec<u8>, len: usize) -> Result<bool> {
+    while buffer.len() < len {
+        let filled = buffer.len();
+        buffer.resize(len, 0);
+
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => {
+                buffer.truncate(filled);
+                return Ok(true);
+            }
+            Ok(read) => buffer.truncate(filled + read),
+            Err(err) if err.kind() == ErrorKind::Interrupted => buffer.truncate(filled),
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(false)
+}
+
+/// A haystack backed by a memory-mapped file, so the OS pages it in and out as it's burned rather than it all being read up front
+pub struct MappedHaystack {
+    map: Mmap,
+    pub needles: Vec<Needle>,
+    pub registry: Registry,
+}
+
+impl MappedHaystack {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(anyhow!("Haystack file does not exist: {}", path.display()));
+        }
+
+        let file = File::open(path)?;
+
+        // Safety: the map is only ever read, but if another process truncates or modifies the file while it's mapped we may see a SIGBUS or inconsistent bytes
+        let map = unsafe { Mmap::map(&file)? };
+
+        Ok(Self {
+            map,
+            needles: Default::default(),
+            registry: Default::default(),
+        })
+    }
+
+    pub fn from_file_with_needles<P: AsRef<Path>>(path: P, needles: Vec<Needle>) -> Result<Self> {
+        let mut haystack = Self::from_file(path)?;
+        haystack.needles = needles;
+        Ok(haystack)
+    }
+
+    pub fn len(&self) -> u64 {
+        self.map.len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn burn(&self) -> Vec<Ashes<'_>> {
+        let engine = Engine::new(&self.needles, &self.registry);
+
+        engine.burn(&self.map)
+    }
+
+    /// Burn the file in overlapping chunks across all available cores, returning exactly what burn() would
+    pub fn burn_parallel(&self) -> Vec<Ashes<'_>> {
+        let engine = Engine::new(&self.needles, &self.registry);
+
+        burn_chunks(&self.map, CHUNK_LEN, &engine)
+    }
+
+    /// Burn the file lazily, a window at a time, handing out Ashes in the same order burn() would
+    pub fn burn_iter(&self) -> Embers<'_, '_> {
+        Embers::new(&self.map, &self.needles, &self.registry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::haystack::{
+        fixtures::{data, describe, needles},
+        Haystack,
+    };
+
+    use super::*;
+
+    /// A reader that only ever hands out a few bytes at a time
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.step.min(buf.len()).min(self.data.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn streaming_test() {
+        let data = data();
+        let haystack = Haystack::with_needles(data.clone(), needles());
+        let expected = describe(haystack.burn());
+        assert!(!expected.is_empty());
+
+        for (window_len, step) in [(1, 1), (5, 3), (16, 7), (40, 100), (1024, 5)] {
+            let reader = Trickle { data: &data, step };
+            let mut streaming = StreamingHaystack::with_window(reader, needles(), window_len);
+
+            assert_eq!(expected, describe(streaming.burn().unwrap()));
+        }
+    }
+
+    #[test]
+    fn streaming_iter_test() {
+        let data = data();
+        let haystack = Haystack::with_needles(data.clone(), needles());
+        let expected = describe(haystack.burn());
+
+        let reader = Trickle {
+            data: &data,
+            step: 5,
+        };
+        let mut streaming = StreamingHaystack::with_window(reader, needles(), 16);
+        let ashes = streaming.burn_iter().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(expected, describe(ashes));
+
+        // Stopping at the first hit leaves most of the reader unread
+        let mut reader = Trickle {
+            data: &data,
+            step: 5,
+        };
+        let mut streaming = StreamingHaystack::with_window(&mut reader, needles(), 16);
+        let mut seen = 0;
+        let flow = streaming
+            .visit(|_| {
+                seen += 1;
+                ControlFlow::Break(())
+            })
+            .unwrap();
+        assert!(flow.is_break());
+        assert_eq!(seen, 1);
+        drop(streaming);
+        assert!(reader.data.len() > data.len() / 2);
+
+        // And so does finding every needle once
+        let mut reader = Trickle {
+            data: &data,
+            step: 5,
+        };
+        let mut streaming = StreamingHaystack::with_window(&mut reader, needles(), 16);
+        let limited = streaming
+            .burn_iter()
+            .max_hits(1)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(limited.len(), streaming.needles.len());
+        drop(streaming);
+        assert!(!reader.data.is_empty());
+    }
+
+    #[test]
+    fn missing_file() {
+        assert!(MappedHaystack::from_file("/this/file/does/not/exist.bin").is_err());
+    }
+
+    #[test]
+    fn mapped_test() {
+        let path = std::env::temp_dir().join("bth_mapped_haystack_test.bin");
+        let data = data();
+        std::fs::write(&path, &data).unwrap();
+
+        let mapped = MappedHaystack::from_file_with_needles(&path, needles()).unwrap();
+        let haystack = Haystack::with_needles(data, needles());
+
+        let expected = describe(haystack.burn());
+        assert_eq!(mapped.len(), haystack.data.len() as u64);
+        assert_eq!(expected, describe(mapped.burn()));
+        assert_eq!(expected, describe(mapped.burn_parallel()));
+        assert_eq!(expected, describe(mapped.burn_iter()));
+
+        drop(mapped);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,80 @@
+use super::ashes::{PacketInfo, PacketSpan};
+
+/// A contiguous run of bytes reassembled from the payloads of one or more packets
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamRun {
+    pub offset: usize, // Relative to the first byte of the stream
+    pub data: Vec<u8>,
+    pub spans: Vec<PacketSpan>,
+}
+
+impl StreamRun {
+    /// Find which packets the bytes at offset..offset+len of this run came from
+    pub fn spans_for(&self, offset: usize, len: usize) -> Vec<PacketSpan> {
+        let start = self.offset + offset;
+        let end = start + len;
+
+        self.spans
+            .iter()
+            .filter(|span| span.stream_offset < end && start < span.stream_offset + span.len)
+            .map(|span| {
+                let overlap_start = start.max(span.stream_offset);
+                let overlap_end = end.min(span.stream_offset + span.len);
+
+                PacketSpan::new(
+                    span.packet.clone(),
+                    span.offset + (overlap_start - span.stream_offset),
+                    overlap_start,
+                    overlap_end - overlap_start,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Stitch together (stream offset, payload, packet) pieces into contiguous runs
+///
+/// Where pieces overlap, the bytes from whichever piece starts earliest in the stream are kept, or from the packet captured first if they start at the same place, and any missing bytes split the stream into separate runs
+pub fn assemble_runs(mut pieces: Vec<(usize, &[u8], PacketInfo)>) -> Vec<StreamRun> {
+    pieces.sort_by_key(|(offset, _, packet)| (*offset, packet.index));
+
+    let mut runs = Vec::<StreamRun>::new();
+    let mut current: Option<StreamRun> = None;
+
+    for (offset, payload, packet) in pieces {
+        let end = offset + payload.len();
+
+        match current.as_mut() {
+            Some(run) if offset <= run.offset + run.data.len() => {
+                let run_end = run.offset + run.data.len();
+
+                // Anything before run_end is a retransmit of bytes we already have
+                if end > run_end {
+                    let skip = run_end - offset;
+
+                    run.data.extend_from_slice(&payload[skip..]);
+                    run.spans
+                        .push(PacketSpan::new(packet, skip, run_end, end - run_end));
+                }
+            }
+            _ => {
+                // Either the first run, or there is a gap since the last one
+                if let Some(run) = current.take() {
+                    runs.push(run);
+                }
+
+                current = Some(StreamRun {
+                    offset,
+                    data: payload.to_owned(),
+                    spans: vec![PacketSpan::new(packet, 0, offset, payload.len())],
+                });
+            }
+        }
+    }
+
+    if let Some(run) = current {
+        runs.push(run);
+    }
+
+    runs
+}
@@ -0,0 +1,396 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+};
+
+use etherparse::{NetSlice, TransportSlice};
+
+use super::{
+    ashes::PacketInfo,
+    capture::Packet,
+    stream::{assemble_runs, StreamRun},
+};
+
+/// One direction of a TCP connection, ordered by sequence number
+///
+/// Retransmitted and overlapping bytes are taken from whichever segment starts earliest in the stream, or was captured first if they start at the same place, and missing segments split the stream into separate runs
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReassembledStream {
+    pub runs: Vec<StreamRun>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TcpFlow {
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+    pub client_to_server: ReassembledStream,
+    pub server_to_client: ReassembledStream,
+}
+
+struct Segment<'a> {
+    seq: u32,
+    payload: &'a [u8],
+    packet: PacketInfo,
+}
+
+#[derive(Default)]
+struct HalfFlow<'a> {
+    isn: Option<u32>,
+    segments: Vec<Segment<'a>>,
+}
+
+impl<'a> HalfFlow<'a> {
+    fn reassemble(&self) -> ReassembledStream {
+        let Some(first) = self.segments.first() else {
+            return ReassembledStream::default();
+        };
+
+        // Work relative to the initial sequence number if we saw the SYN, otherwise relative to the earliest segment
+        let base = self.isn.map(|isn| isn.wrapping_add(1)).unwrap_or(first.seq);
+
+        let offsets = self
+            .segments
+            .iter()
+            .map(|segment| segment.seq.wrapping_sub(base) as i32 as i64)
+            .collect::<Vec<_>>();
+
+        let min_offset = if self.isn.is_some() {
+            0
+        } else {
+            offsets.iter().copied().min().unwrap_or(0)
+        };
+
+        let pieces = offsets
+            .iter()
+            .zip(&self.segments)
+            .filter(|(offset, _)| **offset >= min_offset)
+            .map(|(offset, segment)| {
+                (
+                    (offset - min_offset) as usize,
+                    segment.payload,
+                    segment.packet.clone(),
+                )
+            })
+            .collect();
+
+        ReassembledStream {
+            runs: assemble_runs(pieces),
+        }
+    }
+}
+
+struct FlowBuilder<'a> {
+    client: SocketAddr,
+    server: SocketAddr,
+    handshake_seen: bool,
+    /// Whether either side has sent a FIN or RST, after which a SYN starts a new connection
+    closed: bool,
+    halves: HashMap<SocketAddr, HalfFlow<'a>>,
+}
+
+impl FlowBuilder<'_> {
+    fn new(client: SocketAddr, server: SocketAddr) -> Self {
+        Self {
+            client,
+            server,
+            handshake_seen: false,
+            closed: false,
+            halves: HashMap::new(),
+        }
+    }
+}
+
+/// Group the TCP packets in a capture into bidirectional flows, and reassemble the byte stream in each direction
+pub fn reassemble(packets: &[Packet]) -> Vec<TcpFlow> {
+    let mut builders = Vec::<FlowBuilder>::new();
+    let mut lookup = HashMap::<(SocketAddr, SocketAddr), usize>::new();
+
+    for packet in packets {
+        let Ok(sliced) = packet.slice() else {
+            continue;
+        };
+
+        let (src_ip, dst_ip): (IpAddr, IpAddr) = match &sliced.net {
+            Some(NetSlice::Ipv4(ipv4)) => (
+                ipv4.header().source_addr().into(),
+                ipv4.header().destination_addr().into(),
+            ),
+            Some(NetSlice::Ipv6(ipv6)) => (
+                ipv6.header().source_addr().into(),
+                ipv6.header().destination_addr().into(),
+            ),
+            None => continue,
+        };
+
+        let Some(TransportSlice::Tcp(tcp)) = &sliced.transport else {
+            continue;
+        };
+
+        let src = SocketAddr::new(src_ip, tcp.source_port());
+        let dst = SocketAddr::new(dst_ip, tcp.destination_port());
+
+        // Both directions of the connection share the same key
+        let key = if src <= dst { (src, dst) } else { (dst, src) };
+
+        // A SYN after the last connection on these addresses and ports was closed is a new connection reusing them
+        let index = match lookup.get(&key) {
+            Some(&index) if !(tcp.syn() && builders[index].closed) => index,
+            _ => {
+                builders.push(FlowBuilder::new(src, dst));
+                lookup.insert(key, builders.len() - 1);
+                builders.len() - 1
+            }
+        };
+        let builder = &mut builders[index];
+
+        // The handshake tells us who the client is, otherwise assume it's whoever spoke first
+        if tcp.syn() && !builder.handshake_seen {
+            builder.handshake_seen = true;
+            (builder.client, builder.server) = if tcp.ack() { (dst, src) } else { (src, dst) };
+        }
+
+        let half = builder.halves.entry(src).or_default();
+
+        // A SYN uses up a sequence number, so any data in the same segment starts after it
+        let seq = if tcp.syn() {
+            half.isn = Some(tcp.sequence_number());
+            tcp.sequence_number().wrapping_add(1)
+        } else {
+            tcp.sequence_number()
+        };
+
+        let payload = tcp.payload();
+        if !payload.is_empty() {
+            half.segments.push(Segment {
+                seq,
+                payload,
+                packet: packet.info(),
+            });
+        }
+
+        if tcp.fin() || tcp.rst() {
+            builder.closed = true;
+        }
+    }
+
+    builders
+        .into_iter()
+        .map(|builder| TcpFlow {
+            client: builder.client,
+            server: builder.server,
+            client_to_server: builder
+                .halves
+                .get(&builder.client)
+                .map(|half| half.reassemble())
+                .unwrap_or_default(),
+            server_to_client: builder
+                .halves
+                .get(&builder.server)
+                .map(|half| half.reassemble())
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use etherparse::PacketBuilder;
+    use time::OffsetDateTime;
+
+    use crate::{
+        haystack::{
+            ashes::Direction,
+            capture::{Capture, LinkType},
+        },
+        needle::{
+            ipaddr::variant::IPv4Variant, number::variants::IntegerVariant, variant::NeedleVariant,
+            Matches, Needle,
+        },
+    };
+
+    use super::*;
+
+    const CLIENT: [u8; 4] = [10, 0, 0, 1];
+    const SERVER: [u8; 4] = [10, 0, 0, 2];
+
+    fn tcp_packet(
+        index: usize,
+        from_client: bool,
+        seq: u32,
+        syn: bool,
+        ack: bool,
+        fin: bool,
+        payload: &[u8],
+    ) -> Packet {
+        let (src, dst, src_port, dst_port) = if from_client {
+            (CLIENT, SERVER, 40000, 80)
+        } else {
+            (SERVER, CLIENT, 80, 40000)
+        };
+
+        let mut builder = PacketBuilder::ethernet2([1, 2, 3, 4, 5, 6], [7, 8, 9, 10, 11, 12])
+            .ipv4(src, dst, 64)
+            .tcp(src_port, dst_port, seq, 1024);
+
+        if syn {
+            builder = builder.syn();
+        }
+        if ack {
+            builder = builder.ack(1);
+        }
+        if fin {
+            builder = builder.fin();
+        }
+
+        let mut frame = Vec::<u8>::with_capacity(builder.size(payload.len()));
+        builder.write(&mut frame, payload).unwrap();
+
+        Packet::new(
+            index,
+            OffsetDateTime::from_unix_timestamp(1704067200 + index as i64).unwrap(),
+            LinkType::ETHERNET,
+            frame,
+        )
+    }
+
+    #[test]
+    fn reassembly_test() {
+        // The server's reply arrives out of order, one segment is retransmitted, and the last one overlaps
+        let packets = vec![
+            tcp_packet(0, true, 1000, true, false, false, &[]),
+            tcp_packet(1, false, 5000, true, true, false, &[]),
+            tcp_packet(2, true, 1001, false, true, false, b"GET "),
+            tcp_packet(3, false, 5005, false, true, false, b"world"),
+            tcp_packet(4, false, 5001, false, true, false, b"hell"),
+            tcp_packet(5, true, 1005, false, true, false, b"/ HTTP"),
+            tcp_packet(6, true, 1005, false, true, false, b"/ HTTP"),
+            tcp_packet(7, false, 5008, false, true, false, b"ld!"),
+        ];
+
+        let flows = reassemble(&packets);
+        assert_eq!(flows.len(), 1);
+
+        let flow = &flows[0];
+        assert_eq!(flow.client, "10.0.0.1:40000".parse().unwrap());
+        assert_eq!(flow.server, "10.0.0.2:80".parse().unwrap());
+
+        assert_eq!(flow.client_to_server.runs.len(), 1);
+        assert_eq!(flow.client_to_server.runs[0].data, b"GET / HTTP");
+
+        assert_eq!(flow.server_to_client.runs.len(), 1);
+        assert_eq!(flow.server_to_client.runs[0].data, b"hellworld!");
+
+        // "ld!" overlapped "world" by two bytes, so only the "!" came from packet 7
+        let spans = flow.server_to_client.runs[0].spans_for(8, 2);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].packet.index, 3);
+        assert_eq!(
+            (spans[0].offset, spans[0].stream_offset, spans[0].len),
+            (4, 8, 1)
+        );
+        assert_eq!(spans[1].packet.index, 7);
+        assert_eq!(
+            (spans[1].offset, spans[1].stream_offset, spans[1].len),
+            (2, 9, 1)
+        );
+    }
+
+    #[test]
+    fn reused_ports_test() {
+        // Two connections one after the other, from the same client port to the same server port
+        let packets = vec![
+            tcp_packet(0, true, 1000, true, false, false, &[]),
+            tcp_packet(1, false, 5000, true, true, false, &[]),
+            tcp_packet(2, true, 1001, false, true, false, b"first"),
+            tcp_packet(3, true, 1006, false, true, true, &[]),
+            tcp_packet(4, true, 9000, true, false, false, &[]),
+            tcp_packet(5, false, 3000, true, true, false, &[]),
+            tcp_packet(6, true, 9001, false, true, false, b"second"),
+        ];
+
+        // The second SYN starts a flow of its own, rather than being merged into the first
+        let flows = reassemble(&packets);
+        assert_eq!(flows.len(), 2);
+        assert_eq!(flows[0].client_to_server.runs[0].data, b"first");
+        assert_eq!(flows[1].client_to_server.runs.len(), 1);
+        assert_eq!(flows[1].client_to_server.runs[0].data, b"second");
+        assert_eq!(flows[1].client, "10.0.0.1:40000".parse().unwrap());
+    }
+
+    #[test]
+    fn gap_test() {
+        // No handshake, and the segment carrying bytes 4..8 was never captured
+        let packets = vec![
+            tcp_packet(0, false, 7000, false, true, false, b"abcd"),
+            tcp_packet(1, false, 7008, false, true, false, b"ijkl"),
+        ];
+
+        let flows = reassemble(&packets);
+        assert_eq!(flows.len(), 1);
+
+        // Whoever spoke first is assumed to be the client
+        assert_eq!(flows[0].client, "10.0.0.2:80".parse().unwrap());
+
+        let runs = &flows[0].client_to_server.runs;
+        assert_eq!(runs.len(), 2);
+        assert_eq!((runs[0].offset, runs[0].data.as_slice()), (0, &b"abcd"[..]));
+        assert_eq!((runs[1].offset, runs[1].data.as_slice()), (8, &b"ijkl"[..]));
+    }
+
+    #[test]
+    fn burn_streams_test() {
+        // An IP address (192.168.0.1 as Numeric(U32BE)) split across two segments
+        let packets = vec![
+            tcp_packet(0, true, 1000, true, false, false, &[]),
+            tcp_packet(1, false, 5000, true, true, false, &[]),
+            tcp_packet(
+                2,
+                false,
+                5001,
+                false,
+                true,
+                false,
+                &[0xff, 0xff, 0xc0, 0xa8],
+            ),
+            tcp_packet(3, false, 5005, false, true, false, &[0x00, 0x01, 0xff]),
+        ];
+
+        let private_ip =
+            Needle::new_ip_address_with_tolerance("192.168.0.0".parse().unwrap(), 16).unwrap();
+        let needles = vec![private_ip];
+
+        // Burning packet by packet can't see it...
+        let capture = Capture::with_needles(packets, needles.clone());
+        assert!(capture.burn().is_empty());
+
+        // ...but burning the reassembled stream can
+        let results = capture.burn_streams();
+
+        for result in &results {
+            println!("{:02x?}", result);
+        }
+
+        assert!(results.len() == 1);
+        assert!(results[0].actual.matches(&needles[0]));
+        assert!(matches!(
+            results[0].variant,
+            NeedleVariant::IpAddr(IPv4Variant::Numeric(IntegerVariant::U32BE(_)))
+        ));
+        assert_eq!(results[0].offset, 2);
+
+        let flow = results[0].flow.as_ref().unwrap();
+        assert_eq!(flow.direction, Direction::ServerToClient);
+        assert_eq!(flow.source, "10.0.0.2:80".parse().unwrap());
+
+        assert_eq!(results[0].packet.as_ref().unwrap().index, 2);
+        assert_eq!(results[0].spans.len(), 2);
+        assert_eq!(
+            (results[0].spans[0].packet.index, results[0].spans[0].offset),
+            (2, 2)
+        );
+        assert_eq!(
+            (results[0].spans[1].packet.index, results[0].spans[1].offset),
+            (3, 0)
+        );
+    }
+}
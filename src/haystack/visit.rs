@@ -0,0 +1,288 @@
+use std::{collections::VecDeque, ptr};
+
+use anyhow::Result;
+
+use crate::needle::Needle;
+
+use super::{
+    ashes::Ashes,
+    engine::{Engine, Registry},
+};
+
+/// How many bytes are burned at a time when Ashes are handed out lazily
+pub const WINDOW_LEN: usize = 64 * 1024;
+
+/// Ashes that are found as they're asked for, so only one window's worth are ever held at a time
+///
+/// Each window is burned along with enough of the next one to read the widest variant in full and the few bytes before it that text needs to see, and only the Ashes that start inside it are kept, so the Ashes come out in exactly the same order burn() would return them
+pub struct Embers<'d, 'n> {
+    data: &'d [u8],
+    needles: &'n [Needle],
+    engine: Engine<'n>,
+    window_len: usize,
+    start: usize,
+    pending: VecDeque<Ashes<'n>>,
+}
+
+impl<'d, 'n> Embers<'d, 'n> {
+    pub fn new(data: &'d [u8], needles: &'n [Needle], registry: &'n Registry) -> Self {
+        Self::with_window(data, needles, registry, WINDOW_LEN)
+    }
+
+    pub fn with_window(
+        data: &'d [u8],
+        needles: &'n [Needle],
+        registry: &'n Registry,
+        window_len: usize,
+    ) -> Self {
+        Self {
+            data,
+            needles,
+            engine: Engine::new(needles, registry),
+            window_len: window_len.max(1),
+            start: 0,
+            pending: Default::default(),
+        }
+    }
+
+    /// Stop handing out Ashes for a needle once it's been found `max` times
+    pub fn max_hits(self, max: usize) -> MaxHits<'n, Self> {
+        let needles = self.needles;
+        MaxHits::new(self, needles, max)
+    }
+}
+
+impl<'n> Iterator for Embers<'_, 'n> {
+    type Item = Ashes<'n>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            if self.start >= self.data.len() {
+                return None;
+            }
+
+            let start = self.start;
+            let end = (start + self.window_len).min(self.data.len());
+            let lead = self.engine.lead().min(start);
+            let overlap = (end + self.engine.width()).min(self.data.len());
+
+            self.pending.extend(self.engine.burn_window(
+                &self.data[start - lead..overlap],
+                lead,
+                end - start,
+                start as u64,
+            ));
+
+            self.start = end;
+        }
+
+        self.pending.pop_front()
+    }
+}
+
+/// One item handed out by a lazy burn, which is either Ashes or, for burns that can fail part way, the result of finding them
+pub trait Ember<'n> {
+    fn ashes(&self) -> Option<&Ashes<'n>>;
+}
+
+impl<'n> Ember<'n> for Ashes<'n> {
+    fn ashes(&self) -> Option<&Ashes<'n>> {
+        Some(self)
+    }
+}
+
+impl<'n> Ember<'n> for Result<Ashes<'n>> {
+    fn ashes(&self) -> Option<&Ashes<'n>> {
+        self.as_ref().ok()
+    }
+}
+
+/// Stop handing out Ashes for a needle once it's been found a given number of times
+///
+/// Once every needle has been found that many times nothing more is asked of the inner iterator, so lazy burns finish early, and errors are always passed on
+pub struct MaxHits<'n, I> {
+    inner: I,
+    needles: &'n [Needle],
+    max: usize,
+    counts: Vec<usize>,
+}
+
+impl<'n, I> MaxHits<'n, I>
+where
+    I: Iterator,
+    I::Item: Ember<'n>,
+{
+    pub fn new(inner: I, needles: &'n [Needle], max: usize) -> Self {
+        Self {
+            inner,
+            needles,
+            max,
+            counts: vec![0; needles.len()],
+        }
+    }
+}
+
+impl<'n, I> Iterator for MaxHits<'n, I>
+where
+    I: Iterator,
+    I::Item: Ember<'n>,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.counts.iter().all(|count| *count >= self.max) {
+                return None;
+            }
+
+            let ember = self.inner.next()?;
+            let Some(ash) = ember.ashes() else {
+                return Some(ember);
+            };
+
+            // Needles are told apart by address, as the same needle can be given more than once
+            let Some(i) = self
+                .needles
+                .iter()
+                .position(|needle| ptr::eq(needle, ash.target))
+            else {
+                return Some(ember);
+            };
+
+            if self.counts[i] < self.max {
+                self.counts[i] += 1;
+                return Some(ember);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::ControlFlow;
+
+    use crate::haystack::{
+        fixtures::{data, describe, needles},
+        hay::{Hay, HayData},
+        Haystack,
+    };
+
+    use super::*;
+
+    #[test]
+    fn embers_test() {
+        let haystack = Haystack::with_needles(data(), needles());
+        let expected = describe(haystack.burn());
+        assert!(!expected.is_empty());
+
+        assert_eq!(expected, describe(haystack.burn_iter()));
+
+        for window_len in [1, 3, 7, 16, 64] {
+            let embers = Embers::with_window(
+                &haystack.data,
+                &haystack.needles,
+                &haystack.registry,
+                window_len,
+            );
+            assert_eq!(expected, describe(embers));
+        }
+    }
+
+    #[test]
+    fn max_hits_test() {
+        let haystack = Haystack::with_needles(data(), needles());
+
+        let limited = MaxHits::new(haystack.burn_iter(), &haystack.needles, 1).collect::<Vec<_>>();
+
+        // Every needle is in there four times, but should only be reported once
+        assert_eq!(limited.len(), haystack.needles.len());
+        for needle in &haystack.needles {
+            assert_eq!(
+                limited
+                    .iter()
+                    .filter(|ash| ptr::eq(ash.target, needle))
+                    .count(),
+                1
+            );
+        }
+
+        // And they should be the first hit for each needle
+        let first = haystack
+            .burn()
+            .into_iter()
+            .find(|ash| ptr::eq(ash.target, &haystack.needles[2]));
+        assert_eq!(
+            limited
+                .iter()
+                .find(|ash| ptr::eq(ash.target, &haystack.needles[2]))
+                .map(|ash| ash.offset),
+            first.map(|ash| ash.offset)
+        );
+
+        assert_eq!(
+            MaxHits::new(haystack.burn_iter(), &haystack.needles, 0).count(),
+            0
+        );
+
+        // Limiting the haystack's own lazy burn gives the same Ashes
+        assert_eq!(
+            describe(limited),
+            describe(haystack.burn_iter().max_hits(1))
+        );
+        assert_eq!(haystack.burn_iter().max_hits(0).count(), 0);
+        assert_eq!(
+            haystack.burn_iter().max_hits(usize::MAX).count(),
+            haystack.burn().len()
+        );
+    }
+
+    #[test]
+    fn visit_test() {
+        let haystack = Haystack::with_needles(data(), needles());
+        let total = haystack.burn().len();
+
+        let mut seen = Vec::<u64>::new();
+        let flow = haystack.visit(|ash| {
+            seen.push(ash.offset);
+            if seen.len() == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+
+        assert!(flow.is_break());
+        assert_eq!(seen.len(), 2);
+        assert!(total > 2);
+
+        let mut count = 0;
+        assert!(haystack
+            .visit(|_| {
+                count += 1;
+                ControlFlow::Continue(())
+            })
+            .is_continue());
+        assert_eq!(count, total);
+    }
+
+    #[test]
+    fn hay_test() {
+        let datas = data()
+            .chunks(13)
+            .map(|chunk| HayData::Raw(chunk.to_vec()))
+            .collect();
+        let hay = Hay::with_needles(datas, needles());
+
+        let expected = describe(hay.burn());
+        assert!(!expected.is_empty());
+        assert_eq!(expected, describe(hay.burn_iter()));
+
+        let mut count = 0;
+        let flow = hay.visit(|_| {
+            count += 1;
+            ControlFlow::Break(())
+        });
+        assert!(flow.is_break());
+        assert_eq!(count, 1);
+    }
+}
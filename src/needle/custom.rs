@@ -0,0 +1,373 @@
+use std::{any::Any, cmp::Ordering, fmt::Debug};
+
+use anyhow::Result;
+
+use super::{variant::NeedleVariant, Discombobulate, Interpret, Matches, Needle, Recombobulate};
+
+/// A kind of needle that isn't built in, which can be burned and reported just like the ones that are
+///
+/// Its variants are discombobulated into NeedleVariant::custom, and its variant family has to be registered on the haystack's registry before they can be found
+pub trait CustomNeedle:
+    Matches + Discombobulate + Clone + Debug + PartialEq + PartialOrd + Send + Sync + 'static
+{
+    /// The ways it can be laid out in a haystack
+    type Variant: CustomVariant;
+
+    /// The name its variant family is registered, enabled and disabled under
+    const FAMILY: &'static str;
+
+    /// Whether it only matches a single value, so it can be found by searching for its discombobulated byte sequences
+    fn is_exact(&self) -> bool {
+        false
+    }
+}
+
+/// One way a custom needle can be laid out in a haystack, which recombobulates into Needle::custom
+pub trait CustomVariant:
+    Interpret + Recombobulate + Clone + Debug + PartialEq + PartialOrd + Send + Sync + 'static
+{
+    /// The most bytes any variant can take up
+    const MAX_LEN: usize;
+
+    /// The bytes this variant occupies in the haystack
+    fn byte_sequence(&self) -> &[u8];
+}
+
+/// The parts of a CustomNeedle that don't depend on its concrete type, so it can be boxed inside a Needle
+trait AnyNeedle: Debug + Send + Sync {
+    fn family(&self) -> &'static str;
+    fn is_exact(&self) -> bool;
+    fn max_len(&self) -> usize;
+    fn matches(&self, rhs: &dyn AnyNeedle) -> bool;
+    fn discombobulate(&self) -> Vec<NeedleVariant>;
+    fn as_any(&self) -> &dyn Any;
+    fn clone_box(&self) -> Box<dyn AnyNeedle>;
+    fn eq_dyn(&self, rhs: &dyn AnyNeedle) -> bool;
+    fn partial_cmp_dyn(&self, rhs: &dyn AnyNeedle) -> Option<Ordering>;
+}
+
+impl<N: CustomNeedle> AnyNeedle for N {
+    fn family(&self) -> &'static str {
+        N::FAMILY
+    }
+
+    fn is_exact(&self) -> bool {
+        CustomNeedle::is_exact(self)
+    }
+
+    fn max_len(&self) -> usize {
+        N::Variant::MAX_LEN
+    }
+
+    fn matches(&self, rhs: &dyn AnyNeedle) -> bool {
+        rhs.as_any()
+            .downcast_ref::<N>()
+            .is_some_and(|rhs| Matches::matches(self, rhs))
+    }
+
+    fn discombobulate(&self) -> Vec<NeedleVariant> {
+        Discombobulate::discombobulate(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn AnyNeedle> {
+        Box::new(self.clone())
+    }
+
+    fn eq_dyn(&self, rhs: &dyn AnyNeedle) -> bool {
+        rhs.as_any().downcast_ref::<N>() == Some(self)
+    }
+
+    fn partial_cmp_dyn(&self, rhs: &dyn AnyNeedle) -> Option<Ordering> {
+        match rhs.as_any().downcast_ref::<N>() {
+            Some(rhs) => self.partial_cmp(rhs),
+            None => N::FAMILY.partial_cmp(rhs.family()),
+        }
+    }
+}
+
+/// The parts of a CustomVariant that don't depend on its concrete type, so it can be boxed inside a NeedleVariant
+trait AnyVariant: Debug + Send + Sync {
+    fn family(&self) -> &'static str;
+    fn byte_sequence(&self) -> &[u8];
+    fn recombobulate(&self) -> Result<Needle>;
+    fn as_any(&self) -> &dyn Any;
+    fn clone_box(&self) -> Box<dyn AnyVariant>;
+    fn eq_dyn(&self, rhs: &dyn AnyVariant) -> bool;
+    fn partial_cmp_dyn(&self, rhs: &dyn AnyVariant) -> Option<Ordering>;
+}
+
+/// Ties a variant back to the needle it belongs to, so it knows which family it's in
+struct Variant<N: CustomNeedle>(N::Variant);
+
+impl<N: CustomNeedle> Debug for Variant<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<N: CustomNeedle> AnyVariant for Variant<N> {
+    fn family(&self) -> &'static str {
+        N::FAMILY
+    }
+
+    fn byte_sequence(&self) -> &[u8] {
+        self.0.byte_sequence()
+    }
+
+    fn recombobulate(&self) -> Result<Needle> {
+        self.0.recombobulate()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        &self.0
+    }
+
+    fn clone_box(&self) -> Box<dyn AnyVariant> {
+        Box::new(Variant::<N>(self.0.clone()))
+    }
+
+    fn eq_dyn(&self, rhs: &dyn AnyVariant) -> bool {
+        rhs.as_any().downcast_ref::<N::Variant>() == Some(&self.0)
+    }
+
+    fn partial_cmp_dyn(&self, rhs: &dyn AnyVariant) -> Option<Ordering> {
+        match rhs.as_any().downcast_ref::<N::Variant>() {
+            Some(rhs) => self.0.partial_cmp(rhs),
+            None => N::FAMILY.partial_cmp(rhs.family()),
+        }
+    }
+}
+
+/// A boxed custom needle, held by Needle::Custom
+#[derive(Debug)]
+pub struct BoxedNeedle(Box<dyn AnyNeedle>);
+
+impl BoxedNeedle {
+    pub fn new<N: CustomNeedle>(needle: N) -> Self {
+        Self(Box::new(needle))
+    }
+
+    pub fn family(&self) -> &'static str {
+        self.0.family()
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.0.is_exact()
+    }
+
+    /// The most bytes any of its variants can take up
+    pub fn max_len(&self) -> usize {
+        self.0.max_len()
+    }
+
+    /// The needle itself, if it's of the given type
+    pub fn downcast_ref<N: CustomNeedle>(&self) -> Option<&N> {
+        self.0.as_any().downcast_ref()
+    }
+}
+
+impl Clone for BoxedNeedle {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
+}
+
+impl PartialEq for BoxedNeedle {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.0.eq_dyn(rhs.0.as_ref())
+    }
+}
+
+impl PartialOrd for BoxedNeedle {
+    fn partial_cmp(&self, rhs: &Self) -> Option<Ordering> {
+        self.0.partial_cmp_dyn(rhs.0.as_ref())
+    }
+}
+
+impl Matches for BoxedNeedle {
+    fn matches(&self, rhs: &Self) -> bool {
+        self.0.matches(rhs.0.as_ref())
+    }
+}
+
+impl Discombobulate for BoxedNeedle {
+    fn discombobulate(&self) -> Vec<NeedleVariant> {
+        self.0.discombobulate()
+    }
+}
+
+/// A boxed custom variant, held by NeedleVariant::Custom
+#[derive(Debug)]
+pub struct BoxedVariant(Box<dyn AnyVariant>);
+
+impl BoxedVariant {
+    pub fn new<N: CustomNeedle>(variant: N::Variant) -> Self {
+        Self(Box::new(Variant::<N>(variant)))
+    }
+
+    pub fn family(&self) -> &'static str {
+        self.0.family()
+    }
+
+    pub fn byte_sequence(&self) -> &[u8] {
+        self.0.byte_sequence()
+    }
+
+    /// The variant itself, if it's of the given type
+    pub fn downcast_ref<V: CustomVariant>(&self) -> Option<&V> {
+        self.0.as_any().downcast_ref()
+    }
+}
+
+impl Clone for BoxedVariant {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
+}
+
+impl PartialEq for BoxedVariant {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.0.eq_dyn(rhs.0.as_ref())
+    }
+}
+
+impl PartialOrd for BoxedVariant {
+    fn partial_cmp(&self, rhs: &Self) -> Option<Ordering> {
+        self.0.partial_cmp_dyn(rhs.0.as_ref())
+    }
+}
+
+impl Recombobulate for BoxedVariant {
+    fn recombobulate(&self) -> Result<Needle> {
+        self.0.recombobulate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use crate::haystack::{engine::Registry, Haystack};
+
+    use super::*;
+
+    /// A made up sensor ID, stored as "SN" followed by a big endian u32
+    #[derive(Clone, Debug, PartialEq, PartialOrd)]
+    struct SensorId {
+        id: u32,
+        tolerance: u32,
+    }
+
+    #[derive(Clone, Debug, PartialEq, PartialOrd)]
+    struct SensorIdVariant([u8; 6]);
+
+    impl CustomNeedle for SensorId {
+        type Variant = SensorIdVariant;
+        const FAMILY: &'static str = "sensor_id";
+
+        fn is_exact(&self) -> bool {
+            self.tolerance == 0
+        }
+    }
+
+    impl Matches for SensorId {
+        fn matches(&self, rhs: &Self) -> bool {
+            self.id.abs_diff(rhs.id) <= rhs.tolerance
+        }
+    }
+
+    impl Discombobulate for SensorId {
+        fn discombobulate(&self) -> Vec<NeedleVariant> {
+            let mut bytes = [b'S', b'N', 0, 0, 0, 0];
+            bytes[2..].copy_from_slice(&self.id.to_be_bytes());
+
+            vec![NeedleVariant::custom::<SensorId>(SensorIdVariant(bytes))]
+        }
+    }
+
+    impl CustomVariant for SensorIdVariant {
+        const MAX_LEN: usize = 6;
+
+        fn byte_sequence(&self) -> &[u8] {
+            &self.0
+        }
+    }
+
+    impl Interpret for SensorIdVariant {
+        fn interpret(data: &[u8]) -> Result<Vec<Self>> {
+            match data.get(..6) {
+                Some(bytes) if bytes.starts_with(b"SN") => {
+                    Ok(vec![SensorIdVariant(bytes.try_into()?)])
+                }
+                _ => Err(anyhow!("Failed to interpret bytes as a SensorIdVariant!")),
+            }
+        }
+    }
+
+    impl Recombobulate for SensorIdVariant {
+        fn recombobulate(&self) -> Result<Needle> {
+            let id = u32::from_be_bytes(self.0[2..].try_into()?);
+
+            Ok(Needle::custom(SensorId { id, tolerance: 0 }))
+        }
+    }
+
+    #[test]
+    fn custom_test() {
+        let data: Vec<u8> = vec![
+            0xde, 0xad, b'S', b'N', 0x00, 0x00, 0x30, 0x39, 0xbe, 0xef, b'S', b'N', 0x00, 0x00,
+            0x30, 0x3c, 0xff,
+        ];
+
+        let needles = vec![
+            // Exact, so found through the index
+            Needle::custom(SensorId {
+                id: 12345,
+                tolerance: 0,
+            }),
+            // Within a tolerance, so found by interpreting every offset
+            Needle::custom(SensorId {
+                id: 12350,
+                tolerance: 2,
+            }),
+        ];
+
+        // Until the family is registered, nothing knows how to find them
+        let mut haystack = Haystack::with_needles(data, needles.clone());
+        assert!(haystack.burn().is_empty());
+
+        haystack.registry.register_custom::<SensorId>();
+        let results = haystack.burn();
+
+        for result in &results {
+            println!("{:02x?}", result);
+        }
+
+        assert_eq!(results.len(), 2);
+
+        assert_eq!(results[0].offset, 2);
+        assert_eq!(results[0].target, &needles[0]);
+        assert_eq!(results[0].variant.family(), SensorId::FAMILY);
+
+        assert_eq!(results[1].offset, 10);
+        assert_eq!(results[1].target, &needles[1]);
+        assert_eq!(
+            results[1]
+                .actual
+                .as_custom::<SensorId>()
+                .map(|sensor| sensor.id),
+            Some(12348)
+        );
+
+        // And they can be turned off again like any other family
+        haystack.registry.disable(SensorId::FAMILY);
+        assert!(haystack.burn().is_empty());
+
+        let registry = Registry::default();
+        assert!(!registry.is_enabled(SensorId::FAMILY));
+    }
+}
@@ -1,9 +1,11 @@
+pub mod text;
 pub mod variant;
 
+use ::macaddr::MacAddr6;
 use anyhow::Result;
-use ipnet::Ipv4Net;
-use std::net::Ipv4Addr;
-use variant::IPv4Variant;
+use ipnet::{Ipv4Net, Ipv6Net};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use variant::{IPv4Variant, IPv6Variant};
 
 use super::{variant::NeedleVariant, Discombobulate, Matches};
 
@@ -53,10 +55,116 @@ impl Discombobulate for IPv4 {
             }
         }
 
+        variants.extend(
+            IPv4Variant::text(self.value)
+                .into_iter()
+                .map(NeedleVariant::IpAddr),
+        );
+
+        // IPv4-mapped and IPv4-compatible IPv6 addresses
+        for ipv6 in IPv6::embedding(self.value) {
+            variants.extend(IPv6Variant::all(ipv6).map(NeedleVariant::Ipv6Addr));
+        }
+
         variants
     }
 }
 
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct IPv6 {
+    pub value: Ipv6Addr,
+    pub tolerance: Option<Ipv6Net>,
+}
+
+impl IPv6 {
+    pub fn new(ipaddr: Ipv6Addr) -> Result<Self> {
+        Ok(Self {
+            value: ipaddr,
+            tolerance: None,
+        })
+    }
+
+    pub fn with_tolerance(ipaddr: Ipv6Addr, prefix_len: u8) -> Result<Self> {
+        Ok(Self {
+            value: ipaddr,
+            tolerance: Some(Ipv6Net::new(ipaddr, prefix_len)?),
+        })
+    }
+
+    /// The address a host with this MAC address would give itself on a network using SLAAC
+    pub fn from_eui64(network: Ipv6Addr, macaddr: MacAddr6) -> Result<Self> {
+        let mut octets = network.octets();
+        let mac = macaddr.as_bytes();
+
+        // The universal/local bit is flipped, and FF:FE is wedged between the OUI and the NIC specific part
+        octets[8..].copy_from_slice(&[
+            mac[0] ^ 0x02,
+            mac[1],
+            mac[2],
+            0xff,
+            0xfe,
+            mac[3],
+            mac[4],
+            mac[5],
+        ]);
+
+        Self::new(octets.into())
+    }
+
+    /// The IPv4 address embedded in an IPv4-mapped (::ffff:a.b.c.d) or IPv4-compatible (::a.b.c.d) address
+    ///
+    /// Compatible addresses in 0.0.0.0/8 are ignored, otherwise :: and ::1 (and any long enough run of zeros) would be read as IPv4 addresses
+    pub fn embedded_ipv4(&self) -> Option<Ipv4Addr> {
+        let octets = self.value.octets();
+        let ipv4 = Ipv4Addr::new(octets[12], octets[13], octets[14], octets[15]);
+
+        match octets[..12] {
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff] => Some(ipv4),
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0] if octets[12] != 0 => Some(ipv4),
+            _ => None,
+        }
+    }
+
+    /// The IPv6 addresses an IPv4 address can be embedded in
+    pub fn embedding(ipv4: Ipv4Addr) -> Vec<Ipv6Addr> {
+        let mut embedding = vec![ipv4.to_ipv6_mapped()];
+
+        if ipv4.octets()[0] != 0 {
+            embedding.push(ipv4.to_ipv6_compatible());
+        }
+
+        embedding
+    }
+
+    /// The MAC address an EUI-64 interface ID was built from
+    pub fn eui64_macaddr(&self) -> Option<MacAddr6> {
+        let octets = self.value.octets();
+
+        match octets[8..] {
+            [a, b, c, 0xff, 0xfe, d, e, f] => Some(MacAddr6::new(a ^ 0x02, b, c, d, e, f)),
+            _ => None,
+        }
+    }
+}
+
+impl Matches for IPv6 {
+    fn matches(&self, rhs: &Self) -> bool {
+        // If rhs has a tolerance, check that lhs falls wthin it
+        match &rhs.tolerance {
+            Some(tolerance) => tolerance.contains(&self.value),
+            None => self.value == rhs.value,
+        }
+    }
+}
+
+impl Discombobulate for IPv6 {
+    fn discombobulate(&self) -> Vec<NeedleVariant> {
+        IPv6Variant::all(self.value)
+            .map(NeedleVariant::Ipv6Addr)
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -75,6 +183,46 @@ mod tests {
         assert!(actual.matches(&target));
     }
 
+    #[test]
+    fn ipv6_prefix_test() {
+        let actual = IPv6::new("2001:db8:1:2::42".parse().unwrap()).unwrap();
+        let target = IPv6::with_tolerance("2001:db8:1::".parse().unwrap(), 48).unwrap();
+
+        assert!(actual.matches(&target));
+
+        let target = IPv6::with_tolerance("2001:db8:2::".parse().unwrap(), 48).unwrap();
+
+        assert!(!actual.matches(&target));
+    }
+
+    #[test]
+    fn embedded_ipv4_test() {
+        let mapped = IPv6::new("::ffff:192.168.0.1".parse().unwrap()).unwrap();
+        let compatible = IPv6::new("::192.168.0.1".parse().unwrap()).unwrap();
+        let loopback = IPv6::new(Ipv6Addr::LOCALHOST).unwrap();
+
+        assert_eq!(mapped.embedded_ipv4(), Some(Ipv4Addr::new(192, 168, 0, 1)));
+        assert_eq!(
+            compatible.embedded_ipv4(),
+            Some(Ipv4Addr::new(192, 168, 0, 1))
+        );
+        assert_eq!(loopback.embedded_ipv4(), None);
+
+        assert_eq!(IPv6::embedding(Ipv4Addr::new(0, 0, 0, 1)).len(), 1);
+    }
+
+    #[test]
+    fn eui64_test() {
+        let macaddr: MacAddr6 = "14:7D:DA:AB:CD:EF".parse().unwrap();
+        let actual = IPv6::from_eui64("fe80::".parse().unwrap(), macaddr).unwrap();
+
+        assert_eq!(
+            actual.value,
+            "fe80::167d:daff:feab:cdef".parse::<Ipv6Addr>().unwrap()
+        );
+        assert_eq!(actual.eui64_macaddr(), Some(macaddr));
+    }
+
     #[test]
     fn discombobulation_test() {
         let actual = IPv4::new("192.168.0.1".parse().unwrap()).unwrap();
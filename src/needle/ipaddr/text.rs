@@ -0,0 +1,211 @@
+use std::net::Ipv4Addr;
+
+/// The ways an IPv4 address can be written out as text
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Hash)]
+pub enum IPv4Format {
+    /// 192.168.0.1
+    DottedQuad,
+    /// 192.168.000.001
+    ZeroPadded,
+    /// C0A80001
+    Hex,
+    /// 0100A8C0, as in /proc/net/tcp
+    HexLE,
+    /// 1.0.168.192.in-addr.arpa
+    ReverseDNS,
+}
+
+const REVERSE_DNS_SUFFIX: &[u8] = b".in-addr.arpa";
+
+/// The most characters any format takes up, i.e. 255.255.255.255.in-addr.arpa
+pub const MAX_CHARS: usize = 28;
+
+impl IPv4Format {
+    pub const ALL: [IPv4Format; 5] = [
+        IPv4Format::DottedQuad,
+        IPv4Format::ZeroPadded,
+        IPv4Format::Hex,
+        IPv4Format::HexLE,
+        IPv4Format::ReverseDNS,
+    ];
+
+    /// Write out an address in this format, once for each case it could be written in
+    pub fn format(self, addr: Ipv4Addr) -> Vec<String> {
+        let [a, b, c, d] = addr.octets();
+
+        match self {
+            IPv4Format::DottedQuad => vec![addr.to_string()],
+            IPv4Format::ZeroPadded => vec![format!("{a:03}.{b:03}.{c:03}.{d:03}")],
+            IPv4Format::Hex => cased(format!("{:08x}", addr.to_bits())),
+            IPv4Format::HexLE => cased(format!("{:08x}", addr.to_bits().swap_bytes())),
+            IPv4Format::ReverseDNS => vec![format!("{d}.{c}.{b}.{a}.in-addr.arpa")],
+        }
+    }
+
+    /// Read an address in this format from the start of some ASCII text, returning it and how many characters it took up
+    ///
+    /// The address has to be followed by the end of the text or by something that couldn't carry it on
+    pub fn parse(self, text: &[u8]) -> Option<(Ipv4Addr, usize)> {
+        match self {
+            IPv4Format::DottedQuad | IPv4Format::ZeroPadded => {
+                let (octets, padded, len) = dotted(text)?;
+                (padded == (self == IPv4Format::ZeroPadded)).then_some((octets.into(), len))
+            }
+            IPv4Format::Hex | IPv4Format::HexLE => {
+                let digits = text.get(..8)?;
+                let one_case = !digits.iter().any(u8::is_ascii_uppercase)
+                    || !digits.iter().any(u8::is_ascii_lowercase);
+
+                if !digits.iter().all(u8::is_ascii_hexdigit)
+                    || !one_case
+                    || self.is_continued_by(text.get(8))
+                {
+                    return None;
+                }
+
+                let bits = u32::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+                let bits = match self {
+                    IPv4Format::HexLE => bits.swap_bytes(),
+                    _ => bits,
+                };
+
+                Some((bits.into(), 8))
+            }
+            IPv4Format::ReverseDNS => {
+                let (octets, padded, len) = dotted(text)?;
+                if padded || !text[len..].starts_with(REVERSE_DNS_SUFFIX) {
+                    return None;
+                }
+
+                let [d, c, b, a] = octets;
+                Some(([a, b, c, d].into(), len + REVERSE_DNS_SUFFIX.len()))
+            }
+        }
+    }
+
+    /// Whether a character before an address in this format would really be part of it, e.g. the 2 before 192.168.0.1 in 2192.168.0.1, or the dot in 10.192.168.0.1
+    pub fn is_continued_from(self, previous: Option<&u8>) -> bool {
+        match self {
+            IPv4Format::DottedQuad | IPv4Format::ZeroPadded | IPv4Format::ReverseDNS => {
+                previous.is_some_and(|c| c.is_ascii_digit() || *c == b'.')
+            }
+            IPv4Format::Hex | IPv4Format::HexLE => previous.is_some_and(u8::is_ascii_hexdigit),
+        }
+    }
+
+    /// Whether a character following an address in this format would really be part of it
+    pub fn is_continued_by(self, next: Option<&u8>) -> bool {
+        match self {
+            IPv4Format::DottedQuad | IPv4Format::ZeroPadded => next.is_some_and(u8::is_ascii_digit),
+            IPv4Format::Hex | IPv4Format::HexLE => next.is_some_and(u8::is_ascii_hexdigit),
+            IPv4Format::ReverseDNS => false,
+        }
+    }
+}
+
+/// Both the lower and upper case forms of some hex digits, or just the one if they're all decimal
+fn cased(hex: String) -> Vec<String> {
+    let upper = hex.to_ascii_uppercase();
+
+    if upper == hex {
+        vec![hex]
+    } else {
+        vec![hex, upper]
+    }
+}
+
+/// Read four decimal octets separated by dots, returning them, whether they were zero padded, and how many characters they took up
+///
+/// Zero padding has to be all or nothing, so 192.168.0.01 isn't an address at all
+fn dotted(text: &[u8]) -> Option<([u8; 4], bool, usize)> {
+    let mut octets = [0u8; 4];
+    let mut len = 0;
+    let mut leading_zero = false;
+    let mut all_three = true;
+
+    for (i, octet) in octets.iter_mut().enumerate() {
+        if i > 0 {
+            if text.get(len) != Some(&b'.') {
+                return None;
+            }
+            len += 1;
+        }
+
+        let digits = &text[len..];
+        let width = digits
+            .iter()
+            .take(3)
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        if width == 0 {
+            return None;
+        }
+
+        let value = digits[..width]
+            .iter()
+            .fold(0u16, |value, c| value * 10 + (c - b'0') as u16);
+        *octet = u8::try_from(value).ok()?;
+
+        leading_zero |= width > 1 && digits[0] == b'0';
+        all_three &= width == 3;
+        len += width;
+    }
+
+    // A fourth digit would mean the last octet was really something bigger
+    if IPv4Format::DottedQuad.is_continued_by(text.get(len)) {
+        return None;
+    }
+
+    match (leading_zero, all_three) {
+        (false, _) => Some((octets, false, len)),
+        (true, true) => Some((octets, true, len)),
+        (true, false) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        let addr: Ipv4Addr = "192.168.0.1".parse().unwrap();
+
+        for format in IPv4Format::ALL {
+            for text in format.format(addr) {
+                assert_eq!(format.parse(text.as_bytes()), Some((addr, text.len())));
+            }
+        }
+
+        assert_eq!(
+            IPv4Format::Hex.format(addr),
+            vec!["c0a80001".to_string(), "C0A80001".to_string()]
+        );
+        assert_eq!(
+            IPv4Format::ReverseDNS.format(addr),
+            vec!["1.0.168.192.in-addr.arpa".to_string()]
+        );
+
+        // Padding is all or nothing
+        assert_eq!(IPv4Format::DottedQuad.parse(b"192.168.000.001"), None);
+        assert_eq!(IPv4Format::ZeroPadded.parse(b"192.168.0.1"), None);
+        assert_eq!(IPv4Format::DottedQuad.parse(b"192.168.0.01"), None);
+        assert_eq!(IPv4Format::ZeroPadded.parse(b"192.168.0.01"), None);
+
+        // Nothing that carries on past the address
+        assert_eq!(IPv4Format::DottedQuad.parse(b"192.168.0.1234"), None);
+        assert_eq!(IPv4Format::DottedQuad.parse(b"192.168.0.256"), None);
+        assert_eq!(IPv4Format::Hex.parse(b"C0A800012"), None);
+        assert_eq!(IPv4Format::Hex.parse(b"C0a80001"), None);
+        assert_eq!(
+            IPv4Format::DottedQuad.parse(b"192.168.0.1:80"),
+            Some((addr, 11))
+        );
+
+        // As it appears in /proc/net/tcp
+        assert_eq!(
+            IPv4Format::HexLE.parse(b"0100007F:0016"),
+            Some((Ipv4Addr::LOCALHOST, 8))
+        );
+    }
+}
@@ -1,12 +1,92 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
 use anyhow::{anyhow, Result};
 
-use crate::needle::{number::variants::IntegerVariant, Interpret, Needle, Recombobulate};
+use crate::needle::{
+    number::variants::IntegerVariant, text::TextEncoding, Interpret, Needle, Recombobulate,
+};
 
-use super::IPv4;
+use super::{
+    text::{self, IPv4Format},
+    IPv4, IPv6,
+};
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum IPv4Variant {
     Numeric(IntegerVariant),
+    /// Written out as text, e.g. "192.168.0.1" in UTF-16LE
+    Text(IPv4Format, TextEncoding, Vec<u8>),
+}
+
+impl IPv4Variant {
+    /// The most bytes a textual variant can take up, along with the character after it that shows where it ends
+    pub const MAX_TEXT_LEN: usize = (text::MAX_CHARS + 1) * 2;
+
+    pub fn byte_len(&self) -> usize {
+        self.byte_sequence().len()
+    }
+
+    pub fn byte_sequence(&self) -> &[u8] {
+        match self {
+            IPv4Variant::Numeric(v) => v.byte_sequence(),
+            IPv4Variant::Text(_, _, bytes) => bytes,
+        }
+    }
+
+    /// Every way an address can be written out as text
+    pub fn text(addr: Ipv4Addr) -> Vec<Self> {
+        let mut variants = Vec::<Self>::new();
+        let dotted_quad = addr.to_string();
+
+        for format in IPv4Format::ALL {
+            for text in format.format(addr) {
+                // Addresses like 192.168.100.200 don't look any different padded
+                if format == IPv4Format::ZeroPadded && text == dotted_quad {
+                    continue;
+                }
+
+                for encoding in TextEncoding::ALL {
+                    variants.push(IPv4Variant::Text(format, encoding, encoding.encode(&text)));
+                }
+            }
+        }
+
+        variants
+    }
+
+    /// Every textual interpretation of the bytes at the start of the data
+    pub fn interpret_text(data: &[u8]) -> Vec<Self> {
+        let mut variants = Vec::<Self>::new();
+
+        // Every format starts with a digit, so most offsets can be ruled out straight away
+        if !data.first().is_some_and(u8::is_ascii_hexdigit) {
+            return variants;
+        }
+
+        for encoding in TextEncoding::ALL {
+            let chars = encoding.decode(data, text::MAX_CHARS + 1);
+
+            for format in IPv4Format::ALL {
+                if let Some((_, len)) = format.parse(&chars) {
+                    let bytes = data[..len * encoding.unit_len()].to_vec();
+                    variants.push(IPv4Variant::Text(format, encoding, bytes));
+                }
+            }
+        }
+
+        variants
+    }
+
+    /// Whether the variant really starts and ends where its byte sequence does, rather than being part of the text around it
+    pub fn is_complete(&self, preceding: &[u8], following: &[u8]) -> bool {
+        match self {
+            IPv4Variant::Numeric(_) => true,
+            IPv4Variant::Text(format, encoding, _) => {
+                !format.is_continued_from(encoding.decode_last(preceding).as_ref())
+                    && !format.is_continued_by(encoding.decode(following, 1).first())
+            }
+        }
+    }
 }
 
 impl Recombobulate for IPv4Variant {
@@ -23,6 +103,14 @@ impl Recombobulate for IPv4Variant {
                     Err(anyhow!("Failed to recreate Needle::IpAddr from Integer"))
                 }
             }
+            IPv4Variant::Text(format, encoding, bytes) => {
+                let chars = encoding.decode(bytes, text::MAX_CHARS);
+
+                match format.parse(&chars) {
+                    Some((addr, len)) if len == chars.len() => Ok(Needle::IpAddr(IPv4::new(addr)?)),
+                    _ => Err(anyhow!("Failed to recreate Needle::IpAddr from text")),
+                }
+            }
         }
     }
 }
@@ -49,6 +137,9 @@ impl Interpret for IPv4Variant {
             }
         }
 
+        // Then as text
+        intepretations.extend(Self::interpret_text(data));
+
         if intepretations.is_empty() {
             Err(anyhow!(
                 "Failed to interpret bytes as any valid IPv4Variant!"
@@ -59,6 +150,100 @@ impl Interpret for IPv4Variant {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum IPv6Variant {
+    /// Network byte order
+    BE([u8; 16]),
+    /// All 16 bytes reversed
+    LE([u8; 16]),
+    /// Eight 16 bit words, each little endian (e.g. IN6_ADDR's Word array on Windows)
+    WordLE([u8; 16]),
+}
+
+impl IPv6Variant {
+    pub fn byte_len(&self) -> usize {
+        self.byte_sequence().len()
+    }
+
+    pub fn byte_sequence(&self) -> &[u8] {
+        match self {
+            IPv6Variant::BE(v) | IPv6Variant::LE(v) | IPv6Variant::WordLE(v) => v,
+        }
+    }
+
+    /// The address these bytes represent
+    pub fn value(&self) -> Ipv6Addr {
+        match self {
+            IPv6Variant::BE(v) => Ipv6Addr::from(*v),
+            IPv6Variant::LE(v) => Ipv6Addr::from(reversed(v)),
+            IPv6Variant::WordLE(v) => Ipv6Addr::from(word_swapped(v)),
+        }
+    }
+
+    /// Every way an address can be laid out
+    pub fn all(ipaddr: Ipv6Addr) -> impl Iterator<Item = Self> {
+        let octets = ipaddr.octets();
+
+        [
+            IPv6Variant::BE(octets),
+            IPv6Variant::LE(reversed(&octets)),
+            IPv6Variant::WordLE(word_swapped(&octets)),
+        ]
+        .into_iter()
+    }
+
+    pub fn as_be(data: &[u8]) -> Result<Self> {
+        Ok(IPv6Variant::BE(first_16(data)?))
+    }
+
+    pub fn as_le(data: &[u8]) -> Result<Self> {
+        Ok(IPv6Variant::LE(first_16(data)?))
+    }
+
+    pub fn as_word_le(data: &[u8]) -> Result<Self> {
+        Ok(IPv6Variant::WordLE(first_16(data)?))
+    }
+}
+
+fn first_16(data: &[u8]) -> Result<[u8; 16]> {
+    data.get(..16)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("Not enough bytes for an IPv6 address"))
+}
+
+fn reversed(octets: &[u8; 16]) -> [u8; 16] {
+    let mut reversed = *octets;
+    reversed.reverse();
+    reversed
+}
+
+fn word_swapped(octets: &[u8; 16]) -> [u8; 16] {
+    let mut swapped = *octets;
+    for word in swapped.chunks_exact_mut(2) {
+        word.swap(0, 1);
+    }
+    swapped
+}
+
+impl Recombobulate for IPv6Variant {
+    fn recombobulate(&self) -> Result<Needle> {
+        Ok(Needle::Ipv6Addr(IPv6::new(self.value())?))
+    }
+}
+
+impl Interpret for IPv6Variant {
+    fn interpret(data: &[u8]) -> Result<Vec<Self>>
+    where
+        Self: std::marker::Sized,
+    {
+        Ok(vec![
+            IPv6Variant::as_be(data)?,
+            IPv6Variant::as_le(data)?,
+            IPv6Variant::as_word_le(data)?,
+        ])
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -81,4 +266,29 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn ipv6_recombobulation_test() {
+        let actual_ipv6 = IPv6::new("2001:db8::8a2e:370:7334".parse().unwrap()).unwrap();
+
+        let variants = actual_ipv6.discombobulate();
+        assert_eq!(variants.len(), 3);
+
+        for variant in &variants {
+            assert_eq!(
+                variant.recombobulate().unwrap(),
+                Needle::Ipv6Addr(actual_ipv6.clone())
+            );
+        }
+
+        // Each word of 2001:0db8:... is stored little endian
+        let data = [
+            0x01, 0x20, 0xb8, 0x0d, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2e, 0x8a, 0x70, 0x03,
+            0x34, 0x73,
+        ];
+        assert_eq!(
+            IPv6Variant::as_word_le(&data).unwrap().value(),
+            actual_ipv6.value
+        );
+    }
 }
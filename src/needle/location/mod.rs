@@ -50,6 +50,10 @@ impl Location {
             })
         }
     }
+
+    pub fn tolerance(&self) -> Option<&Distance> {
+        self.tolerance.as_ref()
+    }
 }
 
 impl Matches for Location {
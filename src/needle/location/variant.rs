@@ -20,6 +20,35 @@ pub enum LocationVariant {
     // TODO: Add IntegerVariant versions too
 }
 
+impl LocationVariant {
+    pub fn byte_len(&self) -> usize {
+        match self {
+            LocationVariant::DecimalDegreesLatLon(a, b)
+            | LocationVariant::DecimalDegreesLonLat(a, b)
+            | LocationVariant::DecimalMinutesLatLon(a, b)
+            | LocationVariant::DecimalMinutesLonLat(a, b)
+            | LocationVariant::DecimalSecondsLatLon(a, b)
+            | LocationVariant::DecimalSecondsLonLat(a, b) => {
+                a.byte_sequence().len() + b.byte_sequence().len()
+            }
+        }
+    }
+
+    /// The two floats, in the order they appear in the haystack
+    pub fn byte_sequence(&self) -> Vec<u8> {
+        match self {
+            LocationVariant::DecimalDegreesLatLon(a, b)
+            | LocationVariant::DecimalDegreesLonLat(a, b)
+            | LocationVariant::DecimalMinutesLatLon(a, b)
+            | LocationVariant::DecimalMinutesLonLat(a, b)
+            | LocationVariant::DecimalSecondsLatLon(a, b)
+            | LocationVariant::DecimalSecondsLonLat(a, b) => {
+                [a.byte_sequence(), b.byte_sequence()].concat()
+            }
+        }
+    }
+}
+
 impl Recombobulate for LocationVariant {
     fn recombobulate(&self) -> Result<Needle> {
         match self {
@@ -1,25 +1,44 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
-use itertools::Itertools;
 use macaddr::MacAddr6;
-use oui_lookup::oui_db;
+use oui_lookup::{normalise_vendor, oui_db};
+use variant::MACAddrVariant;
 
-use super::{number::variants::IntegerVariant, variant::NeedleVariant, Discombobulate, Matches};
+use super::{variant::NeedleVariant, Discombobulate, Matches};
 
 pub mod oui_lookup;
+pub mod text;
 pub mod variant;
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum MACTolerance {
     SameOUI,
     SameCompany,
-    SpecificCompany(String), // This uses a case-insensitive sub-string match
+    SpecificCompany(String), // This uses a sub-string match of the normalised vendor names
+    LocallyAdministered,     // Any address with the U/L bit set, e.g. a randomised one
+    Unicast,
+    Multicast,
+    SameNIC, // The same NIC-specific lower half under any OUI, which some randomised addresses keep
+    Within(u64), // Within N of the address, e.g. for devices that were given sequential addresses
+}
+
+impl MACTolerance {
+    /// Whether the tolerance is relative to an address, rather than matching addresses on their own merits
+    pub fn needs_address(&self) -> bool {
+        matches!(
+            self,
+            MACTolerance::SameOUI
+                | MACTolerance::SameCompany
+                | MACTolerance::SameNIC
+                | MACTolerance::Within(_)
+        )
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub struct MACAddr {
-    value: Option<MacAddr6>,
-    tolerance: Option<MACTolerance>,
+    pub value: Option<MacAddr6>,
+    pub tolerance: Option<MACTolerance>,
 }
 
 impl MACAddr {
@@ -43,6 +62,23 @@ impl MACAddr {
             tolerance: Some(MACTolerance::SpecificCompany(company.to_owned())),
         })
     }
+
+    /// Any address at all that falls within a tolerance, which can't be relative to an address
+    pub fn any(tolerance: MACTolerance) -> Result<Self> {
+        if tolerance.needs_address() {
+            return Err(anyhow!("{:?} needs an address to compare with", tolerance));
+        }
+
+        Ok(Self {
+            value: None,
+            tolerance: Some(tolerance),
+        })
+    }
+
+    /// Whether it only matches a single address, so it can be discombobulated
+    pub fn is_exact(&self) -> bool {
+        self.value.is_some() && self.tolerance.is_none()
+    }
 }
 
 impl Matches for MACAddr {
@@ -50,86 +86,81 @@ impl Matches for MACAddr {
         // If rhs has a tolerance, check that lhs falls wthin it
         match &rhs.tolerance {
             Some(tolerance) => match tolerance {
-                MACTolerance::SameOUI => {
-                    //self.value.as_bytes()[..3] == rhs.value.as_bytes()[..3],
-                    if let Some(lhs_value) = self.value {
-                        if let Some(rhs_value) = rhs.value {
-                            return lhs_value.as_bytes()[..3] == rhs_value.as_bytes()[..3];
-                        }
+                MACTolerance::SameOUI => match (self.value, rhs.value) {
+                    (Some(lhs_value), Some(rhs_value)) => {
+                        lhs_value.as_bytes()[..3] == rhs_value.as_bytes()[..3]
                     }
-                    false
-                }
+                    _ => false,
+                },
                 MACTolerance::SameCompany => {
-                    if let Some(lhs_value) = self.value {
-                        if let Some(rhs_value) = rhs.value {
-                            // TODO: Avoid having to reload the entire database every time!
-                            if let Ok(oui_db) = oui_db() {
-                                //Oui::default() {
-                                if let Ok(Some(lhs_info)) =
-                                    oui_db.lookup_by_mac(&lhs_value.to_string())
-                                {
-                                    if let Ok(Some(rhs_info)) =
-                                        oui_db.lookup_by_mac(&rhs_value.to_string())
-                                    {
-                                        // println!("lhs: {:?}", &lhs_info);
-                                        // println!("rhs {:?}", &rhs_info);
-                                        return lhs_info.company_name == rhs_info.company_name;
-                                    }
-                                }
-                            }
+                    let oui_db = oui_db();
+
+                    // The same company's name is often written slightly differently on each of its assignments
+                    match (
+                        self.value.and_then(|value| oui_db.lookup(value)),
+                        rhs.value.and_then(|value| oui_db.lookup(value)),
+                    ) {
+                        (Some(lhs_vendor), Some(rhs_vendor)) => {
+                            lhs_vendor.normalised_name() == rhs_vendor.normalised_name()
                         }
+                        _ => false,
                     }
-
-                    false
                 }
-                MACTolerance::SpecificCompany(company_name) => {
-                    if let Some(lhs_value) = self.value {
-                        if let Ok(oui_db) = oui_db() {
-                            // Oui::default() {
-                            if let Ok(Some(lhs_info)) = oui_db.lookup_by_mac(&lhs_value.to_string())
-                            {
-                                println!("lhs: {:?}", &lhs_info);
-                                println!("rhs {:?}", &company_name);
-                                return lhs_info
-                                    .company_name
-                                    .to_lowercase()
-                                    .contains(&company_name.to_lowercase()); //&lhs_info.company_name == company_name;
-                            }
-                        }
-                    }
-
-                    false
+                MACTolerance::SpecificCompany(company_name) => self
+                    .value
+                    .and_then(|value| oui_db().lookup(value))
+                    .is_some_and(|vendor| {
+                        vendor
+                            .normalised_name()
+                            .contains(&normalise_vendor(company_name))
+                    }),
+                MACTolerance::LocallyAdministered => {
+                    self.value.is_some_and(|value| value.is_local())
                 }
+                MACTolerance::Unicast => self.value.is_some_and(|value| value.is_unicast()),
+                MACTolerance::Multicast => self.value.is_some_and(|value| value.is_multicast()),
+                MACTolerance::SameNIC => match (self.value, rhs.value) {
+                    (Some(lhs_value), Some(rhs_value)) => {
+                        lhs_value.as_bytes()[3..] == rhs_value.as_bytes()[3..]
+                    }
+                    _ => false,
+                },
+                MACTolerance::Within(distance) => match (self.value, rhs.value) {
+                    (Some(lhs_value), Some(rhs_value)) => {
+                        to_bits(lhs_value).abs_diff(to_bits(rhs_value)) <= *distance
+                    }
+                    _ => false,
+                },
             },
             None => self.value == rhs.value,
         }
     }
 }
 
+/// The address as a u48, so that addresses can be compared numerically
+pub fn to_bits(macaddr: MacAddr6) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes[2..].copy_from_slice(macaddr.as_bytes());
+    u64::from_be_bytes(bytes)
+}
+
 impl Discombobulate for MACAddr {
     fn discombobulate(&self) -> Vec<NeedleVariant> {
-        let mut variants = Vec::<NeedleVariant>::new();
-
-        if let Some(value) = self.value {
-            // u48 little endian
-            let rev = value.as_bytes().iter().rev().cloned().collect_vec();
-            if let Ok(v) = IntegerVariant::as_u48_le(&rev) {
-                variants.push(NeedleVariant::Integer(v));
-            }
-
-            // u48 big endian
-            if let Ok(v) = IntegerVariant::as_u48_be(value.as_bytes()) {
-                variants.push(NeedleVariant::Integer(v));
-            }
+        match self.value {
+            Some(value) => MACAddrVariant::all(value)
+                .into_iter()
+                .map(NeedleVariant::MacAddr)
+                .collect(),
+            None => Vec::new(),
         }
-
-        variants
     }
 }
 
 #[cfg(test)]
 mod tests {
 
+    use crate::needle::variant::family;
+
     use super::*;
 
     #[test]
@@ -187,6 +218,44 @@ mod tests {
         assert!(!actual.matches(&target));
     }
 
+    #[test]
+    fn mac_tolerance_test() {
+        let randomised = MACAddr::new("DA:A1:19:11:22:33".parse().unwrap()).unwrap();
+        let burned_in = MACAddr::new("E0:8F:4C:11:22:33".parse().unwrap()).unwrap();
+        let multicast = MACAddr::new("01:00:5E:00:00:FB".parse().unwrap()).unwrap();
+
+        let local = MACAddr::any(MACTolerance::LocallyAdministered).unwrap();
+        assert!(randomised.matches(&local));
+        assert!(!burned_in.matches(&local));
+
+        let unicast = MACAddr::any(MACTolerance::Unicast).unwrap();
+        assert!(burned_in.matches(&unicast));
+        assert!(!multicast.matches(&unicast));
+        assert!(multicast.matches(&MACAddr::any(MACTolerance::Multicast).unwrap()));
+
+        // A randomised address that kept the burned in one's lower half
+        let same_nic =
+            MACAddr::with_tolerance("E0:8F:4C:11:22:33".parse().unwrap(), MACTolerance::SameNIC)
+                .unwrap();
+        assert!(randomised.matches(&same_nic));
+        assert!(!multicast.matches(&same_nic));
+
+        // Sequentially allocated, including across the NIC-specific part overflowing
+        let within = MACAddr::with_tolerance(
+            "E0:8F:4C:FF:FF:F0".parse().unwrap(),
+            MACTolerance::Within(0x20),
+        )
+        .unwrap();
+        let next = MACAddr::new("E0:8F:4D:00:00:0F".parse().unwrap()).unwrap();
+        let too_far = MACAddr::new("E0:8F:4D:00:00:11".parse().unwrap()).unwrap();
+        assert!(next.matches(&within));
+        assert!(!too_far.matches(&within));
+
+        // Tolerances relative to an address need one
+        assert!(MACAddr::any(MACTolerance::SameNIC).is_err());
+        assert!(MACAddr::any(MACTolerance::Within(1)).is_err());
+    }
+
     #[test]
     fn discombobulation_test() {
         let actual = MACAddr::new("11:22:33:44:55:66".parse().unwrap()).unwrap();
@@ -196,5 +265,9 @@ mod tests {
         for variant in &variants {
             println!("{:?}", variant);
         }
+
+        assert!(variants
+            .iter()
+            .all(|variant| variant.family() == family::MACADDR));
     }
 }
@@ -1,10 +1,295 @@
-use std::sync::OnceLock;
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, OnceLock, RwLock},
+};
 
+use anyhow::{anyhow, Context, Result};
 use mac_oui::Oui;
+use macaddr::MacAddr6;
 
-// OnceLock lets us load the DB once and cache the results for future calls
-pub fn oui_db() -> &'static Result<Oui, String> {
-    static OUI: OnceLock<Result<Oui, String>> = OnceLock::new();
+use super::to_bits;
 
-    OUI.get_or_init(Oui::default)
+/// How big a block of MAC addresses the IEEE assigned
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Hash)]
+pub enum Block {
+    /// MA-L, a whole OUI
+    MAL,
+    /// MA-M, a 28 bit prefix
+    MAM,
+    /// MA-S (or the older IAB), a 36 bit prefix
+    MAS,
+}
+
+impl Block {
+    /// Longest prefix first, which is the order they're looked up in
+    pub const ALL: [Block; 3] = [Block::MAS, Block::MAM, Block::MAL];
+
+    pub fn prefix_len(self) -> u32 {
+        match self {
+            Block::MAL => 24,
+            Block::MAM => 28,
+            Block::MAS => 36,
+        }
+    }
+
+    /// The block for a registry name, as used in the IEEE's files
+    pub fn from_registry(registry: &str) -> Option<Self> {
+        match registry {
+            "MA-L" => Some(Block::MAL),
+            "MA-M" => Some(Block::MAM),
+            "MA-S" | "IAB" => Some(Block::MAS),
+            _ => None,
+        }
+    }
+}
+
+/// Who a block of MAC addresses was assigned to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Vendor {
+    pub name: String,
+    pub block: Block,
+}
+
+impl Vendor {
+    /// The vendor's name, normalised so it can be compared with the names on its other assignments
+    pub fn normalised_name(&self) -> String {
+        normalise_vendor(&self.name)
+    }
+}
+
+/// Take out the case, punctuation and company suffixes that vary between a vendor's assignments, e.g. "Intel Corp" and "INTEL CORPORATION" are both "INTEL"
+pub fn normalise_vendor(name: &str) -> String {
+    const SUFFIXES: &[&str] = &[
+        "AB",
+        "AG",
+        "BV",
+        "CO",
+        "COMPANY",
+        "CORP",
+        "CORPORATE",
+        "CORPORATION",
+        "GMBH",
+        "INC",
+        "INCORPORATED",
+        "KK",
+        "LIMITED",
+        "LLC",
+        "LTD",
+        "LTDA",
+        "OY",
+        "PLC",
+        "PTY",
+        "SA",
+        "SPA",
+        "SRL",
+    ];
+
+    // Dots are dropped rather than split on, so that S.A. is still a suffix
+    let mut words = name
+        .replace('.', "")
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_uppercase)
+        .collect::<Vec<_>>();
+
+    while words.len() > 1
+        && words
+            .last()
+            .is_some_and(|word| SUFFIXES.contains(&word.as_str()))
+    {
+        words.pop();
+    }
+
+    words.join(" ")
+}
+
+/// The vendors blocks of MAC addresses were assigned to, looked up by the longest prefix that matches
+///
+/// Assignments loaded from the IEEE's registry files take precedence over the database bundled with mac_oui
+pub struct OuiDatabase {
+    blocks: HashMap<(Block, u64), String>,
+    bundled: bool,
+}
+
+impl OuiDatabase {
+    /// A database that doesn't know about any vendors, for when only the IEEE's files should be used
+    pub fn empty() -> Self {
+        Self {
+            blocks: HashMap::new(),
+            bundled: false,
+        }
+    }
+
+    /// The database bundled with mac_oui
+    pub fn bundled() -> Self {
+        Self {
+            blocks: HashMap::new(),
+            bundled: true,
+        }
+    }
+
+    /// Add the assignments from one of the IEEE's registry files (oui.csv, mam.csv, oui36.csv or iab.csv), returning how many there were
+    pub fn load_ieee_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<usize> {
+        let path = path.as_ref();
+        let mut reader = csv::Reader::from_path(path)
+            .with_context(|| format!("Failed to open OUI database {}", path.display()))?;
+        let mut count = 0;
+
+        for record in reader.records() {
+            let record = record?;
+
+            // Registry,Assignment,Organization Name,Organization Address
+            let (Some(registry), Some(assignment), Some(name)) =
+                (record.get(0), record.get(1), record.get(2))
+            else {
+                return Err(anyhow!("Malformed OUI record: {:?}", record));
+            };
+
+            let block = Block::from_registry(registry.trim())
+                .ok_or_else(|| anyhow!("Unknown OUI registry: {}", registry))?;
+            let assignment = assignment.trim();
+            if assignment.len() as u32 * 4 != block.prefix_len() {
+                return Err(anyhow!(
+                    "{} isn't a valid {:?} assignment",
+                    assignment,
+                    block
+                ));
+            }
+
+            let prefix = u64::from_str_radix(assignment, 16)?;
+            self.blocks.insert((block, prefix), name.trim().to_string());
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Who a MAC address was assigned to, if anyone
+    pub fn lookup(&self, macaddr: MacAddr6) -> Option<Vendor> {
+        let bits = to_bits(macaddr);
+
+        for block in Block::ALL {
+            if let Some(name) = self.blocks.get(&(block, bits >> (48 - block.prefix_len()))) {
+                return Some(Vendor {
+                    name: name.clone(),
+                    block,
+                });
+            }
+        }
+
+        if !self.bundled {
+            return None;
+        }
+
+        let entry = bundled()?.lookup_by_mac(&macaddr.to_string()).ok()??;
+
+        Some(Vendor {
+            name: entry.company_name.clone(),
+            block: Block::from_registry(&entry.assignment_block_size).unwrap_or(Block::MAL),
+        })
+    }
+}
+
+// OnceLock lets us load the bundled DB once and cache it for future calls
+fn bundled() -> Option<&'static Oui> {
+    static OUI: OnceLock<Option<Oui>> = OnceLock::new();
+
+    OUI.get_or_init(|| Oui::default().ok()).as_ref()
+}
+
+fn current() -> &'static RwLock<Arc<OuiDatabase>> {
+    static DB: OnceLock<RwLock<Arc<OuiDatabase>>> = OnceLock::new();
+
+    DB.get_or_init(|| RwLock::new(Arc::new(OuiDatabase::bundled())))
+}
+
+/// The database every vendor lookup uses, which is the bundled one until it's replaced
+pub fn oui_db() -> Arc<OuiDatabase> {
+    match current().read() {
+        Ok(db) => db.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// Replace the database every vendor lookup uses, e.g. with the IEEE's latest registry files
+pub fn set_oui_db(db: OuiDatabase) {
+    match current().write() {
+        Ok(mut current) => *current = Arc::new(db),
+        Err(poisoned) => *poisoned.into_inner() = Arc::new(db),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ieee_csv_test() {
+        let dir = std::env::temp_dir();
+        let files = [
+            (
+                "bth_oui_test.csv",
+                "Registry,Assignment,Organization Name,Organization Address\n\
+                 MA-L,70B3D5,IEEE Registration Authority,445 Hoes Lane Piscataway NJ US 08554\n\
+                 MA-L,E08F4C,Intel Corporate,Lot 8 Jalan Hi-Tech 2/3 Kulim Kedah MY 09000\n",
+            ),
+            (
+                "bth_mam_test.csv",
+                "Registry,Assignment,Organization Name,Organization Address\n\
+                 MA-M,70B3D5A,\"Example Widgets, Ltd.\",1 Example Road Exampleton GB\n",
+            ),
+            (
+                "bth_oui36_test.csv",
+                "Registry,Assignment,Organization Name,Organization Address\n\
+                 MA-S,70B3D5F2F,\"Acme Sensors, Inc.\",2 Example Road Exampleton US\n",
+            ),
+        ];
+
+        let mut db = OuiDatabase::empty();
+        for (name, contents) in files {
+            let path = dir.join(name);
+            std::fs::write(&path, contents).unwrap();
+            assert!(db.load_ieee_csv(&path).unwrap() > 0);
+        }
+
+        let lookup = |macaddr: &str| db.lookup(macaddr.parse().unwrap());
+
+        // The longest prefix wins
+        assert_eq!(
+            lookup("70:B3:D5:F2:F1:23"),
+            Some(Vendor {
+                name: "Acme Sensors, Inc.".to_string(),
+                block: Block::MAS
+            })
+        );
+        assert_eq!(lookup("70:B3:D5:A1:23:45").unwrap().block, Block::MAM);
+        assert_eq!(
+            lookup("70:B3:D5:11:23:45").unwrap().name,
+            "IEEE Registration Authority"
+        );
+        assert_eq!(lookup("00:00:5E:00:53:01"), None);
+
+        // The IEEE and bundled databases name Intel differently
+        let ieee = lookup("E0:8F:4C:11:22:33").unwrap();
+        let bundled = OuiDatabase::bundled()
+            .lookup("E0:8F:4C:11:22:33".parse().unwrap())
+            .unwrap();
+        assert_ne!(ieee.name, bundled.name);
+        assert_eq!(ieee.normalised_name(), bundled.normalised_name());
+
+        assert!(db.load_ieee_csv(dir.join("bth_missing_oui.csv")).is_err());
+    }
+
+    #[test]
+    fn normalise_test() {
+        assert_eq!(normalise_vendor("Google, Inc."), "GOOGLE");
+        assert_eq!(normalise_vendor("Cisco Systems, Inc"), "CISCO SYSTEMS");
+        assert_eq!(
+            normalise_vendor("HUAWEI TECHNOLOGIES CO.,LTD"),
+            "HUAWEI TECHNOLOGIES"
+        );
+        assert_eq!(normalise_vendor("Telefonica S.A."), "TELEFONICA");
+        assert_eq!(normalise_vendor("Corp"), "CORP");
+    }
 }
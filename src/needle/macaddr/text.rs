@@ -0,0 +1,152 @@
+use macaddr::MacAddr6;
+
+/// The ways a MAC address can be written out as text
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Hash)]
+pub enum MACFormat {
+    /// aa:bb:cc:dd:ee:ff
+    Colon,
+    /// aa-bb-cc-dd-ee-ff
+    Hyphen,
+    /// aabb.ccdd.eeff, as Cisco writes them
+    Cisco,
+    /// aabbccddeeff
+    Bare,
+}
+
+/// The most characters any format takes up
+pub const MAX_CHARS: usize = 17;
+
+impl MACFormat {
+    pub const ALL: [MACFormat; 4] = [
+        MACFormat::Colon,
+        MACFormat::Hyphen,
+        MACFormat::Cisco,
+        MACFormat::Bare,
+    ];
+
+    /// How many hex digits there are between separators, and the separator itself
+    fn groups(self) -> (usize, Option<u8>) {
+        match self {
+            MACFormat::Colon => (2, Some(b':')),
+            MACFormat::Hyphen => (2, Some(b'-')),
+            MACFormat::Cisco => (4, Some(b'.')),
+            MACFormat::Bare => (12, None),
+        }
+    }
+
+    fn len(self) -> usize {
+        let (width, separator) = self.groups();
+        12 + separator.map_or(0, |_| 12 / width - 1)
+    }
+
+    /// Write out an address in this format, in both lower and upper case
+    pub fn format(self, macaddr: MacAddr6) -> Vec<String> {
+        let (width, separator) = self.groups();
+        let hex = macaddr
+            .as_bytes()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>();
+
+        let lower = match separator {
+            Some(separator) => hex
+                .as_bytes()
+                .chunks(width)
+                .map(String::from_utf8_lossy)
+                .collect::<Vec<_>>()
+                .join(&char::from(separator).to_string()),
+            None => hex,
+        };
+        let upper = lower.to_ascii_uppercase();
+
+        if upper == lower {
+            vec![lower]
+        } else {
+            vec![lower, upper]
+        }
+    }
+
+    /// Read an address in this format from the start of some ASCII text, returning it and how many characters it took up
+    ///
+    /// The address has to be in a single case, and be followed by the end of the text or something that isn't a hex digit
+    pub fn parse(self, text: &[u8]) -> Option<(MacAddr6, usize)> {
+        let (width, separator) = self.groups();
+        let len = self.len();
+        let mut digits = Vec::<u8>::with_capacity(12);
+        for (i, c) in text.get(..len)?.iter().enumerate() {
+            match separator {
+                Some(separator) if i % (width + 1) == width => {
+                    if *c != separator {
+                        return None;
+                    }
+                }
+                _ if c.is_ascii_hexdigit() => digits.push(*c),
+                _ => return None,
+            }
+        }
+
+        let one_case = !digits.iter().any(u8::is_ascii_uppercase)
+            || !digits.iter().any(u8::is_ascii_lowercase);
+        if !one_case || Self::is_continued_by(text.get(len)) {
+            return None;
+        }
+
+        let mut bytes = [0u8; 6];
+        for (byte, pair) in bytes.iter_mut().zip(digits.chunks_exact(2)) {
+            *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+        }
+
+        Some((bytes.into(), len))
+    }
+
+    /// Whether a character before an address in this format would really be part of it, e.g. the end of a longer hex string such as a hash
+    pub fn is_continued_from(self, previous: Option<&u8>) -> bool {
+        let (_, separator) = self.groups();
+        previous.is_some_and(|c| c.is_ascii_hexdigit() || Some(*c) == separator)
+    }
+
+    /// Whether a character following an address would really be part of it
+    pub fn is_continued_by(next: Option<&u8>) -> bool {
+        next.is_some_and(u8::is_ascii_hexdigit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        let macaddr: MacAddr6 = "AA:BB:CC:DD:EE:FF".parse().unwrap();
+
+        for format in MACFormat::ALL {
+            for text in format.format(macaddr) {
+                assert_eq!(format.parse(text.as_bytes()), Some((macaddr, text.len())));
+            }
+        }
+
+        assert_eq!(
+            MACFormat::Cisco.format(macaddr),
+            vec!["aabb.ccdd.eeff".to_string(), "AABB.CCDD.EEFF".to_string()]
+        );
+        assert_eq!(
+            MACFormat::Hyphen.format(macaddr)[1],
+            "AA-BB-CC-DD-EE-FF".to_string()
+        );
+
+        assert_eq!(MACFormat::Colon.parse(b"aa:bb:cc:dd:ee:fF"), None);
+        assert_eq!(MACFormat::Colon.parse(b"aa:bb:cc:dd:ee:ff0"), None);
+        assert_eq!(MACFormat::Colon.parse(b"aa-bb-cc-dd-ee-ff"), None);
+        assert_eq!(MACFormat::Bare.parse(b"aabbccddeef"), None);
+        assert_eq!(
+            MACFormat::Bare.parse(b"aabbccddeeff\""),
+            Some((macaddr, 12))
+        );
+
+        assert!(MACFormat::Bare.is_continued_from(Some(&b'0')));
+        assert!(MACFormat::Colon.is_continued_from(Some(&b':')));
+        assert!(!MACFormat::Colon.is_continued_from(Some(&b'-')));
+        assert!(!MACFormat::Cisco.is_continued_from(Some(&b'=')));
+        assert!(!MACFormat::Bare.is_continued_from(None));
+    }
+}
@@ -1,42 +1,94 @@
 use anyhow::{anyhow, Result};
 use macaddr::MacAddr6;
 
-use crate::needle::{number::variants::IntegerVariant, Interpret, Needle, Recombobulate};
+use crate::needle::{text::TextEncoding, Interpret, Needle, Recombobulate};
 
-use super::MACAddr;
+use super::{
+    text::{self, MACFormat},
+    MACAddr,
+};
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum MACAddrVariant {
-    Numeric(IntegerVariant),
-    // LE([u8; 6]),
-    // BE([u8; 6]),
+    /// All six bytes reversed
+    LE([u8; 6]),
+    /// Network byte order, as on the wire
+    BE([u8; 6]),
+    /// Written out as text, e.g. "aa:bb:cc:dd:ee:ff" in UTF-16LE
+    Text(MACFormat, TextEncoding, Vec<u8>),
 }
 
-const MAC_ADDR_MAX: i64 = 281474976710655; // FF:FF:FF:FF:FF:FF
+impl MACAddrVariant {
+    /// The most bytes a textual variant can take up, along with the character after it that shows where it ends
+    pub const MAX_TEXT_LEN: usize = (text::MAX_CHARS + 1) * 2;
+
+    pub fn byte_len(&self) -> usize {
+        self.byte_sequence().len()
+    }
+
+    pub fn byte_sequence(&self) -> &[u8] {
+        match self {
+            MACAddrVariant::LE(v) | MACAddrVariant::BE(v) => v,
+            MACAddrVariant::Text(_, _, bytes) => bytes,
+        }
+    }
+
+    /// Every way a MAC address can be laid out
+    pub fn all(macaddr: MacAddr6) -> Vec<Self> {
+        let mut reversed = macaddr.into_array();
+        reversed.reverse();
+
+        let mut variants = vec![
+            MACAddrVariant::LE(reversed),
+            MACAddrVariant::BE(macaddr.into_array()),
+        ];
+
+        for format in MACFormat::ALL {
+            for text in format.format(macaddr) {
+                for encoding in TextEncoding::ALL {
+                    variants.push(MACAddrVariant::Text(
+                        format,
+                        encoding,
+                        encoding.encode(&text),
+                    ));
+                }
+            }
+        }
+
+        variants
+    }
+
+    /// Whether the variant really starts and ends where its byte sequence does, rather than being part of the text around it
+    pub fn is_complete(&self, preceding: &[u8], following: &[u8]) -> bool {
+        match self {
+            MACAddrVariant::Text(format, encoding, _) => {
+                !format.is_continued_from(encoding.decode_last(preceding).as_ref())
+                    && !MACFormat::is_continued_by(encoding.decode(following, 1).first())
+            }
+            _ => true,
+        }
+    }
+}
 
 impl Recombobulate for MACAddrVariant {
     fn recombobulate(&self) -> Result<Needle> {
         match self {
-            MACAddrVariant::Numeric(v) => {
-                if let Ok(Needle::Integer(integer)) = v.recombobulate() {
-                    if (0i64..=MAC_ADDR_MAX).contains(&integer.value) {
-                        let a = ((integer.value as u64) >> 5) as u8;
-                        let b = ((integer.value as u64) >> 4) as u8;
-                        let c = ((integer.value as u64) >> 3) as u8;
-                        let d = ((integer.value as u64) >> 2) as u8;
-                        let e = ((integer.value as u64) >> 1) as u8;
-                        let f = (integer.value as u64) as u8;
-                        Ok(Needle::MacAddr(MACAddr::new(MacAddr6::new(
-                            a, b, c, d, e, f,
-                        ))?))
-                    } else {
-                        Err(anyhow!("Failed to recreate Needle::MacAddr from Integer"))
+            MACAddrVariant::LE(v) => {
+                let mut bytes = *v;
+                bytes.reverse();
+                Ok(Needle::MacAddr(MACAddr::new(bytes.into())?))
+            }
+            MACAddrVariant::BE(v) => Ok(Needle::MacAddr(MACAddr::new((*v).into())?)),
+            MACAddrVariant::Text(format, encoding, bytes) => {
+                let chars = encoding.decode(bytes, text::MAX_CHARS);
+
+                match format.parse(&chars) {
+                    Some((macaddr, len)) if len == chars.len() => {
+                        Ok(Needle::MacAddr(MACAddr::new(macaddr)?))
                     }
-                } else {
-                    Err(anyhow!("Failed to recreate Needle::MacAddr from Integer"))
+                    _ => Err(anyhow!("Failed to recreate Needle::MacAddr from text")),
                 }
-            } // MACAddrVariant::LE(v) => todo!(),
-              // MACAddrVariant::BE(v) => todo!(),
+            }
         }
     }
 }
@@ -48,15 +100,23 @@ impl Interpret for MACAddrVariant {
     {
         let mut intepretations = Vec::<Self>::new();
 
-        // First, interpret as integers
-        if let Ok(integer_variants) = IntegerVariant::interpret(data) {
-            for variant in &integer_variants {
-                // Only keep valid u48 variants
-                if matches!(
-                    variant,
-                    IntegerVariant::U48LE((_, _)) | IntegerVariant::U48BE((_, _))
-                ) {
-                    intepretations.push(MACAddrVariant::Numeric(variant.clone()))
+        // The same six bytes in either order
+        if let Some(bytes) = data.get(..6) {
+            let bytes: [u8; 6] = bytes.try_into()?;
+            intepretations.push(MACAddrVariant::LE(bytes));
+            intepretations.push(MACAddrVariant::BE(bytes));
+        }
+
+        // Then as text, which always starts with a hex digit
+        if data.first().is_some_and(u8::is_ascii_hexdigit) {
+            for encoding in TextEncoding::ALL {
+                let chars = encoding.decode(data, text::MAX_CHARS + 1);
+
+                for format in MACFormat::ALL {
+                    if let Some((_, len)) = format.parse(&chars) {
+                        let bytes = data[..len * encoding.unit_len()].to_vec();
+                        intepretations.push(MACAddrVariant::Text(format, encoding, bytes));
+                    }
                 }
             }
         }
@@ -82,15 +142,16 @@ mod tests {
     fn recombobulation_test() {
         let actual_macaddr = MACAddr::new("AA:BB:CC:DD:EE:FF".parse().unwrap()).unwrap();
 
-        println!("Actual             : {:02x?}", actual_macaddr);
-
         let variants = actual_macaddr.discombobulate();
+        assert_eq!(variants.len(), 2 + 4 * 2 * 2);
 
         for variant in &variants {
-            println!("Discombobulated as : {:02x?}", &variant);
-            if let Ok(Needle::MacAddr(recom_macaddr)) = variant.recombobulate() {
-                assert_eq!(actual_macaddr, recom_macaddr);
-            }
+            assert_eq!(
+                variant.recombobulate().unwrap(),
+                Needle::MacAddr(actual_macaddr.clone()),
+                "{:02x?}",
+                variant
+            );
         }
     }
 }
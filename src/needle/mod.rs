@@ -1,20 +1,30 @@
+pub mod custom;
 pub mod ipaddr;
 pub mod location;
 pub mod macaddr;
 pub mod number;
+pub mod pattern;
+pub mod sockaddr;
+pub mod string;
+pub mod text;
 pub mod timestamp;
+pub mod uuid;
 pub mod variant;
 
-use std::net::Ipv4Addr;
+use std::{
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    ops::RangeInclusive,
+};
 
 use ::macaddr::MacAddr6;
 use anyhow::{anyhow, Result};
 use location::variant::LocationVariant;
 use macaddr::MACTolerance;
 use measurements::Distance;
-use time::{format_description, Duration, PrimitiveDateTime};
+use time::{Duration, OffsetDateTime, UtcOffset};
 
 use self::{
+    custom::{BoxedNeedle, CustomNeedle},
     number::variants::{FloatVariant, IntegerVariant},
     timestamp::{variants::TimestampVariant, Timestamp},
     variant::NeedleVariant,
@@ -23,16 +33,33 @@ use self::{
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum Needle {
     Bytes(Vec<u8>),
+    Pattern(pattern::BytePattern),
     Integer(number::Integer),
     Float(number::Float),
     Timestamp(timestamp::Timestamp),
     Location(location::Location),
     IpAddr(ipaddr::IPv4),
+    Ipv6Addr(ipaddr::IPv6),
     MacAddr(macaddr::MACAddr),
+    SockAddr(sockaddr::SockAddr),
+    String(string::Str),
+    Uuid(uuid::UUID),
+    Custom(BoxedNeedle),
 }
 
 impl Needle {
     // Bytes creation
+    pub fn new_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.is_empty() {
+            return Err(anyhow!("Can't search for an empty sequence of bytes"));
+        }
+
+        Ok(Needle::Bytes(bytes.to_vec()))
+    }
+
+    pub fn new_pattern(hex: &str) -> Result<Self> {
+        Ok(Needle::Pattern(pattern::BytePattern::new(hex)?))
+    }
 
     // Integer creation
     pub fn new_integer(value: i64) -> Result<Self> {
@@ -58,27 +85,33 @@ impl Needle {
 
     // Timestamp creation
     pub fn new_timestamp(dtg: &str) -> Result<Self> {
-        let format =
-            format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second]").unwrap();
-
-        if let Ok(datetime) = PrimitiveDateTime::parse(dtg, &format) {
-            Ok(Self::Timestamp(Timestamp::new(datetime)))
-        } else {
-            Err(anyhow!("Failed to parse timestamp string: {}", dtg))
-        }
+        Ok(Self::Timestamp(Timestamp::new(Timestamp::parse(dtg)?)))
     }
 
     pub fn new_timestamp_with_tolerance(dtg: &str, tolerance: Duration) -> Result<Self> {
-        let format =
-            format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second]").unwrap();
+        Ok(Self::Timestamp(Timestamp::with_tolerance(
+            Timestamp::parse(dtg)?,
+            tolerance,
+        )))
+    }
 
-        if let Ok(datetime) = PrimitiveDateTime::parse(dtg, &format) {
-            Ok(Self::Timestamp(Timestamp::with_tolerance(
-                datetime, tolerance,
-            )))
-        } else {
-            Err(anyhow!("Failed to parse timestamp string: {}", dtg))
+    /// A timestamp that may have been stored as the local time it has an offset for, or in UTC
+    pub fn new_timestamp_with_offset(dtg: OffsetDateTime) -> Result<Self> {
+        Ok(Self::Timestamp(Timestamp::with_offset(dtg)))
+    }
+
+    /// A UTC timestamp that may have been stored as local time in any of the given offsets, e.g. `Timestamp::whole_hour_offsets()`
+    pub fn new_timestamp_with_offsets(dtg: &str, offsets: &[UtcOffset]) -> Result<Self> {
+        if offsets.is_empty() {
+            return Err(anyhow!(
+                "A timestamp needs at least one UTC offset to be in"
+            ));
         }
+
+        Ok(Self::Timestamp(Timestamp::with_offsets(
+            Timestamp::parse(dtg)?,
+            offsets,
+        )))
     }
 
     // Location creation
@@ -104,6 +137,48 @@ impl Needle {
         )?))
     }
 
+    pub fn new_ipv6_address(ipaddr: Ipv6Addr) -> Result<Self> {
+        Ok(Needle::Ipv6Addr(ipaddr::IPv6::new(ipaddr)?))
+    }
+
+    pub fn new_ipv6_address_with_tolerance(ipaddr: Ipv6Addr, prefix_len: u8) -> Result<Self> {
+        Ok(Needle::Ipv6Addr(ipaddr::IPv6::with_tolerance(
+            ipaddr, prefix_len,
+        )?))
+    }
+
+    // Socket address creation
+    pub fn new_socket_address(sockaddr: SocketAddr) -> Result<Self> {
+        Ok(Needle::SockAddr(sockaddr::SockAddr::new(sockaddr)?))
+    }
+
+    pub fn new_socket_address_with_tolerance(
+        sockaddr: SocketAddr,
+        prefix_len: u8,
+        ports: RangeInclusive<u16>,
+    ) -> Result<Self> {
+        Ok(Needle::SockAddr(sockaddr::SockAddr::with_tolerance(
+            sockaddr, prefix_len, ports,
+        )?))
+    }
+
+    // String creation
+    pub fn new_string(value: &str) -> Result<Self> {
+        Ok(Needle::String(string::Str::new(value)?))
+    }
+
+    pub fn new_string_with_options(
+        value: &str,
+        case_insensitive: bool,
+        null_terminated: bool,
+    ) -> Result<Self> {
+        Ok(Needle::String(string::Str::with_options(
+            value,
+            case_insensitive,
+            null_terminated,
+        )?))
+    }
+
     // MAC Address creation
     pub fn new_mac_address(macaddr: MacAddr6) -> Result<Self> {
         Ok(Needle::MacAddr(macaddr::MACAddr::new(macaddr)?))
@@ -121,6 +196,53 @@ impl Needle {
     pub fn any_mac_address_for_company(company: &str) -> Result<Self> {
         Ok(Needle::MacAddr(macaddr::MACAddr::with_company(company)?))
     }
+
+    pub fn any_mac_address(tolerance: MACTolerance) -> Result<Self> {
+        Ok(Needle::MacAddr(macaddr::MACAddr::any(tolerance)?))
+    }
+
+    // UUID creation
+    pub fn new_uuid(uuid: ::uuid::Uuid) -> Result<Self> {
+        Ok(Needle::Uuid(uuid::UUID::new(uuid)?))
+    }
+
+    pub fn any_uuid(version: Option<usize>, variant: Option<::uuid::Variant>) -> Result<Self> {
+        Ok(Needle::Uuid(uuid::UUID::any(version, variant)?))
+    }
+
+    // Custom creation
+    pub fn custom<N: CustomNeedle>(needle: N) -> Self {
+        Needle::Custom(BoxedNeedle::new(needle))
+    }
+
+    /// The custom needle inside, if it's of the given type
+    pub fn as_custom<N: CustomNeedle>(&self) -> Option<&N> {
+        match self {
+            Needle::Custom(custom) => custom.downcast_ref(),
+            _ => None,
+        }
+    }
+
+    /// Whether this Needle only matches a single value, so every byte sequence it could appear as can be worked out up front
+    pub fn is_exact(&self) -> bool {
+        match self {
+            Needle::Integer(integer) => integer.tolerance.is_none(),
+            Needle::Float(float) => float.tolerance.is_none(),
+            Needle::Timestamp(timestamp) => timestamp.tolerance.is_none(),
+            Needle::Location(location) => location.tolerance().is_none(),
+            Needle::IpAddr(ipaddr) => ipaddr.tolerance.is_none(),
+            Needle::Ipv6Addr(ipaddr) => ipaddr.tolerance.is_none(),
+            Needle::SockAddr(sockaddr) => sockaddr.tolerance.is_none(),
+            Needle::MacAddr(macaddr) => macaddr.is_exact(),
+            Needle::Uuid(uuid) => uuid.is_exact(),
+            // Case insensitive strings are still exact, they're just searched for without regard to case
+            Needle::String(_) => true,
+            Needle::Bytes(_) => true,
+            // Patterns are matched directly against the haystack instead
+            Needle::Pattern(_) => false,
+            Needle::Custom(custom) => custom.is_exact(),
+        }
+    }
 }
 
 pub trait Matches {
@@ -133,10 +255,26 @@ impl Matches for Needle {
             (Needle::Timestamp(lhs), Needle::Timestamp(rhs)) => lhs.matches(rhs),
             (Needle::Location(lhs), Needle::Location(rhs)) => lhs.matches(rhs),
             (Needle::IpAddr(lhs), Needle::IpAddr(rhs)) => lhs.matches(rhs),
+            (Needle::Ipv6Addr(lhs), Needle::Ipv6Addr(rhs)) => lhs.matches(rhs),
+            // IPv4 addresses can be embedded in IPv6 ones...
+            (Needle::Ipv6Addr(lhs), Needle::IpAddr(rhs)) => lhs
+                .embedded_ipv4()
+                .and_then(|ipv4| ipaddr::IPv4::new(ipv4).ok())
+                .is_some_and(|ipv4| ipv4.matches(rhs)),
+            // ...and so can MAC addresses, as EUI-64 interface IDs
+            (Needle::Ipv6Addr(lhs), Needle::MacAddr(rhs)) => lhs
+                .eui64_macaddr()
+                .and_then(|macaddr| macaddr::MACAddr::new(macaddr).ok())
+                .is_some_and(|macaddr| macaddr.matches(rhs)),
             (Needle::MacAddr(lhs), Needle::MacAddr(rhs)) => lhs.matches(rhs),
+            (Needle::SockAddr(lhs), Needle::SockAddr(rhs)) => lhs.matches(rhs),
+            (Needle::String(lhs), Needle::String(rhs)) => lhs.matches(rhs),
+            (Needle::Uuid(lhs), Needle::Uuid(rhs)) => lhs.matches(rhs),
             (Needle::Integer(lhs), Needle::Integer(rhs)) => lhs.matches(rhs),
             (Needle::Float(lhs), Needle::Float(rhs)) => lhs.matches(rhs),
             (Needle::Bytes(lhs), Needle::Bytes(rhs)) => lhs == rhs,
+            (Needle::Bytes(lhs), Needle::Pattern(rhs)) => rhs.is_match(lhs),
+            (Needle::Custom(lhs), Needle::Custom(rhs)) => lhs.matches(rhs),
             _ => false,
         }
     }
@@ -153,11 +291,17 @@ impl Discombobulate for Needle {
         match &self {
             Needle::Timestamp(timestamp) => timestamp.discombobulate(),
             Needle::Location(location) => location.discombobulate(),
-            Needle::IpAddr(_) => todo!(),
-            Needle::MacAddr(_) => todo!(),
+            Needle::IpAddr(ipaddr) => ipaddr.discombobulate(),
+            Needle::Ipv6Addr(ipaddr) => ipaddr.discombobulate(),
+            Needle::MacAddr(macaddr) => macaddr.discombobulate(),
+            Needle::SockAddr(sockaddr) => sockaddr.discombobulate(),
+            Needle::String(string) => string.discombobulate(),
+            Needle::Uuid(uuid) => uuid.discombobulate(),
             Needle::Integer(integer) => integer.discombobulate(),
             Needle::Float(float) => float.discombobulate(),
-            Needle::Bytes(_) => todo!(),
+            Needle::Bytes(bytes) => vec![NeedleVariant::Bytes(bytes.clone())],
+            Needle::Pattern(_) => Vec::new(),
+            Needle::Custom(custom) => custom.discombobulate(),
         }
     }
 }
@@ -244,6 +388,16 @@ mod tests {
 
     #[test]
     fn new_timestamps() {
+        // Fractions of a second are optional, down to the nanosecond
+        let Needle::Timestamp(timestamp) =
+            Needle::new_timestamp("2023-12-31 23:59:58.123456789").unwrap()
+        else {
+            panic!("Not a timestamp");
+        };
+        assert_eq!(timestamp.value.nanosecond(), 123_456_789);
+        assert!(Needle::new_timestamp("2023-12-31 23:59:58.").is_err());
+        assert!(Needle::new_timestamp("2023-12-31 23:59").is_err());
+
         let needle = Needle::new_timestamp("2023-12-31 23:59:58").unwrap();
         let variants = needle.discombobulate();
 
@@ -384,6 +538,25 @@ mod tests {
         let rhs = Needle::new_timestamp("2024-01-01 00:00:00").unwrap();
 
         assert!(lhs.matches(&rhs));
+
+        // lhs is 6ms after rhs, with a tolerance of 5ms (so does NOT match)
+        let lhs = Needle::new_timestamp("2024-01-01 00:00:00.006").unwrap();
+        let rhs =
+            Needle::new_timestamp_with_tolerance("2024-01-01 00:00:00", Duration::milliseconds(5))
+                .unwrap();
+
+        assert!(!lhs.matches(&rhs));
+
+        // lhs is 5ms before rhs, with a tolerance of 5ms (so DOES match)
+        let lhs = Needle::new_timestamp("2023-12-31 23:59:59.995").unwrap();
+
+        assert!(lhs.matches(&rhs));
+
+        // lhs is half a second after rhs, with no tolerance (so does NOT match)
+        let lhs = Needle::new_timestamp("2024-01-01 00:00:00.5").unwrap();
+        let rhs = Needle::new_timestamp("2024-01-01 00:00:00").unwrap();
+
+        assert!(!lhs.matches(&rhs));
     }
 
     #[test]
@@ -470,6 +643,41 @@ mod tests {
         assert!(lhs.matches(&rhs));
     }
 
+    #[test]
+    fn matches_ipv6addr() {
+        // Within the same /64
+        let lhs = Needle::new_ipv6_address("2001:db8::1".parse().unwrap()).unwrap();
+        let rhs =
+            Needle::new_ipv6_address_with_tolerance("2001:db8::".parse().unwrap(), 64).unwrap();
+
+        assert!(lhs.matches(&rhs));
+
+        // An IPv4-mapped address matches the IPv4 address...
+        let lhs = Needle::new_ipv6_address("::ffff:192.168.0.1".parse().unwrap()).unwrap();
+        let rhs = Needle::new_ip_address("192.168.0.1".parse().unwrap()).unwrap();
+
+        assert!(lhs.matches(&rhs));
+
+        // ...and any network it's in
+        let rhs =
+            Needle::new_ip_address_with_tolerance("192.168.0.0".parse().unwrap(), 16).unwrap();
+
+        assert!(lhs.matches(&rhs));
+
+        // But not the other way around
+        assert!(!rhs.matches(&lhs));
+
+        // An EUI-64 interface ID matches the MAC address it came from
+        let lhs = Needle::new_ipv6_address("fe80::167d:daff:feab:cdef".parse().unwrap()).unwrap();
+        let rhs = Needle::new_mac_address("14:7D:DA:AB:CD:EF".parse().unwrap()).unwrap();
+
+        assert!(lhs.matches(&rhs));
+
+        let rhs = Needle::new_mac_address("14:7D:DA:AB:CD:00".parse().unwrap()).unwrap();
+
+        assert!(!lhs.matches(&rhs));
+    }
+
     #[test]
     fn matches_macaddr() {
         // Exactly the same
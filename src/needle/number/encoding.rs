@@ -0,0 +1,241 @@
+use anyhow::Result;
+use integer_encoding::VarInt;
+
+use super::variants::{FloatVariant, IntegerVariant};
+
+/// Every way an integer can be laid out in a haystack, without the bytes themselves
+///
+/// Decoding only reads the primitive value, so it's cheap enough to try at every offset before deciding whether it's worth building an IntegerVariant
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IntegerEncoding {
+    U8,
+    U8Varint,
+    U16LE,
+    U16BE,
+    U16Varint,
+    I16LE,
+    I16BE,
+    I16Varint,
+    U32LE,
+    U32BE,
+    U32Varint,
+    I32LE,
+    I32BE,
+    I32Varint,
+    U48LE,
+    U48BE,
+    U64LE,
+    U64BE,
+    U64Varint,
+    I64LE,
+    I64BE,
+    I64Varint,
+}
+
+impl IntegerEncoding {
+    /// The same encodings, in the same order, as IntegerVariant::interpret
+    pub const ALL: [IntegerEncoding; 22] = [
+        IntegerEncoding::U8,
+        IntegerEncoding::U8Varint,
+        IntegerEncoding::U16LE,
+        IntegerEncoding::U16BE,
+        IntegerEncoding::U16Varint,
+        IntegerEncoding::I16LE,
+        IntegerEncoding::I16BE,
+        IntegerEncoding::I16Varint,
+        IntegerEncoding::U32LE,
+        IntegerEncoding::U32BE,
+        IntegerEncoding::U32Varint,
+        IntegerEncoding::I32LE,
+        IntegerEncoding::I32BE,
+        IntegerEncoding::I32Varint,
+        IntegerEncoding::U48LE,
+        IntegerEncoding::U48BE,
+        IntegerEncoding::U64LE,
+        IntegerEncoding::U64BE,
+        IntegerEncoding::U64Varint,
+        IntegerEncoding::I64LE,
+        IntegerEncoding::I64BE,
+        IntegerEncoding::I64Varint,
+    ];
+
+    /// The most bytes a value in this encoding can take up
+    pub fn max_len(self) -> usize {
+        match self {
+            IntegerEncoding::U8 => 1,
+            IntegerEncoding::U16LE
+            | IntegerEncoding::U16BE
+            | IntegerEncoding::I16LE
+            | IntegerEncoding::I16BE
+            | IntegerEncoding::U8Varint => 2,
+            IntegerEncoding::U16Varint | IntegerEncoding::I16Varint => 3,
+            IntegerEncoding::U32LE
+            | IntegerEncoding::U32BE
+            | IntegerEncoding::I32LE
+            | IntegerEncoding::I32BE => 4,
+            IntegerEncoding::U32Varint | IntegerEncoding::I32Varint => 5,
+            IntegerEncoding::U48LE | IntegerEncoding::U48BE => 6,
+            IntegerEncoding::U64LE
+            | IntegerEncoding::U64BE
+            | IntegerEncoding::I64LE
+            | IntegerEncoding::I64BE => 8,
+            IntegerEncoding::U64Varint | IntegerEncoding::I64Varint => 10,
+        }
+    }
+
+    /// The value at the start of the data, cast to an i64 the same way recombobulating the IntegerVariant would
+    pub fn decode(self, data: &[u8]) -> Option<i64> {
+        match self {
+            IntegerEncoding::U8 => data.first().map(|b| *b as i64),
+            IntegerEncoding::U8Varint => u8::decode_var(data).map(|(i, _)| i as i64),
+            IntegerEncoding::U16LE => fixed(data).map(|b| u16::from_le_bytes(b) as i64),
+            IntegerEncoding::U16BE => fixed(data).map(|b| u16::from_be_bytes(b) as i64),
+            IntegerEncoding::U16Varint => u16::decode_var(data).map(|(i, _)| i as i64),
+            IntegerEncoding::I16LE => fixed(data).map(|b| i16::from_le_bytes(b) as i64),
+            IntegerEncoding::I16BE => fixed(data).map(|b| i16::from_be_bytes(b) as i64),
+            IntegerEncoding::I16Varint => i16::decode_var(data).map(|(i, _)| i as i64),
+            IntegerEncoding::U32LE => fixed(data).map(|b| u32::from_le_bytes(b) as i64),
+            IntegerEncoding::U32BE => fixed(data).map(|b| u32::from_be_bytes(b) as i64),
+            IntegerEncoding::U32Varint => u32::decode_var(data).map(|(i, _)| i as i64),
+            IntegerEncoding::I32LE => fixed(data).map(|b| i32::from_le_bytes(b) as i64),
+            IntegerEncoding::I32BE => fixed(data).map(|b| i32::from_be_bytes(b) as i64),
+            IntegerEncoding::I32Varint => i32::decode_var(data).map(|(i, _)| i as i64),
+            IntegerEncoding::U48LE => fixed::<6>(data).map(|b| {
+                let mut padded = [0u8; 8];
+                padded[..6].copy_from_slice(&b);
+                u64::from_le_bytes(padded) as i64
+            }),
+            IntegerEncoding::U48BE => fixed::<6>(data).map(|b| {
+                let mut padded = [0u8; 8];
+                padded[2..].copy_from_slice(&b);
+                u64::from_be_bytes(padded) as i64
+            }),
+            IntegerEncoding::U64LE => fixed(data).map(|b| u64::from_le_bytes(b) as i64),
+            IntegerEncoding::U64BE => fixed(data).map(|b| u64::from_be_bytes(b) as i64),
+            IntegerEncoding::U64Varint => u64::decode_var(data).map(|(i, _)| i as i64),
+            IntegerEncoding::I64LE => fixed(data).map(i64::from_le_bytes),
+            IntegerEncoding::I64BE => fixed(data).map(i64::from_be_bytes),
+            IntegerEncoding::I64Varint => i64::decode_var(data).map(|(i, _)| i),
+        }
+    }
+
+    /// Build the full IntegerVariant for the value at the start of the data
+    pub fn variant(self, data: &[u8]) -> Result<IntegerVariant> {
+        match self {
+            IntegerEncoding::U8 => IntegerVariant::as_u8(data),
+            IntegerEncoding::U8Varint => IntegerVariant::as_u8_varint(data),
+            IntegerEncoding::U16LE => IntegerVariant::as_u16_le(data),
+            IntegerEncoding::U16BE => IntegerVariant::as_u16_be(data),
+            IntegerEncoding::U16Varint => IntegerVariant::as_u16_varint(data),
+            IntegerEncoding::I16LE => IntegerVariant::as_i16_le(data),
+            IntegerEncoding::I16BE => IntegerVariant::as_i16_be(data),
+            IntegerEncoding::I16Varint => IntegerVariant::as_i16_varint(data),
+            IntegerEncoding::U32LE => IntegerVariant::as_u32_le(data),
+            IntegerEncoding::U32BE => IntegerVariant::as_u32_be(data),
+            IntegerEncoding::U32Varint => IntegerVariant::as_u32_varint(data),
+            IntegerEncoding::I32LE => IntegerVariant::as_i32_le(data),
+            IntegerEncoding::I32BE => IntegerVariant::as_i32_be(data),
+            IntegerEncoding::I32Varint => IntegerVariant::as_i32_varint(data),
+            IntegerEncoding::U48LE => IntegerVariant::as_u48_le(data),
+            IntegerEncoding::U48BE => IntegerVariant::as_u48_be(data),
+            IntegerEncoding::U64LE => IntegerVariant::as_u64_le(data),
+            IntegerEncoding::U64BE => IntegerVariant::as_u64_be(data),
+            IntegerEncoding::U64Varint => IntegerVariant::as_u64_varint(data),
+            IntegerEncoding::I64LE => IntegerVariant::as_i64_le(data),
+            IntegerEncoding::I64BE => IntegerVariant::as_i64_be(data),
+            IntegerEncoding::I64Varint => IntegerVariant::as_i64_varint(data),
+        }
+    }
+}
+
+/// Every way a float can be laid out in a haystack, without the bytes themselves
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FloatEncoding {
+    F32LE,
+    F32BE,
+    F64LE,
+    F64BE,
+}
+
+impl FloatEncoding {
+    /// The same encodings, in the same order, as FloatVariant::interpret
+    pub const ALL: [FloatEncoding; 4] = [
+        FloatEncoding::F32LE,
+        FloatEncoding::F32BE,
+        FloatEncoding::F64LE,
+        FloatEncoding::F64BE,
+    ];
+
+    pub fn byte_len(self) -> usize {
+        match self {
+            FloatEncoding::F32LE | FloatEncoding::F32BE => 4,
+            FloatEncoding::F64LE | FloatEncoding::F64BE => 8,
+        }
+    }
+
+    /// The value at the start of the data, widened to an f64 the same way recombobulating the FloatVariant would
+    pub fn decode(self, data: &[u8]) -> Option<f64> {
+        match self {
+            FloatEncoding::F32LE => fixed(data).map(|b| f32::from_le_bytes(b) as f64),
+            FloatEncoding::F32BE => fixed(data).map(|b| f32::from_be_bytes(b) as f64),
+            FloatEncoding::F64LE => fixed(data).map(f64::from_le_bytes),
+            FloatEncoding::F64BE => fixed(data).map(f64::from_be_bytes),
+        }
+    }
+
+    /// Build the full FloatVariant for the value at the start of the data
+    pub fn variant(self, data: &[u8]) -> Result<FloatVariant> {
+        match self {
+            FloatEncoding::F32LE => FloatVariant::as_f32_le(data),
+            FloatEncoding::F32BE => FloatVariant::as_f32_be(data),
+            FloatEncoding::F64LE => FloatVariant::as_f64_le(data),
+            FloatEncoding::F64BE => FloatVariant::as_f64_be(data),
+        }
+    }
+}
+
+/// The first N bytes of the data, if there are enough of them
+fn fixed<const N: usize>(data: &[u8]) -> Option<[u8; N]> {
+    data.get(..N)?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::needle::{Needle, Recombobulate};
+
+    use super::*;
+
+    #[test]
+    fn decode_test() {
+        let data = [0xc0u8, 0xa8, 0x00, 0x01, 0xff, 0xff, 0x7f, 0x80];
+
+        // Decoding the primitive should always agree with building the variant
+        for encoding in IntegerEncoding::ALL {
+            for i in 0..data.len() {
+                let decoded = encoding.decode(&data[i..]);
+                let variant = encoding.variant(&data[i..]);
+
+                assert_eq!(decoded.is_some(), variant.is_ok(), "{:?}", encoding);
+
+                if let Ok(variant) = &variant {
+                    assert!(variant.byte_sequence().len() <= encoding.max_len());
+                }
+
+                if let (Some(decoded), Ok(variant)) = (decoded, variant) {
+                    if let Ok(Needle::Integer(integer)) = variant.recombobulate() {
+                        assert_eq!(decoded, integer.value, "{:?}", encoding);
+                    }
+                }
+            }
+        }
+
+        for encoding in FloatEncoding::ALL {
+            for i in 0..data.len() {
+                let decoded = encoding.decode(&data[i..]);
+                let variant = encoding.variant(&data[i..]);
+
+                assert_eq!(decoded.is_some(), variant.is_ok(), "{:?}", encoding);
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub mod encoding;
 pub mod primitives;
 pub mod variants;
 
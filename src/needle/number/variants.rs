@@ -3,6 +3,8 @@ use integer_encoding::VarInt;
 
 use crate::needle::{Interpret, Needle, Recombobulate};
 
+use super::encoding::IntegerEncoding;
+
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum IntegerVariant {
     // u8
@@ -312,8 +314,8 @@ impl IntegerVariant {
     ///
     pub fn as_u48_le(data: &[u8]) -> Result<IntegerVariant> {
         if data.len() >= 6 {
-            // u64::from_le_bytes() requires 8 bytes to work, so pad with zeroes
-            let data = [data, &[0u8, 0]].concat();
+            // u64::from_le_bytes() requires 8 bytes to work, so pad the first 6 with zeroes
+            let data = [&data[0..6], &[0u8, 0]].concat();
             let i = u64::from_le_bytes(data[0..8].try_into()?);
             Ok(IntegerVariant::U48LE((data[0..6].to_owned(), i)))
         } else {
@@ -490,6 +492,35 @@ impl IntegerVariant {
             IntegerVariant::I64Varint(v) => &v.0,
         }
     }
+
+    /// How the integer is laid out, if it's one of the encodings the interpretive scan tries
+    pub fn encoding(&self) -> Option<IntegerEncoding> {
+        match self {
+            IntegerVariant::U8(_) => Some(IntegerEncoding::U8),
+            IntegerVariant::U8Varint(_) => Some(IntegerEncoding::U8Varint),
+            IntegerVariant::I8(_) | IntegerVariant::I8Varint(_) => None,
+            IntegerVariant::U16LE(_) => Some(IntegerEncoding::U16LE),
+            IntegerVariant::U16BE(_) => Some(IntegerEncoding::U16BE),
+            IntegerVariant::U16Varint(_) => Some(IntegerEncoding::U16Varint),
+            IntegerVariant::I16LE(_) => Some(IntegerEncoding::I16LE),
+            IntegerVariant::I16BE(_) => Some(IntegerEncoding::I16BE),
+            IntegerVariant::I16Varint(_) => Some(IntegerEncoding::I16Varint),
+            IntegerVariant::U32LE(_) => Some(IntegerEncoding::U32LE),
+            IntegerVariant::U32BE(_) => Some(IntegerEncoding::U32BE),
+            IntegerVariant::U32Varint(_) => Some(IntegerEncoding::U32Varint),
+            IntegerVariant::I32LE(_) => Some(IntegerEncoding::I32LE),
+            IntegerVariant::I32BE(_) => Some(IntegerEncoding::I32BE),
+            IntegerVariant::I32Varint(_) => Some(IntegerEncoding::I32Varint),
+            IntegerVariant::U48LE(_) => Some(IntegerEncoding::U48LE),
+            IntegerVariant::U48BE(_) => Some(IntegerEncoding::U48BE),
+            IntegerVariant::U64LE(_) => Some(IntegerEncoding::U64LE),
+            IntegerVariant::U64BE(_) => Some(IntegerEncoding::U64BE),
+            IntegerVariant::U64Varint(_) => Some(IntegerEncoding::U64Varint),
+            IntegerVariant::I64LE(_) => Some(IntegerEncoding::I64LE),
+            IntegerVariant::I64BE(_) => Some(IntegerEncoding::I64BE),
+            IntegerVariant::I64Varint(_) => Some(IntegerEncoding::I64Varint),
+        }
+    }
 }
 
 impl Recombobulate for IntegerVariant {
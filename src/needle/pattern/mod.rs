@@ -0,0 +1,191 @@
+pub mod parse;
+
+use std::str::FromStr;
+
+use anyhow::Result;
+
+/// One part of a byte pattern
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum PatternToken {
+    /// A byte whose masked bits are the same as the value's, e.g. 4? is Byte(0x40, 0xf0)
+    Byte(u8, u8),
+    /// A byte whose masked bits aren't the same as the value's, e.g. ~00 is NotByte(0x00, 0xff)
+    NotByte(u8, u8),
+    /// Any number of bytes from the first to the second, inclusive
+    Jump(usize, usize),
+    /// Any one of the sequences
+    Alternation(Vec<Vec<PatternToken>>),
+}
+
+/// A sequence of bytes with wildcards, masks, gaps and alternations, written out as a YARA hex string, e.g. "4D 5A ?? 00 [2-6] (50 45 | 4E 45)"
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct BytePattern {
+    pub tokens: Vec<PatternToken>,
+}
+
+impl BytePattern {
+    pub fn new(hex: &str) -> Result<Self> {
+        Ok(Self {
+            tokens: parse::parse(hex)?,
+        })
+    }
+
+    /// The fewest bytes a match can take up
+    pub fn min_len(&self) -> usize {
+        lengths(&self.tokens).0
+    }
+
+    /// The most bytes a match can take up
+    pub fn max_len(&self) -> usize {
+        lengths(&self.tokens).1
+    }
+
+    /// How many bytes at the start of the data match the pattern, trying the shortest jumps and the first alternatives first
+    pub fn match_len(&self, data: &[u8]) -> Option<usize> {
+        match_tokens(&self.tokens, data, 0, &Some)
+    }
+
+    /// Whether all of the bytes, and nothing more, match the pattern
+    pub fn is_match(&self, bytes: &[u8]) -> bool {
+        match_tokens(&self.tokens, bytes, 0, &|end| {
+            (end == bytes.len()).then_some(end)
+        })
+        .is_some()
+    }
+
+    /// The longest run of exact bytes that's always the same distance from the start of a match, and that distance
+    ///
+    /// Searching for these first means the whole pattern only needs to be tried where they're found
+    pub fn atom(&self) -> Option<(usize, Vec<u8>)> {
+        let mut longest: Option<(usize, Vec<u8>)> = None;
+        let mut run: Option<(usize, Vec<u8>)> = None;
+
+        for (offset, token) in self.tokens.iter().enumerate() {
+            match token {
+                PatternToken::Byte(value, 0xff) => {
+                    run.get_or_insert_with(|| (offset, Vec::new()))
+                        .1
+                        .push(*value);
+                }
+                PatternToken::Byte(_, _) | PatternToken::NotByte(_, _) => {
+                    longest = longer(longest, run.take());
+                }
+                // Anything after a jump or alternation is no longer a fixed distance from the start
+                PatternToken::Jump(_, _) | PatternToken::Alternation(_) => break,
+            }
+        }
+
+        longer(longest, run)
+    }
+}
+
+impl FromStr for BytePattern {
+    type Err = anyhow::Error;
+
+    fn from_str(hex: &str) -> Result<Self> {
+        Self::new(hex)
+    }
+}
+
+fn longer(
+    longest: Option<(usize, Vec<u8>)>,
+    run: Option<(usize, Vec<u8>)>,
+) -> Option<(usize, Vec<u8>)> {
+    match (longest, run) {
+        (Some(longest), Some(run)) if run.1.len() > longest.1.len() => Some(run),
+        (Some(longest), _) => Some(longest),
+        (None, run) => run,
+    }
+}
+
+/// The fewest and most bytes a sequence of tokens can take up
+fn lengths(tokens: &[PatternToken]) -> (usize, usize) {
+    tokens
+        .iter()
+        .map(|token| match token {
+            PatternToken::Byte(_, _) | PatternToken::NotByte(_, _) => (1, 1),
+            PatternToken::Jump(min, max) => (*min, *max),
+            PatternToken::Alternation(alternatives) => {
+                let lengths = alternatives
+                    .iter()
+                    .map(|alternative| lengths(alternative))
+                    .collect::<Vec<_>>();
+                (
+                    lengths.iter().map(|(min, _)| *min).min().unwrap_or(0),
+                    lengths.iter().map(|(_, max)| *max).max().unwrap_or(0),
+                )
+            }
+        })
+        .fold((0, 0), |(min, max), (token_min, token_max)| {
+            (min + token_min, max + token_max)
+        })
+}
+
+/// Match the tokens against the data from a position, handing where they end to whatever comes after them
+fn match_tokens(
+    tokens: &[PatternToken],
+    data: &[u8],
+    pos: usize,
+    then: &dyn Fn(usize) -> Option<usize>,
+) -> Option<usize> {
+    let Some((token, rest)) = tokens.split_first() else {
+        return then(pos);
+    };
+    let next = |pos| match_tokens(rest, data, pos, then);
+
+    match token {
+        PatternToken::Byte(value, mask) => data
+            .get(pos)
+            .filter(|byte| *byte & mask == value & mask)
+            .and_then(|_| next(pos + 1)),
+        PatternToken::NotByte(value, mask) => data
+            .get(pos)
+            .filter(|byte| *byte & mask != value & mask)
+            .and_then(|_| next(pos + 1)),
+        PatternToken::Jump(min, max) => (*min..=*max)
+            .take_while(|skip| pos + skip <= data.len())
+            .find_map(|skip| next(pos + skip)),
+        PatternToken::Alternation(alternatives) => alternatives
+            .iter()
+            .find_map(|alternative| match_tokens(alternative, data, pos, &next)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_test() {
+        let pattern = BytePattern::new("4D 5A ?? 00 [2-4] (50 45 | 4E ?5) ~00").unwrap();
+        assert_eq!(pattern.min_len(), 9);
+        assert_eq!(pattern.max_len(), 11);
+        assert_eq!(pattern.atom(), Some((0, vec![0x4d, 0x5a])));
+
+        let data = [
+            0x4d, 0x5a, 0x90, 0x00, 0xff, 0xff, 0xff, 0x4e, 0x45, 0x01, 0xff,
+        ];
+        assert_eq!(pattern.match_len(&data), Some(10));
+        assert!(pattern.is_match(&data[..10]));
+        assert!(!pattern.is_match(&data));
+
+        // The shortest jump is tried first, even when a longer one would also match
+        let data = [0x4d, 0x5a, 0x90, 0x00, 0x50, 0x45, 0x50, 0x45, 0x01];
+        assert_eq!(pattern.match_len(&data), Some(9));
+
+        // The negated byte can't be a zero
+        let data = [0x4d, 0x5a, 0x90, 0x00, 0xff, 0xff, 0x50, 0x45, 0x00];
+        assert_eq!(pattern.match_len(&data), None);
+
+        // Masks can leave out any of the bits, not just whole nibbles
+        let pattern = BytePattern {
+            tokens: vec![
+                PatternToken::Byte(0x80, 0x80),
+                PatternToken::Byte(0x01, 0xff),
+            ],
+        };
+        assert_eq!(pattern.match_len(&[0xc3, 0x01]), Some(2));
+        assert_eq!(pattern.match_len(&[0x43, 0x01]), None);
+        assert_eq!(pattern.atom(), Some((1, vec![0x01])));
+    }
+}
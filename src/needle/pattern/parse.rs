@@ -0,0 +1,229 @@
+use anyhow::{anyhow, Result};
+
+use super::PatternToken;
+
+/// The longest jump a pattern can have, as in YARA
+pub const MAX_JUMP: usize = 0xffff;
+
+/// Parse a YARA hex string, with or without the braces around it
+///
+/// Jumps have to be bounded, and can't come at the start or end of the pattern, as YARA requires
+pub fn parse(hex: &str) -> Result<Vec<PatternToken>> {
+    let hex = hex.trim();
+    let hex = hex
+        .strip_prefix('{')
+        .and_then(|hex| hex.strip_suffix('}'))
+        .unwrap_or(hex);
+
+    let mut parser = Parser {
+        text: hex.as_bytes(),
+        pos: 0,
+    };
+    let tokens = parser.sequence()?;
+
+    if let Some(c) = parser.peek() {
+        return Err(anyhow!(
+            "Unexpected '{}' at {} in byte pattern {:?}",
+            c as char,
+            parser.pos,
+            hex
+        ));
+    }
+    if tokens.is_empty() {
+        return Err(anyhow!("Byte pattern {:?} is empty", hex));
+    }
+    if matches!(tokens.first(), Some(PatternToken::Jump(_, _)))
+        || matches!(tokens.last(), Some(PatternToken::Jump(_, _)))
+    {
+        return Err(anyhow!(
+            "Byte pattern {:?} can't start or end with a jump",
+            hex
+        ));
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    /// The next character that isn't whitespace, without consuming it
+    fn peek(&mut self) -> Option<u8> {
+        while self.text.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+
+        self.text.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<()> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            _ => Err(anyhow!(
+                "Expected '{}' at {} in byte pattern",
+                expected as char,
+                self.pos
+            )),
+        }
+    }
+
+    /// Tokens up to the end of the pattern, or of the alternative they're in
+    fn sequence(&mut self) -> Result<Vec<PatternToken>> {
+        let mut tokens = Vec::new();
+
+        while let Some(c) = self.peek() {
+            let token = match c {
+                b'|' | b')' => break,
+                b'~' => {
+                    self.pos += 1;
+                    let (value, mask) = self.byte()?;
+                    PatternToken::NotByte(value, mask)
+                }
+                b'[' => self.jump()?,
+                b'(' => self.alternation()?,
+                _ => {
+                    let (value, mask) = self.byte()?;
+                    PatternToken::Byte(value, mask)
+                }
+            };
+
+            tokens.push(token);
+        }
+
+        Ok(tokens)
+    }
+
+    /// Two hex digits, either of which can be a ? wildcard
+    fn byte(&mut self) -> Result<(u8, u8)> {
+        self.peek();
+        let mut value = 0;
+        let mut mask = 0;
+
+        for _ in 0..2 {
+            let c = self.text.get(self.pos).copied();
+            let (nibble, nibble_mask) = match c {
+                Some(b'?') => (0, 0),
+                Some(c) if c.is_ascii_hexdigit() => ((c as char).to_digit(16).unwrap() as u8, 0xf),
+                _ => {
+                    return Err(anyhow!(
+                        "Expected a hex digit or ? at {} in byte pattern",
+                        self.pos
+                    ))
+                }
+            };
+
+            value = value << 4 | nibble;
+            mask = mask << 4 | nibble_mask;
+            self.pos += 1;
+        }
+
+        Ok((value, mask))
+    }
+
+    /// [n] or [n-m]
+    fn jump(&mut self) -> Result<PatternToken> {
+        self.expect(b'[')?;
+        let min = self.number()?;
+        let max = match self.peek() {
+            Some(b'-') => {
+                self.pos += 1;
+                self.number()?
+            }
+            _ => min,
+        };
+        self.expect(b']')?;
+
+        if min > max || max > MAX_JUMP {
+            return Err(anyhow!("Invalid jump [{}-{}] in byte pattern", min, max));
+        }
+
+        Ok(PatternToken::Jump(min, max))
+    }
+
+    fn number(&mut self) -> Result<usize> {
+        self.peek();
+        let start = self.pos;
+        while self.text.get(self.pos).is_some_and(u8::is_ascii_digit) {
+            self.pos += 1;
+        }
+
+        std::str::from_utf8(&self.text[start..self.pos])?
+            .parse()
+            .map_err(|_| {
+                anyhow!(
+                    "Expected a bounded jump length at {} in byte pattern",
+                    start
+                )
+            })
+    }
+
+    /// (a | b | ...)
+    fn alternation(&mut self) -> Result<PatternToken> {
+        self.expect(b'(')?;
+        let mut alternatives = vec![self.sequence()?];
+        while self.peek() == Some(b'|') {
+            self.pos += 1;
+            alternatives.push(self.sequence()?);
+        }
+        self.expect(b')')?;
+
+        if alternatives.iter().any(Vec::is_empty) {
+            return Err(anyhow!("Empty alternative in byte pattern"));
+        }
+
+        Ok(PatternToken::Alternation(alternatives))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        assert_eq!(
+            parse("{ 4D 5a ?? ?0 ~00 [2-6] [4] (01 | 02 ?3) }").unwrap(),
+            vec![
+                PatternToken::Byte(0x4d, 0xff),
+                PatternToken::Byte(0x5a, 0xff),
+                PatternToken::Byte(0x00, 0x00),
+                PatternToken::Byte(0x00, 0x0f),
+                PatternToken::NotByte(0x00, 0xff),
+                PatternToken::Jump(2, 6),
+                PatternToken::Jump(4, 4),
+                PatternToken::Alternation(vec![
+                    vec![PatternToken::Byte(0x01, 0xff)],
+                    vec![
+                        PatternToken::Byte(0x02, 0xff),
+                        PatternToken::Byte(0x03, 0x0f)
+                    ],
+                ]),
+            ]
+        );
+
+        // Whitespace between bytes is optional
+        assert_eq!(parse("4D5A").unwrap(), parse("4D 5A").unwrap());
+
+        for invalid in [
+            "",
+            "4D 5",
+            "4D 5G",
+            "[2] 4D",
+            "4D [2]",
+            "4D [6-2] 5A",
+            "4D [2-] 5A",
+            "4D [-] 5A",
+            "4D (5A | ) 00",
+            "4D (5A 00",
+            "4D ) 5A",
+        ] {
+            assert!(parse(invalid).is_err(), "{:?} should be invalid", invalid);
+        }
+    }
+}
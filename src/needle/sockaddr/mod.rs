@@ -0,0 +1,117 @@
+pub mod variant;
+
+use std::{net::SocketAddr, ops::RangeInclusive};
+
+use anyhow::{anyhow, Result};
+use ipnet::IpNet;
+use variant::SockAddrVariant;
+
+use super::{variant::NeedleVariant, Discombobulate, Matches};
+
+/// The addresses and ports a socket address needle with a tolerance will match
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct SockAddrTolerance {
+    pub network: IpNet,
+    /// The first and last ports, inclusive
+    pub ports: (u16, u16),
+}
+
+/// An IP address and port, i.e. one end of a connection
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct SockAddr {
+    pub value: SocketAddr,
+    pub tolerance: Option<SockAddrTolerance>,
+}
+
+impl SockAddr {
+    pub fn new(sockaddr: SocketAddr) -> Result<Self> {
+        Ok(Self {
+            value: sockaddr,
+            tolerance: None,
+        })
+    }
+
+    /// Any address in the network the given one is part of, on any port in the range
+    pub fn with_tolerance(
+        sockaddr: SocketAddr,
+        prefix_len: u8,
+        ports: RangeInclusive<u16>,
+    ) -> Result<Self> {
+        if ports.is_empty() {
+            return Err(anyhow!("Port range {:?} is empty", ports));
+        }
+
+        Ok(Self {
+            value: sockaddr,
+            tolerance: Some(SockAddrTolerance {
+                network: IpNet::new(sockaddr.ip(), prefix_len)?,
+                ports: (*ports.start(), *ports.end()),
+            }),
+        })
+    }
+}
+
+impl Matches for SockAddr {
+    fn matches(&self, rhs: &Self) -> bool {
+        // Dual-stack sockets see IPv4 peers as IPv4-mapped IPv6 addresses, which are the same host
+        let ip = self.value.ip();
+        let canonical = ip.to_canonical();
+        let port = self.value.port();
+
+        // If rhs has a tolerance, check that lhs falls wthin it
+        match &rhs.tolerance {
+            Some(tolerance) => {
+                let (first, last) = tolerance.ports;
+
+                (tolerance.network.contains(&ip) || tolerance.network.contains(&canonical))
+                    && (first..=last).contains(&port)
+            }
+            None => canonical == rhs.value.ip().to_canonical() && port == rhs.value.port(),
+        }
+    }
+}
+
+impl Discombobulate for SockAddr {
+    fn discombobulate(&self) -> Vec<NeedleVariant> {
+        SockAddrVariant::all(self.value)
+            .into_iter()
+            .map(NeedleVariant::SockAddr)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sockaddr_matches_test() {
+        let target = SockAddr::new("192.168.0.1:443".parse().unwrap()).unwrap();
+
+        let actual = SockAddr::new("192.168.0.1:443".parse().unwrap()).unwrap();
+        assert!(actual.matches(&target));
+
+        let actual = SockAddr::new("192.168.0.1:80".parse().unwrap()).unwrap();
+        assert!(!actual.matches(&target));
+
+        // The same host, as seen by a dual-stack socket
+        let actual = SockAddr::new("[::ffff:192.168.0.1]:443".parse().unwrap()).unwrap();
+        assert!(actual.matches(&target));
+
+        let target =
+            SockAddr::with_tolerance("10.0.0.0:0".parse().unwrap(), 8, 8000..=8999).unwrap();
+
+        let actual = SockAddr::new("10.1.2.3:8080".parse().unwrap()).unwrap();
+        assert!(actual.matches(&target));
+
+        let actual = SockAddr::new("10.1.2.3:9000".parse().unwrap()).unwrap();
+        assert!(!actual.matches(&target));
+
+        let actual = SockAddr::new("11.1.2.3:8080".parse().unwrap()).unwrap();
+        assert!(!actual.matches(&target));
+
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = SockAddr::with_tolerance("10.0.0.0:0".parse().unwrap(), 8, 9000..=8000);
+        assert!(empty.is_err());
+    }
+}
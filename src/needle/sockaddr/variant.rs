@@ -0,0 +1,345 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use anyhow::{anyhow, Result};
+
+use crate::needle::{
+    ipaddr::text::IPv4Format, text::TextEncoding, Interpret, Needle, Recombobulate,
+};
+
+use super::SockAddr;
+
+const AF_INET: u8 = 2;
+
+/// How a sockaddr's address family is stored, which depends on the OS and its byte order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Hash)]
+pub enum FamilyField {
+    /// A little endian u16, e.g. Linux or Windows on x86
+    LE,
+    /// A big endian u16, e.g. Linux on MIPS or PowerPC
+    BE,
+    /// A length byte then a family byte, as on macOS and the BSDs
+    BSD,
+}
+
+impl FamilyField {
+    pub const ALL: [FamilyField; 3] = [FamilyField::LE, FamilyField::BE, FamilyField::BSD];
+
+    /// The field for AF_INET, which is the same everywhere
+    fn inet(self) -> [u8; 2] {
+        match self {
+            FamilyField::LE => [AF_INET, 0],
+            FamilyField::BE => [0, AF_INET],
+            FamilyField::BSD => [16, AF_INET],
+        }
+    }
+
+    /// The fields for AF_INET6, which is 10 on Linux, 23 on Windows, 24 on OpenBSD and NetBSD, 28 on FreeBSD and 30 on macOS
+    fn inet6(self) -> &'static [[u8; 2]] {
+        match self {
+            FamilyField::LE => &[[10, 0], [23, 0]],
+            FamilyField::BE => &[[0, 10]],
+            FamilyField::BSD => &[[28, 24], [28, 28], [28, 30]],
+        }
+    }
+}
+
+/// The most characters a socket address can take up as text, i.e. [ffff:ffff:ffff:ffff:ffff:ffff:255.255.255.255]:65535
+const MAX_CHARS: usize = 53;
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum SockAddrVariant {
+    /// struct sockaddr_in, without the padding at the end: the family in host order, then the port and address in network order
+    In(FamilyField, [u8; 8]),
+    /// struct sockaddr_in6 with no flow information, without the scope ID at the end
+    In6(FamilyField, [u8; 24]),
+    /// An IPv4 address then a port, both in network order, e.g. in a SOCKS reply or a compact peer list
+    Packed([u8; 6]),
+    /// An IPv6 address then a port, both in network order
+    Packed6([u8; 18]),
+    /// Written out as text, e.g. "192.168.0.1:443" or "[2001:db8::1]:443"
+    Text(TextEncoding, Vec<u8>),
+}
+
+impl SockAddrVariant {
+    /// The most bytes any variant can take up, along with the character after text that shows where it ends
+    pub const MAX_LEN: usize = (MAX_CHARS + 1) * 2;
+
+    pub fn byte_len(&self) -> usize {
+        self.byte_sequence().len()
+    }
+
+    pub fn byte_sequence(&self) -> &[u8] {
+        match self {
+            SockAddrVariant::In(_, v) => v,
+            SockAddrVariant::In6(_, v) => v,
+            SockAddrVariant::Packed(v) => v,
+            SockAddrVariant::Packed6(v) => v,
+            SockAddrVariant::Text(_, v) => v,
+        }
+    }
+
+    /// The address and port these bytes represent
+    pub fn value(&self) -> Option<SocketAddr> {
+        match self {
+            SockAddrVariant::In(_, v) => Some(SocketAddr::new(
+                Ipv4Addr::new(v[4], v[5], v[6], v[7]).into(),
+                u16::from_be_bytes([v[2], v[3]]),
+            )),
+            SockAddrVariant::In6(_, v) => Some(SocketAddr::new(
+                Ipv6Addr::from(<[u8; 16]>::try_from(&v[8..]).ok()?).into(),
+                u16::from_be_bytes([v[2], v[3]]),
+            )),
+            SockAddrVariant::Packed(v) => Some(SocketAddr::new(
+                Ipv4Addr::new(v[0], v[1], v[2], v[3]).into(),
+                u16::from_be_bytes([v[4], v[5]]),
+            )),
+            SockAddrVariant::Packed6(v) => Some(SocketAddr::new(
+                Ipv6Addr::from(<[u8; 16]>::try_from(&v[..16]).ok()?).into(),
+                u16::from_be_bytes([v[16], v[17]]),
+            )),
+            SockAddrVariant::Text(encoding, v) => {
+                let chars = encoding.decode(v, MAX_CHARS);
+
+                match parse_text(&chars) {
+                    Some((sockaddr, len)) if len == chars.len() => Some(sockaddr),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Every way a socket address can be laid out
+    pub fn all(sockaddr: SocketAddr) -> Vec<Self> {
+        match sockaddr.ip().to_canonical() {
+            IpAddr::V4(ip) => {
+                let mut variants = ipv4(ip, sockaddr.port());
+
+                // Dual-stack sockets see IPv4 peers as IPv4-mapped IPv6 addresses
+                variants.extend(ipv6(ip.to_ipv6_mapped(), sockaddr.port()));
+                variants
+            }
+            IpAddr::V6(ip) => ipv6(ip, sockaddr.port()),
+        }
+    }
+
+    /// Whether the variant really starts and ends where its byte sequence does, rather than being part of the text around it
+    pub fn is_complete(&self, preceding: &[u8], following: &[u8]) -> bool {
+        match self {
+            SockAddrVariant::Text(encoding, bytes) => {
+                // IPv6 addresses are bracketed, but IPv4 ones can be the end of a longer one, e.g. 10.0.0.1:80 in 110.0.0.1:80
+                let bracketed = encoding.decode(bytes, 1).first() == Some(&b'[');
+                let continued = !bracketed
+                    && IPv4Format::DottedQuad
+                        .is_continued_from(encoding.decode_last(preceding).as_ref());
+
+                !continued
+                    && !encoding
+                        .decode(following, 1)
+                        .first()
+                        .is_some_and(u8::is_ascii_digit)
+            }
+            _ => true,
+        }
+    }
+}
+
+fn ipv4(ip: Ipv4Addr, port: u16) -> Vec<SockAddrVariant> {
+    let mut variants = Vec::<SockAddrVariant>::new();
+
+    for field in FamilyField::ALL {
+        let mut bytes = [0u8; 8];
+        bytes[..2].copy_from_slice(&field.inet());
+        bytes[2..4].copy_from_slice(&port.to_be_bytes());
+        bytes[4..].copy_from_slice(&ip.octets());
+        variants.push(SockAddrVariant::In(field, bytes));
+    }
+
+    let mut bytes = [0u8; 6];
+    bytes[..4].copy_from_slice(&ip.octets());
+    bytes[4..].copy_from_slice(&port.to_be_bytes());
+    variants.push(SockAddrVariant::Packed(bytes));
+
+    variants.extend(text(SocketAddr::new(ip.into(), port)));
+    variants
+}
+
+fn ipv6(ip: Ipv6Addr, port: u16) -> Vec<SockAddrVariant> {
+    let mut variants = Vec::<SockAddrVariant>::new();
+
+    for field in FamilyField::ALL {
+        for family in field.inet6() {
+            let mut bytes = [0u8; 24];
+            bytes[..2].copy_from_slice(family);
+            bytes[2..4].copy_from_slice(&port.to_be_bytes());
+            bytes[8..].copy_from_slice(&ip.octets());
+            variants.push(SockAddrVariant::In6(field, bytes));
+        }
+    }
+
+    let mut bytes = [0u8; 18];
+    bytes[..16].copy_from_slice(&ip.octets());
+    bytes[16..].copy_from_slice(&port.to_be_bytes());
+    variants.push(SockAddrVariant::Packed6(bytes));
+
+    // Any scope ID is left off, as it can't be parsed back
+    variants.extend(text(SocketAddr::new(ip.into(), port)));
+    variants
+}
+
+fn text(sockaddr: SocketAddr) -> Vec<SockAddrVariant> {
+    let text = sockaddr.to_string();
+
+    TextEncoding::ALL
+        .into_iter()
+        .map(|encoding| SockAddrVariant::Text(encoding, encoding.encode(&text)))
+        .collect()
+}
+
+/// Read "a.b.c.d:port" or "[v6]:port" from the start of some ASCII text, returning it and how many characters it took up
+fn parse_text(text: &[u8]) -> Option<(SocketAddr, usize)> {
+    let (ip, len) = match text.first()? {
+        b'[' => {
+            let end = text.iter().position(|c| *c == b']')?;
+            let ip: Ipv6Addr = std::str::from_utf8(&text[1..end]).ok()?.parse().ok()?;
+            (IpAddr::V6(ip), end + 1)
+        }
+        _ => {
+            let (ip, len) = IPv4Format::DottedQuad.parse(text)?;
+            (IpAddr::V4(ip), len)
+        }
+    };
+
+    if text.get(len) != Some(&b':') {
+        return None;
+    }
+
+    let digits = &text[len + 1..];
+    let width = digits
+        .iter()
+        .take(5)
+        .take_while(|c| c.is_ascii_digit())
+        .count();
+
+    // No leading zeros, and nothing that carries on past the port
+    if width == 0
+        || (width > 1 && digits[0] == b'0')
+        || digits.get(width).is_some_and(u8::is_ascii_digit)
+    {
+        return None;
+    }
+
+    let port: u16 = std::str::from_utf8(&digits[..width]).ok()?.parse().ok()?;
+
+    Some((SocketAddr::new(ip, port), len + 1 + width))
+}
+
+impl Recombobulate for SockAddrVariant {
+    fn recombobulate(&self) -> Result<Needle> {
+        match self.value() {
+            Some(sockaddr) => Ok(Needle::SockAddr(SockAddr::new(sockaddr)?)),
+            None => Err(anyhow!("Failed to recreate Needle::SockAddr")),
+        }
+    }
+}
+
+impl Interpret for SockAddrVariant {
+    fn interpret(data: &[u8]) -> Result<Vec<Self>>
+    where
+        Self: std::marker::Sized,
+    {
+        let mut interpretations = Vec::<Self>::new();
+
+        if let Some(bytes) = data.get(..8) {
+            for field in FamilyField::ALL {
+                if bytes[..2] == field.inet() {
+                    interpretations.push(SockAddrVariant::In(field, bytes.try_into()?));
+                }
+            }
+        }
+
+        // The flow information is almost always zero, and requiring it keeps the layout distinct from random bytes
+        if let Some(bytes) = data.get(..24) {
+            for field in FamilyField::ALL {
+                if field.inet6().contains(&[bytes[0], bytes[1]]) && bytes[4..8] == [0; 4] {
+                    interpretations.push(SockAddrVariant::In6(field, bytes.try_into()?));
+                }
+            }
+        }
+
+        if let Some(bytes) = data.get(..6) {
+            interpretations.push(SockAddrVariant::Packed(bytes.try_into()?));
+        }
+
+        if let Some(bytes) = data.get(..18) {
+            interpretations.push(SockAddrVariant::Packed6(bytes.try_into()?));
+        }
+
+        // Text has to start with an address, which starts with a digit or a bracket
+        if data
+            .first()
+            .is_some_and(|c| c.is_ascii_digit() || *c == b'[')
+        {
+            for encoding in TextEncoding::ALL {
+                let chars = encoding.decode(data, MAX_CHARS + 1);
+
+                if let Some((_, len)) = parse_text(&chars) {
+                    let bytes = data[..len * encoding.unit_len()].to_vec();
+                    interpretations.push(SockAddrVariant::Text(encoding, bytes));
+                }
+            }
+        }
+
+        if interpretations.is_empty() {
+            Err(anyhow!(
+                "Failed to interpret bytes as any valid SockAddrVariant!"
+            ))
+        } else {
+            Ok(interpretations)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::needle::{Discombobulate, Matches};
+
+    use super::*;
+
+    #[test]
+    fn sockaddr_recombobulation_test() {
+        for sockaddr in ["192.168.0.1:443", "[2001:db8::1]:8080"] {
+            let actual = SockAddr::new(sockaddr.parse().unwrap()).unwrap();
+
+            for variant in actual.discombobulate() {
+                let Needle::SockAddr(recombobulated) = variant.recombobulate().unwrap() else {
+                    panic!("{:?} didn't recombobulate to a SockAddr", variant);
+                };
+
+                assert!(recombobulated.matches(&actual), "{:?}", variant);
+            }
+        }
+    }
+
+    #[test]
+    fn sockaddr_in_test() {
+        // struct sockaddr_in for 127.0.0.1:8080 on x86 Linux, padding and all
+        let data = [
+            0x02, 0x00, 0x1f, 0x90, 0x7f, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ];
+
+        let variants = SockAddrVariant::interpret(&data).unwrap();
+        assert!(variants.contains(&SockAddrVariant::In(
+            FamilyField::LE,
+            data[..8].try_into().unwrap()
+        )));
+        assert_eq!(variants[0].value(), Some("127.0.0.1:8080".parse().unwrap()));
+
+        assert_eq!(
+            parse_text(b"[::1]:22 "),
+            Some(("[::1]:22".parse().unwrap(), 8))
+        );
+        assert_eq!(parse_text(b"127.0.0.1:080"), None);
+        assert_eq!(parse_text(b"127.0.0.1:65536"), None);
+    }
+}
@@ -0,0 +1,201 @@
+pub mod variant;
+
+use anyhow::{anyhow, Result};
+use variant::StringVariant;
+
+use super::{variant::NeedleVariant, Discombobulate, Matches};
+
+/// The most case forms of a case insensitive string that are searched for, beyond which it's only searched for in lower and upper case
+const MAX_CASE_FORMS: usize = 64;
+
+/// The ways a character can be written when case is ignored: as it is, or as its lower or upper case form, where that's a single character that lowers to the same thing
+///
+/// So ß matches ß but not SS, as the number of characters has to stay the same
+fn case_forms(c: char) -> Vec<char> {
+    let lower = c.to_lowercase().collect::<String>();
+    let mut forms = vec![c];
+
+    for form in [
+        c.to_lowercase().collect::<Vec<_>>(),
+        c.to_uppercase().collect(),
+    ] {
+        if let [form] = form[..] {
+            if form.to_lowercase().eq(lower.chars()) {
+                forms.push(form);
+            }
+        }
+    }
+
+    forms.sort();
+    forms.dedup();
+    forms
+}
+
+/// Some text, in whichever encoding and with whatever length prefix it was written out with
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct Str {
+    pub value: String,
+    pub case_insensitive: bool,
+    /// Only match strings that are followed by a null character
+    pub null_terminated: bool,
+}
+
+impl Str {
+    pub fn new(value: &str) -> Result<Self> {
+        Self::with_options(value, false, false)
+    }
+
+    pub fn with_options(
+        value: &str,
+        case_insensitive: bool,
+        null_terminated: bool,
+    ) -> Result<Self> {
+        if value.is_empty() {
+            return Err(anyhow!("Can't search for an empty string"));
+        }
+
+        Ok(Self {
+            value: value.to_string(),
+            case_insensitive,
+            null_terminated,
+        })
+    }
+
+    /// The most bytes any of the string's variants take up
+    pub fn max_len(&self) -> usize {
+        self.discombobulate()
+            .iter()
+            .map(NeedleVariant::byte_len)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl Matches for Str {
+    fn matches(&self, rhs: &Self) -> bool {
+        if rhs.null_terminated && !self.null_terminated {
+            return false;
+        }
+
+        if rhs.case_insensitive {
+            self.value.chars().count() == rhs.value.chars().count()
+                && self
+                    .value
+                    .chars()
+                    .zip(rhs.value.chars())
+                    .all(|(actual, target)| case_forms(target).contains(&actual))
+        } else {
+            self.value == rhs.value
+        }
+    }
+}
+
+impl Discombobulate for Str {
+    fn discombobulate(&self) -> Vec<NeedleVariant> {
+        // Searching ignores the case of ASCII letters, but not of anything else, so the string is searched for with every other letter in each of its case forms
+        let mut values = vec![self.value.clone()];
+        if self.case_insensitive && !self.value.is_ascii() {
+            let forms = self
+                .value
+                .chars()
+                .map(|c| match c.is_ascii() {
+                    true => vec![c],
+                    false => case_forms(c),
+                })
+                .collect::<Vec<_>>();
+
+            let count = forms
+                .iter()
+                .try_fold(1usize, |count, forms| count.checked_mul(forms.len()));
+
+            values = match count {
+                Some(count) if count <= MAX_CASE_FORMS => {
+                    forms.iter().fold(vec![String::new()], |values, forms| {
+                        values
+                            .iter()
+                            .flat_map(|value| forms.iter().map(move |c| format!("{value}{c}")))
+                            .collect()
+                    })
+                }
+                // Too many letters with another case to try every combination, so only the mixed case ones go unfound
+                _ => {
+                    let mut values = vec![
+                        self.value.clone(),
+                        self.value.to_lowercase(),
+                        self.value.to_uppercase(),
+                    ];
+                    values.retain(|value| Str::new(value).is_ok_and(|value| value.matches(self)));
+                    values
+                }
+            };
+            values.sort();
+            values.dedup();
+        }
+
+        values
+            .iter()
+            .flat_map(|value| StringVariant::all(value, self.null_terminated))
+            .map(NeedleVariant::String)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::needle::{Needle, Recombobulate};
+
+    use super::*;
+
+    #[test]
+    fn string_matches_test() {
+        let target = Str::new("Hello").unwrap();
+        assert!(Str::new("Hello").unwrap().matches(&target));
+        assert!(!Str::new("hello").unwrap().matches(&target));
+
+        let target = Str::with_options("Grüße", true, false).unwrap();
+        assert!(Str::new("GRÜßE").unwrap().matches(&target));
+        assert!(Str::new("grüße").unwrap().matches(&target));
+        assert!(Str::new("gRüßE").unwrap().matches(&target));
+        assert!(!Str::new("grusse").unwrap().matches(&target));
+        assert!(!Str::new("GRÜSSE").unwrap().matches(&target));
+
+        // A terminator is only required if the target asks for one
+        let terminated = Str::with_options("Hello", false, true).unwrap();
+        assert!(terminated.matches(&Str::new("Hello").unwrap()));
+        assert!(!Str::new("Hello").unwrap().matches(&terminated));
+
+        assert!(Str::new("").is_err());
+    }
+
+    #[test]
+    fn discombobulation_test() {
+        // The lower and upper case forms only add variants when there are non-ASCII letters
+        let ascii = Str::with_options("hello", true, false).unwrap();
+        assert_eq!(
+            ascii.discombobulate(),
+            Str::new("hello").unwrap().discombobulate()
+        );
+
+        let plain = Str::new("héllo").unwrap().discombobulate().len();
+        let insensitive = Str::with_options("héllo", true, false).unwrap();
+        assert_eq!(insensitive.discombobulate().len(), plain * 2);
+
+        // Every variant is one the string would match
+        let target = Str::with_options("Grüße", true, false).unwrap();
+        let variants = target.discombobulate();
+        assert_eq!(
+            variants.len(),
+            Str::new("Grüße").unwrap().discombobulate().len() * 2
+        );
+        for variant in variants {
+            let Ok(Needle::String(actual)) = variant.recombobulate() else {
+                panic!("{:?} didn't recombobulate", variant);
+            };
+            assert!(actual.matches(&target), "{:?}", actual);
+        }
+
+        let terminated = Str::with_options("hello", false, true).unwrap();
+        assert_eq!(terminated.discombobulate().len(), 5);
+        assert_eq!(terminated.max_len(), 24);
+    }
+}
@@ -0,0 +1,463 @@
+use anyhow::{anyhow, Result};
+use integer_encoding::VarInt;
+
+use crate::needle::{Interpret, Needle, Recombobulate};
+
+use super::Str;
+
+/// How a string's characters are laid out in a haystack
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Hash)]
+pub enum StringEncoding {
+    /// Only used for strings that are entirely ASCII, which are byte for byte the same in UTF-8
+    Ascii,
+    /// Only used for strings that aren't entirely ASCII
+    UTF8,
+    UTF16LE,
+    UTF16BE,
+    UTF32LE,
+    UTF32BE,
+}
+
+impl StringEncoding {
+    pub const ALL: [StringEncoding; 6] = [
+        StringEncoding::Ascii,
+        StringEncoding::UTF8,
+        StringEncoding::UTF16LE,
+        StringEncoding::UTF16BE,
+        StringEncoding::UTF32LE,
+        StringEncoding::UTF32BE,
+    ];
+
+    /// How many bytes each code unit takes up
+    pub fn unit_len(self) -> usize {
+        match self {
+            StringEncoding::Ascii | StringEncoding::UTF8 => 1,
+            StringEncoding::UTF16LE | StringEncoding::UTF16BE => 2,
+            StringEncoding::UTF32LE | StringEncoding::UTF32BE => 4,
+        }
+    }
+
+    /// The encodings a string can be laid out in, which is ASCII or UTF-8 depending on its characters, and all the wider ones
+    pub fn for_str(value: &str) -> impl Iterator<Item = StringEncoding> {
+        let ascii = value.is_ascii();
+
+        StringEncoding::ALL
+            .into_iter()
+            .filter(move |encoding| match encoding {
+                StringEncoding::Ascii => ascii,
+                StringEncoding::UTF8 => !ascii,
+                _ => true,
+            })
+    }
+
+    pub fn encode(self, value: &str) -> Vec<u8> {
+        match self {
+            StringEncoding::Ascii | StringEncoding::UTF8 => value.as_bytes().to_vec(),
+            StringEncoding::UTF16LE => value.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            StringEncoding::UTF16BE => value.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+            StringEncoding::UTF32LE => value
+                .chars()
+                .flat_map(|c| (c as u32).to_le_bytes())
+                .collect(),
+            StringEncoding::UTF32BE => value
+                .chars()
+                .flat_map(|c| (c as u32).to_be_bytes())
+                .collect(),
+        }
+    }
+
+    /// Decode all of the bytes, if they're a valid string in this encoding
+    pub fn decode(self, bytes: &[u8]) -> Option<String> {
+        if !bytes.len().is_multiple_of(self.unit_len()) {
+            return None;
+        }
+
+        let units = bytes.chunks_exact(self.unit_len());
+        let value = match self {
+            StringEncoding::Ascii => bytes
+                .is_ascii()
+                .then(|| String::from_utf8_lossy(bytes).into_owned())?,
+            StringEncoding::UTF8 => std::str::from_utf8(bytes).ok()?.to_string(),
+            StringEncoding::UTF16LE => String::from_utf16(
+                &units
+                    .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                    .collect::<Vec<_>>(),
+            )
+            .ok()?,
+            StringEncoding::UTF16BE => String::from_utf16(
+                &units
+                    .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                    .collect::<Vec<_>>(),
+            )
+            .ok()?,
+            StringEncoding::UTF32LE => units
+                .map(|unit| char::from_u32(u32::from_le_bytes(unit.try_into().ok()?)))
+                .collect::<Option<String>>()?,
+            StringEncoding::UTF32BE => units
+                .map(|unit| char::from_u32(u32::from_be_bytes(unit.try_into().ok()?)))
+                .collect::<Option<String>>()?,
+        };
+
+        // Non-ASCII strings are always laid out as UTF-8, so the two never overlap
+        (self != StringEncoding::UTF8 || !value.is_ascii()).then_some(value)
+    }
+
+    /// How many bytes of the data, up to `max` characters, make up a run of printable characters, and whether a null character follows them
+    fn printable(self, data: &[u8], max: usize) -> (usize, bool) {
+        let mut len = 0;
+
+        for _ in 0..max {
+            let rest = &data[len..];
+            let next = match self {
+                StringEncoding::Ascii | StringEncoding::UTF8 => {
+                    // No UTF-8 character is longer than four bytes
+                    let prefix = &rest[..rest.len().min(4)];
+                    let valid = match std::str::from_utf8(prefix) {
+                        Ok(valid) => valid,
+                        Err(e) => std::str::from_utf8(&prefix[..e.valid_up_to()]).unwrap_or(""),
+                    };
+                    valid.chars().next().map(|c| (c, c.len_utf8()))
+                }
+                StringEncoding::UTF16LE | StringEncoding::UTF16BE => {
+                    let units = rest
+                        .chunks_exact(2)
+                        .take(2)
+                        .map(|unit| match self {
+                            StringEncoding::UTF16LE => u16::from_le_bytes([unit[0], unit[1]]),
+                            _ => u16::from_be_bytes([unit[0], unit[1]]),
+                        })
+                        .collect::<Vec<_>>();
+                    char::decode_utf16(units)
+                        .next()
+                        .and_then(Result::ok)
+                        .map(|c| (c, c.len_utf16() * 2))
+                }
+                StringEncoding::UTF32LE | StringEncoding::UTF32BE => rest
+                    .get(..4)
+                    .and_then(|unit| {
+                        char::from_u32(match self {
+                            StringEncoding::UTF32LE => u32::from_le_bytes(unit.try_into().ok()?),
+                            _ => u32::from_be_bytes(unit.try_into().ok()?),
+                        })
+                    })
+                    .map(|c| (c, 4)),
+            };
+
+            match next {
+                Some((c, width)) if !c.is_control() => len += width,
+                _ => break,
+            }
+        }
+
+        let terminated = data
+            .get(len..len + self.unit_len())
+            .is_some_and(|unit| unit.iter().all(|b| *b == 0));
+
+        (len, terminated)
+    }
+}
+
+/// How the length of a string is given before it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Hash)]
+pub enum LengthPrefix {
+    /// As in a Pascal string
+    U8,
+    U16LE,
+    U16BE,
+    U32LE,
+    U32BE,
+    /// As in protobuf, or .NET's BinaryWriter
+    Varint,
+}
+
+impl LengthPrefix {
+    pub const ALL: [LengthPrefix; 6] = [
+        LengthPrefix::U8,
+        LengthPrefix::U16LE,
+        LengthPrefix::U16BE,
+        LengthPrefix::U32LE,
+        LengthPrefix::U32BE,
+        LengthPrefix::Varint,
+    ];
+
+    /// The prefix for a string this many bytes long, if it can hold it
+    pub fn encode(self, len: usize) -> Option<Vec<u8>> {
+        Some(match self {
+            LengthPrefix::U8 => u8::try_from(len).ok()?.to_le_bytes().to_vec(),
+            LengthPrefix::U16LE => u16::try_from(len).ok()?.to_le_bytes().to_vec(),
+            LengthPrefix::U16BE => u16::try_from(len).ok()?.to_be_bytes().to_vec(),
+            LengthPrefix::U32LE => u32::try_from(len).ok()?.to_le_bytes().to_vec(),
+            LengthPrefix::U32BE => u32::try_from(len).ok()?.to_be_bytes().to_vec(),
+            LengthPrefix::Varint => (len as u64).encode_var_vec(),
+        })
+    }
+
+    /// Read a prefix from the start of the data, returning the length it gives and how many bytes it took up
+    ///
+    /// Varints have to be as short as they can be, so each length has exactly one prefix
+    pub fn decode(self, data: &[u8]) -> Option<(usize, usize)> {
+        let (len, width) = match self {
+            LengthPrefix::U8 => (*data.first()? as usize, 1),
+            LengthPrefix::U16LE => (
+                u16::from_le_bytes(data.get(..2)?.try_into().ok()?) as usize,
+                2,
+            ),
+            LengthPrefix::U16BE => (
+                u16::from_be_bytes(data.get(..2)?.try_into().ok()?) as usize,
+                2,
+            ),
+            LengthPrefix::U32LE => (
+                u32::from_le_bytes(data.get(..4)?.try_into().ok()?) as usize,
+                4,
+            ),
+            LengthPrefix::U32BE => (
+                u32::from_be_bytes(data.get(..4)?.try_into().ok()?) as usize,
+                4,
+            ),
+            LengthPrefix::Varint => {
+                let (len, width) = u64::decode_var(data)?;
+                (usize::try_from(len).ok()?, width)
+            }
+        };
+
+        (width == self.encode(len)?.len()).then_some((len, width))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum StringVariant {
+    /// The characters on their own, e.g. inside some longer text
+    Bare(StringEncoding, Vec<u8>),
+    /// Followed by a null character, as in C
+    Terminated(StringEncoding, Vec<u8>),
+    /// After its length in bytes
+    Prefixed(LengthPrefix, StringEncoding, Vec<u8>),
+}
+
+impl StringVariant {
+    /// The most characters a string is read as when interpreting the haystack
+    pub const MAX_CHARS: usize = 256;
+
+    pub fn byte_len(&self) -> usize {
+        self.byte_sequence().len()
+    }
+
+    pub fn byte_sequence(&self) -> &[u8] {
+        match self {
+            StringVariant::Bare(_, bytes)
+            | StringVariant::Terminated(_, bytes)
+            | StringVariant::Prefixed(_, _, bytes) => bytes,
+        }
+    }
+
+    pub fn encoding(&self) -> StringEncoding {
+        match self {
+            StringVariant::Bare(encoding, _)
+            | StringVariant::Terminated(encoding, _)
+            | StringVariant::Prefixed(_, encoding, _) => *encoding,
+        }
+    }
+
+    pub fn prefix(&self) -> Option<LengthPrefix> {
+        match self {
+            StringVariant::Prefixed(prefix, _, _) => Some(*prefix),
+            _ => None,
+        }
+    }
+
+    /// The same variant laid out as some other bytes, e.g. the ones found by a case insensitive search
+    pub fn with_bytes(&self, bytes: &[u8]) -> Self {
+        match self {
+            StringVariant::Bare(encoding, _) => StringVariant::Bare(*encoding, bytes.to_vec()),
+            StringVariant::Terminated(encoding, _) => {
+                StringVariant::Terminated(*encoding, bytes.to_vec())
+            }
+            StringVariant::Prefixed(prefix, encoding, _) => {
+                StringVariant::Prefixed(*prefix, *encoding, bytes.to_vec())
+            }
+        }
+    }
+
+    /// Every way a string can be laid out, which with a null terminator is only ever bare
+    pub fn all(value: &str, null_terminated: bool) -> Vec<Self> {
+        let mut variants = Vec::new();
+
+        for encoding in StringEncoding::for_str(value) {
+            let text = encoding.encode(value);
+
+            if null_terminated {
+                let mut bytes = text;
+                bytes.extend(vec![0u8; encoding.unit_len()]);
+                variants.push(StringVariant::Terminated(encoding, bytes));
+                continue;
+            }
+
+            for prefix in LengthPrefix::ALL {
+                if let Some(mut bytes) = prefix.encode(text.len()) {
+                    bytes.extend(&text);
+                    variants.push(StringVariant::Prefixed(prefix, encoding, bytes));
+                }
+            }
+
+            variants.push(StringVariant::Bare(encoding, text));
+        }
+
+        variants
+    }
+}
+
+impl Recombobulate for StringVariant {
+    fn recombobulate(&self) -> Result<Needle> {
+        let (value, null_terminated) = match self {
+            StringVariant::Bare(encoding, bytes) => (encoding.decode(bytes), false),
+            StringVariant::Terminated(encoding, bytes) => {
+                let (text, terminator) = bytes
+                    .split_at_checked(bytes.len().saturating_sub(encoding.unit_len()))
+                    .ok_or_else(|| anyhow!("Terminated string is too short: {:02x?}", bytes))?;
+
+                if terminator.iter().any(|b| *b != 0) {
+                    return Err(anyhow!("String isn't null terminated: {:02x?}", bytes));
+                }
+                (encoding.decode(text), true)
+            }
+            StringVariant::Prefixed(prefix, encoding, bytes) => {
+                let (len, width) = prefix
+                    .decode(bytes)
+                    .ok_or_else(|| anyhow!("Invalid {:?} length prefix: {:02x?}", prefix, bytes))?;
+
+                if width + len != bytes.len() {
+                    return Err(anyhow!(
+                        "{:?} length prefix doesn't match the string: {:02x?}",
+                        prefix,
+                        bytes
+                    ));
+                }
+                (encoding.decode(&bytes[width..]), false)
+            }
+        };
+
+        let value = value
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| anyhow!("Invalid {:?} string: {:02x?}", self.encoding(), self))?;
+
+        Ok(Needle::String(Str {
+            value,
+            case_insensitive: false,
+            null_terminated,
+        }))
+    }
+}
+
+impl Interpret for StringVariant {
+    fn interpret(data: &[u8]) -> Result<Vec<Self>> {
+        let mut interpretations = Vec::<StringVariant>::new();
+
+        for encoding in StringEncoding::ALL {
+            // UTF-8 reads the same characters as ASCII, along with everything else, so it's left to decoding to tell them apart
+            if encoding == StringEncoding::Ascii {
+                continue;
+            }
+
+            let (len, terminated) = encoding.printable(data, Self::MAX_CHARS);
+            if len == 0 {
+                continue;
+            }
+
+            // UTF-8 that turns out to be ASCII is read as ASCII
+            let encoding = match encoding {
+                StringEncoding::UTF8 if data[..len].is_ascii() => StringEncoding::Ascii,
+                _ => encoding,
+            };
+
+            interpretations.push(StringVariant::Bare(encoding, data[..len].to_vec()));
+            if terminated {
+                let bytes = data[..len + encoding.unit_len()].to_vec();
+                interpretations.push(StringVariant::Terminated(encoding, bytes));
+            }
+        }
+
+        for prefix in LengthPrefix::ALL {
+            let Some((len, width)) = prefix.decode(data) else {
+                continue;
+            };
+            if len == 0 || len > Self::MAX_CHARS * 4 {
+                continue;
+            }
+            let Some(bytes) = data.get(..width + len) else {
+                continue;
+            };
+
+            for encoding in StringEncoding::ALL {
+                if encoding.decode(&bytes[width..]).is_some() {
+                    interpretations.push(StringVariant::Prefixed(prefix, encoding, bytes.to_vec()));
+                }
+            }
+        }
+
+        if interpretations.is_empty() {
+            Err(anyhow!(
+                "Failed to interpret bytes as any valid StringVariant!"
+            ))
+        } else {
+            Ok(interpretations)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recombobulation_test() {
+        for value in ["hello", "héllo wörld", "🦀"] {
+            let variants = StringVariant::all(value, false);
+            assert_eq!(variants.len(), 5 * (LengthPrefix::ALL.len() + 1));
+
+            for variant in variants.iter().chain(&StringVariant::all(value, true)) {
+                match variant.recombobulate().unwrap() {
+                    Needle::String(string) => assert_eq!(string.value, value),
+                    needle => panic!("{:?} isn't a string", needle),
+                }
+            }
+        }
+
+        let variant = StringVariant::Prefixed(
+            LengthPrefix::U8,
+            StringEncoding::Ascii,
+            b"\x05hello".to_vec(),
+        );
+        assert!(variant.recombobulate().is_ok());
+        assert!(variant.with_bytes(b"\x06hello").recombobulate().is_err());
+
+        // Each length has a single varint prefix
+        assert_eq!(LengthPrefix::Varint.encode(300), Some(vec![0xac, 0x02]));
+        assert_eq!(LengthPrefix::Varint.decode(&[0x85, 0x00]), None);
+        assert_eq!(LengthPrefix::U8.encode(256), None);
+    }
+
+    #[test]
+    fn interpret_test() {
+        let mut data = StringEncoding::UTF16LE.encode("hello");
+        data.extend([0, 0, 0xff]);
+
+        let variants = StringVariant::interpret(&data).unwrap();
+        assert!(variants.contains(&StringVariant::Bare(
+            StringEncoding::UTF16LE,
+            data[..10].to_vec()
+        )));
+        assert!(variants.contains(&StringVariant::Terminated(
+            StringEncoding::UTF16LE,
+            data[..12].to_vec()
+        )));
+
+        // A .NET BinaryWriter string
+        let data = b"\x0bhello world\x01\x02";
+        let variants = StringVariant::interpret(data).unwrap();
+        assert!(variants.contains(&StringVariant::Prefixed(
+            LengthPrefix::Varint,
+            StringEncoding::Ascii,
+            data[..12].to_vec()
+        )));
+    }
+}
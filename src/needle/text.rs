@@ -0,0 +1,76 @@
+/// How text is laid out in a haystack
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Hash)]
+pub enum TextEncoding {
+    Ascii,
+    UTF16LE,
+}
+
+impl TextEncoding {
+    pub const ALL: [TextEncoding; 2] = [TextEncoding::Ascii, TextEncoding::UTF16LE];
+
+    /// The most bytes any encoding takes up per character
+    pub const MAX_UNIT_LEN: usize = 2;
+
+    /// How many bytes each character takes up
+    pub fn unit_len(self) -> usize {
+        match self {
+            TextEncoding::Ascii => 1,
+            TextEncoding::UTF16LE => 2,
+        }
+    }
+
+    /// Lay out ASCII text in this encoding
+    pub fn encode(self, text: &str) -> Vec<u8> {
+        match self {
+            TextEncoding::Ascii => text.as_bytes().to_vec(),
+            TextEncoding::UTF16LE => text.bytes().flat_map(|c| [c, 0]).collect(),
+        }
+    }
+
+    /// Read up to `max` ASCII characters from the start of the data, stopping at the first thing that isn't one
+    pub fn decode(self, data: &[u8], max: usize) -> Vec<u8> {
+        match self {
+            TextEncoding::Ascii => data
+                .iter()
+                .take(max)
+                .take_while(|c| c.is_ascii())
+                .copied()
+                .collect(),
+            TextEncoding::UTF16LE => data
+                .chunks_exact(2)
+                .take(max)
+                .take_while(|unit| unit[0].is_ascii() && unit[1] == 0)
+                .map(|unit| unit[0])
+                .collect(),
+        }
+    }
+
+    /// The ASCII character at the very end of the data, if it ends with one
+    pub fn decode_last(self, data: &[u8]) -> Option<u8> {
+        let unit = data.get(data.len().checked_sub(self.unit_len())?..)?;
+        self.decode(unit, 1).first().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_test() {
+        for encoding in TextEncoding::ALL {
+            let encoded = encoding.encode("192.168.0.1");
+            assert_eq!(encoded.len(), 11 * encoding.unit_len());
+
+            // Anything after the text shouldn't be read
+            let mut data = encoded.clone();
+            data.extend([0xff, 0xff]);
+            assert_eq!(encoding.decode(&data, 64), b"192.168.0.1");
+            assert_eq!(encoding.decode(&data, 3), b"192");
+
+            // As should only the last character
+            assert_eq!(encoding.decode_last(&encoded), Some(b'1'));
+            assert_eq!(encoding.decode_last(&data), None);
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use anyhow::{anyhow, Result};
+use time::{OffsetDateTime, PrimitiveDateTime};
+
+use crate::needle::number::encoding::{FloatEncoding, IntegerEncoding};
+
+use super::Timestamp;
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+/// What a timestamp counting from an epoch counts in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Hash)]
+pub enum EpochUnit {
+    Seconds,
+    Nanos,
+    Days,
+}
+
+impl EpochUnit {
+    pub fn nanos(self) -> i128 {
+        match self {
+            EpochUnit::Seconds => NANOS_PER_SEC,
+            EpochUnit::Nanos => 1,
+            EpochUnit::Days => 86_400 * NANOS_PER_SEC,
+        }
+    }
+}
+
+/// Timestamps that count in a fixed unit from some other epoch than the Unix one
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Hash)]
+pub enum Epoch {
+    /// Apple Cocoa/Core Data, seconds since 2001-01-01, often as an f64
+    Cocoa,
+    /// Apple Cocoa/Core Data, nanoseconds since 2001-01-01
+    CocoaNanos,
+    /// Mac HFS+, seconds since 1904-01-01
+    HFSPlus,
+    /// SAS datetimes, seconds since 1960-01-01
+    SAS,
+    /// GPS, seconds since 1980-01-06, without the leap seconds GPS time has gained since
+    GPS,
+    /// Excel and OLE Automation dates, days since 1899-12-30
+    OLE,
+    /// Days since noon on 4714-11-24 BC in the proleptic Gregorian calendar
+    JulianDay,
+    /// Days since 1858-11-17, which is the Julian Day less 2400000.5
+    ModifiedJulianDay,
+}
+
+impl Epoch {
+    pub const ALL: [Epoch; 8] = [
+        Epoch::Cocoa,
+        Epoch::CocoaNanos,
+        Epoch::HFSPlus,
+        Epoch::SAS,
+        Epoch::GPS,
+        Epoch::OLE,
+        Epoch::JulianDay,
+        Epoch::ModifiedJulianDay,
+    ];
+
+    /// Seconds from the Unix epoch to this one
+    pub fn origin(self) -> i64 {
+        match self {
+            Epoch::Cocoa | Epoch::CocoaNanos => 978_307_200,
+            Epoch::HFSPlus => -2_082_844_800,
+            Epoch::SAS => -315_619_200,
+            Epoch::GPS => 315_964_800,
+            Epoch::OLE => -2_209_161_600,
+            Epoch::JulianDay => -210_866_760_000,
+            Epoch::ModifiedJulianDay => -3_506_716_800,
+        }
+    }
+
+    pub fn unit(self) -> EpochUnit {
+        match self {
+            Epoch::CocoaNanos => EpochUnit::Nanos,
+            Epoch::OLE | Epoch::JulianDay | Epoch::ModifiedJulianDay => EpochUnit::Days,
+            _ => EpochUnit::Seconds,
+        }
+    }
+
+    /// The integer encodings timestamps from this epoch are stored as, which are only those wide enough to hold a useful range of them
+    pub fn integer_encodings(self) -> &'static [IntegerEncoding] {
+        match self {
+            Epoch::OLE | Epoch::JulianDay => &[],
+            Epoch::CocoaNanos => &[
+                IntegerEncoding::U64LE,
+                IntegerEncoding::U64BE,
+                IntegerEncoding::U64Varint,
+                IntegerEncoding::I64LE,
+                IntegerEncoding::I64BE,
+                IntegerEncoding::I64Varint,
+            ],
+            Epoch::ModifiedJulianDay => &[
+                IntegerEncoding::U16LE,
+                IntegerEncoding::U16BE,
+                IntegerEncoding::U16Varint,
+                IntegerEncoding::U32LE,
+                IntegerEncoding::U32BE,
+                IntegerEncoding::U32Varint,
+                IntegerEncoding::I32LE,
+                IntegerEncoding::I32BE,
+                IntegerEncoding::I32Varint,
+            ],
+            _ => &[
+                IntegerEncoding::U32LE,
+                IntegerEncoding::U32BE,
+                IntegerEncoding::U32Varint,
+                IntegerEncoding::I32LE,
+                IntegerEncoding::I32BE,
+                IntegerEncoding::I32Varint,
+                IntegerEncoding::U64LE,
+                IntegerEncoding::U64BE,
+                IntegerEncoding::U64Varint,
+                IntegerEncoding::I64LE,
+                IntegerEncoding::I64BE,
+                IntegerEncoding::I64Varint,
+            ],
+        }
+    }
+
+    /// The float encodings timestamps from this epoch are stored as, which are never f32s, as they can't even hold the minute
+    pub fn float_encodings(self) -> &'static [FloatEncoding] {
+        match self {
+            Epoch::CocoaNanos | Epoch::HFSPlus => &[],
+            _ => &[FloatEncoding::F64LE, FloatEncoding::F64BE],
+        }
+    }
+
+    /// Nanoseconds from this epoch to the timestamp
+    fn nanos_since(self, timestamp: &Timestamp) -> i128 {
+        timestamp.value.assume_utc().unix_timestamp_nanos() - self.origin() as i128 * NANOS_PER_SEC
+    }
+
+    /// The timestamp as a whole number of units since this epoch, if it is one
+    pub fn to_integer(self, timestamp: &Timestamp) -> Option<i64> {
+        let nanos = self.nanos_since(timestamp);
+        let unit = self.unit().nanos();
+
+        match nanos % unit {
+            0 => (nanos / unit).try_into().ok(),
+            _ => None,
+        }
+    }
+
+    /// The timestamp as a fractional number of units since this epoch
+    pub fn to_float(self, timestamp: &Timestamp) -> f64 {
+        let nanos = self.nanos_since(timestamp);
+        let unit = self.unit().nanos();
+
+        // Splitting off the whole units first keeps the fraction from losing precision
+        (nanos.div_euclid(unit) as f64) + (nanos.rem_euclid(unit) as f64 / unit as f64)
+    }
+
+    /// The raw integer a number of seconds since the Unix epoch would be stored as, rounded down
+    pub fn raw_integer(self, unix_secs: i64) -> i64 {
+        let nanos = (unix_secs as i128 - self.origin() as i128) * NANOS_PER_SEC;
+        nanos
+            .div_euclid(self.unit().nanos())
+            .clamp(i64::MIN as i128, i64::MAX as i128) as i64
+    }
+
+    /// The raw float a number of seconds since the Unix epoch would be stored as
+    pub fn raw_float(self, unix_secs: i64) -> f64 {
+        let nanos = (unix_secs as i128 - self.origin() as i128) * NANOS_PER_SEC;
+        nanos as f64 / self.unit().nanos() as f64
+    }
+
+    pub fn from_integer(self, value: i64) -> Result<Timestamp> {
+        let nanos = value as i128 * self.unit().nanos();
+        self.nanos_after(nanos)
+    }
+
+    /// Floats can't hold every nanosecond, so the timestamp is rounded to the nearest power of ten nanoseconds that they can
+    pub fn from_float(self, value: f64) -> Result<Timestamp> {
+        if !value.is_finite() {
+            return Err(anyhow!("{} isn't a valid {:?} timestamp", value, self));
+        }
+
+        // Anything that far out would overflow rather than just fail to be a valid date
+        let nanos = value * self.unit().nanos() as f64;
+        if nanos.abs() > 1e30 {
+            return Err(anyhow!(
+                "{} is out of range for a {:?} timestamp",
+                value,
+                self
+            ));
+        }
+
+        let precision = nanos.abs() * f64::EPSILON * 2.0;
+        let mut step = 1i128;
+        while (step as f64) < precision {
+            step *= 10;
+        }
+
+        let nanos = (nanos / step as f64).round() as i128 * step;
+        self.nanos_after(nanos)
+    }
+
+    /// The timestamp a number of nanoseconds after this epoch
+    fn nanos_after(self, nanos: i128) -> Result<Timestamp> {
+        let nanos = nanos + self.origin() as i128 * NANOS_PER_SEC;
+
+        if let Ok(dtg) = OffsetDateTime::from_unix_timestamp_nanos(nanos) {
+            Ok(Timestamp::new(PrimitiveDateTime::new(
+                dtg.date(),
+                dtg.time(),
+            )))
+        } else {
+            Err(anyhow!(
+                "Failed to recreate Needle::Timestamp from {:?} value",
+                self
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn epoch_test() {
+        // Every epoch should start where it says it does
+        for (epoch, start) in [
+            (Epoch::Cocoa, datetime!(2001-01-01 00:00:00)),
+            (Epoch::HFSPlus, datetime!(1904-01-01 00:00:00)),
+            (Epoch::SAS, datetime!(1960-01-01 00:00:00)),
+            (Epoch::GPS, datetime!(1980-01-06 00:00:00)),
+            (Epoch::OLE, datetime!(1899-12-30 00:00:00)),
+            (Epoch::ModifiedJulianDay, datetime!(1858-11-17 00:00:00)),
+        ] {
+            assert_eq!(epoch.from_integer(0).unwrap().value, start, "{:?}", epoch);
+            assert_eq!(epoch.to_integer(&Timestamp::new(start)), Some(0));
+        }
+
+        // The Unix epoch is Julian Day 2440587.5
+        let unix = Timestamp::new(datetime!(1970-01-01 00:00:00));
+        assert_eq!(Epoch::JulianDay.to_float(&unix), 2_440_587.5);
+        assert_eq!(Epoch::JulianDay.to_integer(&unix), None);
+        assert_eq!(Epoch::ModifiedJulianDay.to_integer(&unix), Some(40_587));
+
+        // Times round trip through floats, even when they're a fraction of a day
+        let dtg = Timestamp::new(datetime!(2023-12-31 23:59:58));
+        for epoch in Epoch::ALL {
+            let value = epoch.to_float(&dtg);
+            assert_eq!(epoch.from_float(value).unwrap(), dtg, "{:?}", epoch);
+        }
+
+        assert_eq!(Epoch::Cocoa.to_float(&dtg), 725_759_998.0);
+        assert_eq!(Epoch::OLE.to_integer(&dtg), None);
+        assert!((Epoch::OLE.to_float(&dtg) - 45_291.999_976_851_85).abs() < 1e-9);
+
+        // Cocoa timestamps often have a fraction of a second
+        let fractional = Timestamp::new(datetime!(2023-12-31 23:59:58.25));
+        assert_eq!(Epoch::Cocoa.to_float(&fractional), 725_759_998.25);
+        assert_eq!(Epoch::Cocoa.from_float(725_759_998.25).unwrap(), fractional);
+        assert_eq!(
+            Epoch::CocoaNanos.to_integer(&fractional),
+            Some(725_759_998_250_000_000)
+        );
+
+        assert!(Epoch::OLE.from_float(f64::NAN).is_err());
+        assert!(Epoch::JulianDay.from_float(1e300).is_err());
+    }
+}
@@ -1,16 +1,35 @@
+pub mod epoch;
 pub mod variants;
 
-use time::{Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time};
+use time::{
+    macros::format_description, Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time,
+    UtcOffset,
+};
 
 use anyhow::{anyhow, Result};
 
-use self::variants::TimestampVariant::*;
+use self::epoch::Epoch;
+use self::variants::TimestampVariant::{self, *};
+use super::number::variants::IntegerVariant;
+use super::number::Integer;
 use super::variant::NeedleVariant;
 use super::Discombobulate;
 
 use super::Matches;
 use super::Needle;
 
+/// Seconds between the start of 1601, which FILETIME and WebKit timestamps count from, and the Unix epoch
+pub const SECS_FROM_1601_TO_EPOCH: i64 = 11_644_473_600;
+
+/// Seconds between the start of 1900, which NTP timestamps count from, and the Unix epoch
+pub const SECS_FROM_1900_TO_EPOCH: i64 = 2_208_988_800;
+
+/// The short NTP format only holds the low 16 bits of the seconds, so it wraps around every 18 hours or so
+pub const NTP_SHORT_WRAP_SECS: i64 = 1 << 16;
+
+/// PTP counts seconds in 48 bits
+const PTP_MAX_SECS: u64 = (1 << 48) - 1;
+
 pub fn u8_to_month(value: u8) -> Option<Month> {
     match value {
         1 => Some(Month::January),
@@ -32,8 +51,11 @@ pub fn u8_to_month(value: u8) -> Option<Month> {
 //#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub struct Timestamp {
+    /// The time in UTC
     pub value: PrimitiveDateTime,
     pub tolerance: Option<Duration>,
+    /// The UTC offsets the time may have been stored in as local time, where none means it was stored in UTC
+    pub offsets: Vec<UtcOffset>,
 }
 
 impl Timestamp {
@@ -41,6 +63,7 @@ impl Timestamp {
         Self {
             value,
             tolerance: None,
+            offsets: Vec::new(),
         }
     }
 
@@ -48,9 +71,82 @@ impl Timestamp {
         Self {
             value,
             tolerance: Some(tolerance),
+            offsets: Vec::new(),
+        }
+    }
+
+    /// A UTC time that may have been stored as local time in any of the given offsets
+    pub fn with_offsets(value: PrimitiveDateTime, offsets: &[UtcOffset]) -> Self {
+        let mut offsets = offsets.to_vec();
+        offsets.sort();
+        offsets.dedup();
+
+        Self {
+            value,
+            tolerance: None,
+            offsets,
         }
     }
 
+    /// A time with a known offset, which may have been stored either as that local time or in UTC
+    pub fn with_offset(value: OffsetDateTime) -> Self {
+        let utc = value.to_offset(UtcOffset::UTC);
+        Self::with_offsets(
+            PrimitiveDateTime::new(utc.date(), utc.time()),
+            &[UtcOffset::UTC, value.offset()],
+        )
+    }
+
+    /// Every whole-hour offset in use, from UTC-12:00 to UTC+14:00
+    pub fn whole_hour_offsets() -> Vec<UtcOffset> {
+        (-12..=14)
+            .filter_map(|hours| UtcOffset::from_hms(hours, 0, 0).ok())
+            .collect()
+    }
+
+    /// The offsets the time may have been stored in, which is just UTC unless others were given
+    pub fn candidate_offsets(&self) -> Vec<UtcOffset> {
+        match self.offsets.is_empty() {
+            true => vec![UtcOffset::UTC],
+            false => self.offsets.clone(),
+        }
+    }
+
+    /// The time as it would have been stored in each of its candidate offsets
+    pub fn local_times(&self) -> Vec<(UtcOffset, PrimitiveDateTime)> {
+        self.candidate_offsets()
+            .into_iter()
+            .filter_map(|offset| {
+                let local = self
+                    .value
+                    .checked_add(Duration::seconds(offset.whole_seconds() as i64))?;
+                Some((offset, local))
+            })
+            .collect()
+    }
+
+    /// Which of this needle's offsets an actual timestamp was stored in, taking the closest if it could be several
+    pub fn matching_offset(&self, actual: &Timestamp) -> Option<UtcOffset> {
+        let tolerance = self.tolerance.map_or(Duration::ZERO, Duration::abs);
+
+        self.local_times()
+            .into_iter()
+            .map(|(offset, local)| (offset, (actual.value - local).abs()))
+            .filter(|(_, difference)| *difference <= tolerance)
+            .min_by_key(|(_, difference)| *difference)
+            .map(|(offset, _)| offset)
+    }
+
+    /// Parse a timestamp like "2023-12-31 23:59:58", which can have up to nine digits of a fraction of a second, e.g. "2023-12-31 23:59:58.123"
+    pub fn parse(dtg: &str) -> Result<PrimitiveDateTime> {
+        let format = format_description!(
+            "[year]-[month]-[day] [hour]:[minute]:[second][optional [.[subsecond]]]"
+        );
+
+        PrimitiveDateTime::parse(dtg, format)
+            .map_err(|_| anyhow!("Failed to parse timestamp string: {}", dtg))
+    }
+
     /*
 
         The DOS date/time format is a bitmask:
@@ -79,6 +175,63 @@ impl Timestamp {
         self.value.assume_utc().unix_timestamp() // as i32
     }
 
+    /// 100ns ticks since 1601, as Windows FILETIMEs and NTFS store them
+    pub fn to_filetime(&self) -> i64 {
+        let nanos = self.value.assume_utc().unix_timestamp_nanos();
+        (nanos.div_euclid(100) + SECS_FROM_1601_TO_EPOCH as i128 * 10_000_000) as i64
+    }
+
+    /// Microseconds since 1601, as WebKit and Chrome store them
+    pub fn to_webkit(&self) -> i64 {
+        let nanos = self.value.assume_utc().unix_timestamp_nanos();
+        (nanos.div_euclid(1000) + SECS_FROM_1601_TO_EPOCH as i128 * 1_000_000) as i64
+    }
+
+    /// NTP seconds and 32 bit fraction of a second since 1900, if the timestamp is in one of the eras an NTP timestamp can be read back into
+    ///
+    /// NTP seconds wrap around in 2036, so timestamps are only taken to be from 1968 to 2104, the years either side of it that the seconds alone can tell apart
+    pub fn to_ntp(&self) -> Option<(u32, u32)> {
+        let nanos = self.value.assume_utc().unix_timestamp_nanos();
+        let secs = nanos.div_euclid(1_000_000_000) + SECS_FROM_1900_TO_EPOCH as i128;
+        let nanos = nanos.rem_euclid(1_000_000_000);
+
+        if !(1 << 31..(1 << 31) + (1 << 32)).contains(&secs) {
+            return None;
+        }
+
+        // Rounding to the nearest fraction, rather than truncating, means the nanoseconds can always be read back exactly
+        let fraction = ((nanos << 32) + 500_000_000) / 1_000_000_000;
+        if fraction > u32::MAX as i128 {
+            return None;
+        }
+
+        Some((secs as u32, fraction as u32))
+    }
+
+    /// The low 16 bits of the NTP seconds and a 16 bit fraction of a second, as the short NTP format holds them
+    pub fn to_ntp_short(&self) -> (u16, u16) {
+        let nanos = self.value.assume_utc().unix_timestamp_nanos()
+            + SECS_FROM_1900_TO_EPOCH as i128 * 1_000_000_000;
+
+        // In 65536ths of a second, rounded to the nearest
+        let ticks = ((nanos << 16) + 500_000_000).div_euclid(1_000_000_000);
+
+        ((ticks >> 16) as u16, ticks as u16)
+    }
+
+    /// PTP seconds and nanoseconds since the Unix epoch, if the timestamp is after it
+    pub fn to_ptp(&self) -> Option<(u64, u32)> {
+        let nanos = self.value.assume_utc().unix_timestamp_nanos();
+        if nanos < 0 {
+            return None;
+        }
+
+        let secs = u64::try_from(nanos.div_euclid(1_000_000_000)).ok()?;
+        let nanos = nanos.rem_euclid(1_000_000_000) as u32;
+
+        (secs <= PTP_MAX_SECS).then_some((secs, nanos))
+    }
+
     pub fn from_dos_time(value: u32) -> Result<Self> {
         let year = ((value >> 25) & 0x7F) + 1980;
         let month = (value >> 21) & 0x0F;
@@ -126,7 +279,7 @@ impl Timestamp {
     }
 
     pub fn from_epoch_millis(value: i64) -> Result<Self> {
-        if let Ok(dtg) = OffsetDateTime::from_unix_timestamp_nanos(value as i128 / 1000000) {
+        if let Ok(dtg) = OffsetDateTime::from_unix_timestamp_nanos(value as i128 * 1_000_000) {
             Ok(Timestamp::new(PrimitiveDateTime::new(
                 dtg.date(),
                 dtg.time(),
@@ -139,7 +292,7 @@ impl Timestamp {
     }
 
     pub fn from_epoch_micros(value: i64) -> Result<Self> {
-        if let Ok(dtg) = OffsetDateTime::from_unix_timestamp_nanos(value as i128 / 1000) {
+        if let Ok(dtg) = OffsetDateTime::from_unix_timestamp_nanos(value as i128 * 1_000) {
             Ok(Timestamp::new(PrimitiveDateTime::new(
                 dtg.date(),
                 dtg.time(),
@@ -163,105 +316,262 @@ impl Timestamp {
             ))
         }
     }
-}
 
-impl Matches for Timestamp {
-    fn matches(&self, rhs: &Self) -> bool {
-        // If rhs has a tolerance, check that lhs falls wthin it
-        match &rhs.tolerance {
-            Some(tolerance) => {
-                let actual_difference = (self.value - rhs.value).whole_seconds().abs();
-                let max_allowed_difference = tolerance.whole_seconds().abs();
+    pub fn from_filetime(value: i64) -> Result<Self> {
+        let nanos = (value as i128 - SECS_FROM_1601_TO_EPOCH as i128 * 10_000_000) * 100;
 
-                // println!("Actual dif: {}", actual_difference);
-                // println!("Allowed dif: {}", max_allowed_difference);
+        if let Ok(dtg) = OffsetDateTime::from_unix_timestamp_nanos(nanos) {
+            Ok(Timestamp::new(PrimitiveDateTime::new(
+                dtg.date(),
+                dtg.time(),
+            )))
+        } else {
+            Err(anyhow!(
+                "Failed to recreate Needle::Timestamp from FILETIME value"
+            ))
+        }
+    }
 
-                actual_difference <= max_allowed_difference
-            }
-            None => self.value == rhs.value,
+    pub fn from_webkit(value: i64) -> Result<Self> {
+        let nanos = (value as i128 - SECS_FROM_1601_TO_EPOCH as i128 * 1_000_000) * 1000;
+
+        if let Ok(dtg) = OffsetDateTime::from_unix_timestamp_nanos(nanos) {
+            Ok(Timestamp::new(PrimitiveDateTime::new(
+                dtg.date(),
+                dtg.time(),
+            )))
+        } else {
+            Err(anyhow!(
+                "Failed to recreate Needle::Timestamp from WebKit value"
+            ))
         }
     }
+
+    pub fn from_ntp(secs: u32, fraction: u32) -> Result<Self> {
+        // Seconds without the top bit set are from the second era, starting in 2036
+        let era: i128 = if secs & 0x8000_0000 == 0 { 1 << 32 } else { 0 };
+        let secs = era + secs as i128 - SECS_FROM_1900_TO_EPOCH as i128;
+        let nanos = (fraction as i128 * 1_000_000_000 + (1 << 31)) >> 32;
+
+        if let Ok(dtg) = OffsetDateTime::from_unix_timestamp_nanos(secs * 1_000_000_000 + nanos) {
+            Ok(Timestamp::new(PrimitiveDateTime::new(
+                dtg.date(),
+                dtg.time(),
+            )))
+        } else {
+            Err(anyhow!(
+                "Failed to recreate Needle::Timestamp from NTP value"
+            ))
+        }
+    }
+
+    /// The time with these short NTP seconds and fraction that's nearest to `near`, as the short format can't say which 18 hours it's from
+    pub fn from_ntp_short(secs: u16, fraction: u16, near: PrimitiveDateTime) -> Result<Self> {
+        let near = near.assume_utc().unix_timestamp_nanos()
+            + SECS_FROM_1900_TO_EPOCH as i128 * 1_000_000_000;
+        let near = (near << 16).div_euclid(1_000_000_000);
+
+        // Take whichever wrap of the short format puts it within half a wrap of `near`
+        let start = near - (NTP_SHORT_WRAP_SECS as i128) * (1 << 15);
+        let ticks = ((secs as i128) << 16) | fraction as i128;
+        let ticks = start + (ticks - start).rem_euclid(NTP_SHORT_WRAP_SECS as i128 * (1 << 16));
+
+        let nanos = ((ticks * 1_000_000_000 + (1 << 15)) >> 16)
+            - SECS_FROM_1900_TO_EPOCH as i128 * 1_000_000_000;
+
+        if let Ok(dtg) = OffsetDateTime::from_unix_timestamp_nanos(nanos) {
+            Ok(Timestamp::new(PrimitiveDateTime::new(
+                dtg.date(),
+                dtg.time(),
+            )))
+        } else {
+            Err(anyhow!(
+                "Failed to recreate Needle::Timestamp from NTP short value"
+            ))
+        }
+    }
+
+    pub fn from_ptp(secs: u64, nanos: u32) -> Result<Self> {
+        if secs > PTP_MAX_SECS || nanos >= 1_000_000_000 {
+            return Err(anyhow!("Invalid PTP timestamp: {}.{:09}", secs, nanos));
+        }
+
+        if let Ok(dtg) =
+            OffsetDateTime::from_unix_timestamp_nanos(secs as i128 * 1_000_000_000 + nanos as i128)
+        {
+            Ok(Timestamp::new(PrimitiveDateTime::new(
+                dtg.date(),
+                dtg.time(),
+            )))
+        } else {
+            Err(anyhow!(
+                "Failed to recreate Needle::Timestamp from PTP value"
+            ))
+        }
+    }
+}
+
+impl Matches for Timestamp {
+    fn matches(&self, rhs: &Self) -> bool {
+        // Check that lhs falls within rhs's tolerance, to the nanosecond, in any of rhs's offsets
+        rhs.matching_offset(self).is_some()
+    }
 }
 
 impl Discombobulate for Timestamp {
     fn discombobulate(&self) -> Vec<NeedleVariant> {
+        // Local times are discombobulated as though they were UTC, as that's how they're stored
+        if !self.offsets.is_empty() {
+            return self
+                .local_times()
+                .into_iter()
+                .flat_map(|(_, local)| Timestamp::new(local).discombobulate())
+                .collect();
+        }
+
         let mut variants = Vec::<NeedleVariant>::new();
 
-        // Epoch seconds
-        let epoch_secs = self.value.assume_utc().unix_timestamp();
-        if let Ok(integer_needle) = Needle::new_integer(epoch_secs) {
-            let needle_variants = integer_needle.discombobulate();
+        // Epoch seconds, millis, micros and nanos, in whichever of them can hold the timestamp without losing any of it
+        let epoch_nanos = self.value.assume_utc().unix_timestamp_nanos();
+        for (scale, wrap) in [
+            (
+                1_000_000_000,
+                EpochSecs as fn(IntegerVariant) -> TimestampVariant,
+            ),
+            (1_000_000, EpochMillis),
+            (1_000, EpochMicros),
+            (1, EpochNanos),
+        ] {
+            if epoch_nanos % scale != 0 {
+                continue;
+            }
+            let Ok(value) = i64::try_from(epoch_nanos / scale) else {
+                continue;
+            };
 
-            for needle_variant in &needle_variants {
-                if let NeedleVariant::Integer(v) = needle_variant {
-                    variants.push(NeedleVariant::Timestamp(EpochSecs(v.clone())));
+            if let Ok(integer_needle) = Needle::new_integer(value) {
+                let needle_variants = integer_needle.discombobulate();
+
+                for needle_variant in &needle_variants {
+                    if let NeedleVariant::Integer(v) = needle_variant {
+                        variants.push(NeedleVariant::Timestamp(wrap(v.clone())));
+                    }
                 }
             }
         }
 
-        // Epoch millis
-        let epoch_millis = epoch_secs * 1000;
-        if let Ok(integer_needle) = Needle::new_integer(epoch_millis) {
-            let needle_variants = integer_needle.discombobulate();
-
-            for needle_variant in &needle_variants {
-                if let NeedleVariant::Integer(v) = needle_variant {
-                    variants.push(NeedleVariant::Timestamp(EpochMillis(v.clone())));
+        // 18-digit 'Windows NT time format', 'Win32 FILETIME or SYSTEMTIME' or NTFS file time
+        // The timestamp is the number of 100-nanosecond intervals (1 nanosecond = one billionth of a second) since Jan 1, 1601 UTC
+        let filetime = self.to_filetime();
+        let filetime_is_exact = epoch_nanos % 100 == 0;
+        if filetime_is_exact {
+            if let Ok(integer_needle) = Needle::new_integer(filetime) {
+                let needle_variants = integer_needle.discombobulate();
+
+                for needle_variant in &needle_variants {
+                    if let NeedleVariant::Integer(v) = needle_variant {
+                        variants.push(NeedleVariant::Timestamp(FileTime(v.clone())));
+                    }
                 }
             }
         }
 
-        // Epoch micros
-        let epoch_micros = epoch_millis * 1000;
-        if let Ok(integer_needle) = Needle::new_integer(epoch_micros) {
-            let needle_variants = integer_needle.discombobulate();
+        // A FILETIME struct is two u32s, low half first, which only differs from a u64 when they're big endian
+        if filetime_is_exact && filetime >= 0 {
+            let halves = variants::filetime_halves(filetime as u64);
+            variants.push(NeedleVariant::Timestamp(FileTimeHalves((
+                halves.to_vec(),
+                filetime as u64,
+            ))));
+        }
 
-            for needle_variant in &needle_variants {
-                if let NeedleVariant::Integer(v) = needle_variant {
-                    variants.push(NeedleVariant::Timestamp(EpochMicros(v.clone())));
+        // WebKit/Chrome timestamps
+        // A 64-bit value for microseconds since Jan 1, 1601 00:00 UTC. One microsecond is one-millionth of a second
+        let webkit = self.to_webkit();
+        if epoch_nanos % 1000 == 0 {
+            if let Ok(integer_needle) = Needle::new_integer(webkit) {
+                let needle_variants = integer_needle.discombobulate();
+
+                for needle_variant in &needle_variants {
+                    if let NeedleVariant::Integer(v) = needle_variant {
+                        variants.push(NeedleVariant::Timestamp(WebKit(v.clone())));
+                    }
                 }
             }
         }
 
-        // Epoch nanos
-        let epoch_nanos = epoch_micros * 1000;
-        if let Ok(integer_needle) = Needle::new_integer(epoch_nanos) {
-            let needle_variants = integer_needle.discombobulate();
+        // Timestamps counting from other epochs, e.g.
+        //   Apple Cocoa Core Data: seconds (or nanoseconds) since January 1, 2001, often as an f64
+        //   Mac HFS+: seconds since January 1, 1904
+        //   SAS 4GL datetime: seconds since January 1, 1960
+        //   Excel/OLE Automation: days since December 30, 1899 as an f64
+        for epoch in Epoch::ALL {
+            if let Some(value) = epoch.to_integer(self) {
+                for needle_variant in Integer::new(value).discombobulate() {
+                    if let NeedleVariant::Integer(v) = needle_variant {
+                        if v.encoding()
+                            .is_some_and(|encoding| epoch.integer_encodings().contains(&encoding))
+                        {
+                            variants.push(NeedleVariant::Timestamp(Offset(epoch, v)));
+                        }
+                    }
+                }
+            }
 
-            for needle_variant in &needle_variants {
-                if let NeedleVariant::Integer(v) = needle_variant {
-                    variants.push(NeedleVariant::Timestamp(EpochNanos(v.clone())));
+            // Floats only get a timestamp to the nearest microsecond or so
+            let float = epoch.to_float(self);
+            if !epoch.float_encodings().is_empty()
+                && epoch
+                    .from_float(float)
+                    .is_ok_and(|timestamp| timestamp == *self)
+            {
+                for needle_variant in float.discombobulate() {
+                    if let NeedleVariant::Float(v) = needle_variant {
+                        variants.push(NeedleVariant::Timestamp(OffsetFloat(epoch, v)));
+                    }
                 }
             }
         }
 
-        // 18-digit 'Windows NT time format', 'Win32 FILETIME or SYSTEMTIME' or NTFS file time
-        // The timestamp is the number of 100-nanosecond intervals (1 nanosecond = one billionth of a second) since Jan 1, 1601 UTC
-
-        // WebKit/Chrome timestamps
-        // A 64-bit value for microseconds since Jan 1, 1601 00:00 UTC. One microsecond is one-millionth of a second
-
-        // Apple Cocoa Core Data timestamp
-        // The number of seconds (or nanoseconds) since midnight, January 1, 2001
-        // Difference between this and epoch is 978307200 seconds
-
-        // Mac HFS+ timestamp
-        // Seconds since January 1, 1904 (add 2082844800 to epoch)
-
-        // SAS 4GL datetime
-        // Seconds since January 1, 1960 (add 315619200 to epoch)
-
         // DOS/FAT timestamp
+        // Only even seconds from 1980 to 2107 can be stored
         let dos_time = self.to_dos_time();
-        let needle_variants = dos_time.discombobulate();
+        if Timestamp::from_dos_time(dos_time).is_ok_and(|timestamp| timestamp == *self) {
+            let needle_variants = dos_time.discombobulate();
 
-        for needle_variant in &needle_variants {
-            if let NeedleVariant::Integer(v) = needle_variant {
-                variants.push(NeedleVariant::Timestamp(DOSTime(v.clone())));
+            for needle_variant in &needle_variants {
+                if let NeedleVariant::Integer(v) = needle_variant {
+                    variants.push(NeedleVariant::Timestamp(DOSTime(v.clone())));
+                }
             }
         }
 
         // NTP timestamp
+        // Seconds since January 1, 1900 and a 32-bit fraction of a second, in network byte order
+        if let Some((secs, fraction)) = self.to_ntp() {
+            let mut bytes = secs.to_be_bytes().to_vec();
+            bytes.extend(fraction.to_be_bytes());
+            variants.push(NeedleVariant::Timestamp(NTP((bytes, secs, fraction))));
+        }
+
+        // NTP short format
+        // The low 16 bits of the NTP seconds and a 16-bit fraction of a second, in network byte order, which only hold some times exactly
+        let (secs, fraction) = self.to_ntp_short();
+        if Timestamp::from_ntp_short(secs, fraction, self.value)
+            .is_ok_and(|short| short.value == self.value)
+        {
+            let mut bytes = secs.to_be_bytes().to_vec();
+            bytes.extend(fraction.to_be_bytes());
+            variants.push(NeedleVariant::Timestamp(NTPShort((bytes, secs, fraction))));
+        }
+
+        // IEEE 1588 PTP timestamp
+        // 48-bit seconds and 32-bit nanoseconds since the epoch, in network byte order
+        if let Some((secs, nanos)) = self.to_ptp() {
+            let mut bytes = secs.to_be_bytes()[2..].to_vec();
+            bytes.extend(nanos.to_be_bytes());
+            variants.push(NeedleVariant::Timestamp(PTP((bytes, secs, nanos))));
+        }
 
         variants
     }
@@ -272,12 +582,16 @@ mod tests {
     //use integer_encoding::VarInt;
     use time::{format_description, macros::datetime};
 
+    use crate::needle::Recombobulate;
+
     use super::*;
 
     #[test]
     fn dos_time_test() {
-        let format =
-            format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second]").unwrap();
+        let format = format_description::parse_borrowed::<1>(
+            "[year]-[month]-[day] [hour]:[minute]:[second]",
+        )
+        .unwrap();
 
         let dtg = Timestamp::new(datetime!(2023-12-31 23:59:58));
         println!("DTG: {}", dtg.value.format(&format).unwrap());
@@ -294,10 +608,212 @@ mod tests {
         println!("dos2: {}", dos2.value.format(&format).unwrap());
     }
 
+    #[test]
+    fn filetime_test() {
+        // 2023-12-31 23:59:58 as a FILETIME and a WebKit timestamp
+        let dtg = Timestamp::new(datetime!(2023-12-31 23:59:58));
+        assert_eq!(dtg.to_filetime(), 133_485_407_980_000_000);
+        assert_eq!(dtg.to_webkit(), 13_348_540_798_000_000);
+
+        assert_eq!(Timestamp::from_filetime(dtg.to_filetime()).unwrap(), dtg);
+        assert_eq!(Timestamp::from_webkit(dtg.to_webkit()).unwrap(), dtg);
+
+        // Both count from the start of 1601, and keep their sub-second precision
+        let start = Timestamp::new(datetime!(1601-01-01 00:00:00));
+        assert_eq!(start.to_filetime(), 0);
+        assert_eq!(Timestamp::from_filetime(0).unwrap(), start);
+        assert_eq!(
+            Timestamp::from_filetime(1).unwrap().value,
+            datetime!(1601-01-01 00:00:00.0000001)
+        );
+        assert_eq!(
+            Timestamp::from_webkit(1).unwrap().value,
+            datetime!(1601-01-01 00:00:00.000001)
+        );
+
+        assert!(Timestamp::from_filetime(i64::MAX).is_err());
+    }
+
+    #[test]
+    fn epoch_test() {
+        let dtg = Timestamp::new(datetime!(2023-12-31 23:59:58.123456789));
+
+        assert_eq!(
+            Timestamp::from_epoch_millis(1_704_067_198_123)
+                .unwrap()
+                .value,
+            datetime!(2023-12-31 23:59:58.123)
+        );
+        assert_eq!(
+            Timestamp::from_epoch_micros(1_704_067_198_123_456)
+                .unwrap()
+                .value,
+            datetime!(2023-12-31 23:59:58.123456)
+        );
+        assert_eq!(
+            Timestamp::from_epoch_nanos(1_704_067_198_123_456_789).unwrap(),
+            dtg
+        );
+
+        // Only the units that can hold every nanosecond of the timestamp are used
+        let variants = dtg.discombobulate();
+        assert!(variants
+            .iter()
+            .any(|variant| matches!(variant, NeedleVariant::Timestamp(EpochNanos(_)))));
+        assert!(!variants.iter().any(|variant| matches!(
+            variant,
+            NeedleVariant::Timestamp(EpochSecs(_) | EpochMillis(_) | EpochMicros(_) | FileTime(_))
+        )));
+
+        for variant in variants {
+            assert_eq!(
+                variant.recombobulate().unwrap(),
+                Needle::Timestamp(dtg.clone()),
+                "{:02x?}",
+                variant
+            );
+        }
+    }
+
+    #[test]
+    fn ntp_test() {
+        // The Unix epoch is a well known NTP value
+        let unix = Timestamp::new(datetime!(1970-01-01 00:00:00));
+        assert_eq!(unix.to_ntp(), Some((0x83aa_7e80, 0)));
+        assert_eq!(Timestamp::from_ntp(0x83aa_7e80, 0).unwrap(), unix);
+
+        // Fractions of a second survive the trip to NTP and back, down to the nanosecond
+        let dtg = Timestamp::new(datetime!(2023-12-31 23:59:58.123456789));
+        let (secs, fraction) = dtg.to_ntp().unwrap();
+        assert_eq!(secs, 0xe93c_7efe);
+        assert_eq!(Timestamp::from_ntp(secs, fraction).unwrap(), dtg);
+        assert_eq!(
+            Timestamp::from_ntp(secs, 0x8000_0000).unwrap().value,
+            datetime!(2023-12-31 23:59:58.5)
+        );
+
+        // NTP seconds wrap around in 2036, and the timestamps after that are in the next era
+        let wrapped = Timestamp::new(datetime!(2036-02-07 06:28:16));
+        assert_eq!(wrapped.to_ntp(), Some((0, 0)));
+        assert_eq!(Timestamp::from_ntp(0, 0).unwrap(), wrapped);
+
+        let later = Timestamp::new(datetime!(2040-06-01 12:00:00.5));
+        let (secs, fraction) = later.to_ntp().unwrap();
+        assert_eq!(Timestamp::from_ntp(secs, fraction).unwrap(), later);
+
+        // But there's no telling 1900 from 2036
+        assert_eq!(
+            Timestamp::new(datetime!(1900-01-01 00:00:00)).to_ntp(),
+            None
+        );
+    }
+
+    #[test]
+    fn ntp_short_test() {
+        // 23:59:58.5 is 0xe93c7efe.8 in NTP, so 0x7efe.8000 in the short format
+        let dtg = Timestamp::new(datetime!(2023-12-31 23:59:58.5));
+        assert_eq!(dtg.to_ntp_short(), (0x7efe, 0x8000));
+
+        // Which reads back as that time from anywhere within nine hours of it, and the next wrap around after that
+        for near in [
+            datetime!(2023-12-31 23:59:58.5),
+            datetime!(2023-12-31 15:00:00),
+            datetime!(2024-01-01 09:00:00),
+        ] {
+            assert_eq!(
+                Timestamp::from_ntp_short(0x7efe, 0x8000, near).unwrap(),
+                dtg
+            );
+        }
+        assert_eq!(
+            Timestamp::from_ntp_short(0x7efe, 0x8000, datetime!(2024-01-01 10:00:00))
+                .unwrap()
+                .value,
+            datetime!(2024-01-01 18:12:14.5)
+        );
+
+        // Fractions are only kept to a 65536th of a second
+        let dtg = Timestamp::new(datetime!(2023-12-31 23:59:58.123456789));
+        let (secs, fraction) = dtg.to_ntp_short();
+        assert_eq!(fraction, 0x1f9b);
+        let short = Timestamp::from_ntp_short(secs, fraction, dtg.value).unwrap();
+        assert!((short.value - dtg.value).abs() < Duration::microseconds(8));
+
+        // Only times the short format holds exactly are discombobulated into it
+        let variants = dtg.discombobulate();
+        assert!(!variants.iter().any(|variant| matches!(
+            variant,
+            NeedleVariant::Timestamp(TimestampVariant::NTPShort(_))
+        )));
+    }
+
+    #[test]
+    fn ptp_test() {
+        let dtg = Timestamp::new(datetime!(2023-12-31 23:59:58.000000001));
+        assert_eq!(dtg.to_ptp(), Some((1_704_067_198, 1)));
+        assert_eq!(Timestamp::from_ptp(1_704_067_198, 1).unwrap(), dtg);
+
+        assert!(Timestamp::from_ptp(1_704_067_198, 1_000_000_000).is_err());
+        assert!(Timestamp::from_ptp(1 << 48, 0).is_err());
+        assert_eq!(
+            Timestamp::new(datetime!(1969-12-31 23:59:59)).to_ptp(),
+            None
+        );
+
+        // Less than a second before the epoch mustn't round towards it
+        let before = Timestamp::new(datetime!(1969-12-31 23:59:59.5));
+        assert_eq!(before.to_ptp(), None);
+        assert!(!before
+            .discombobulate()
+            .iter()
+            .any(|variant| matches!(variant, NeedleVariant::Timestamp(TimestampVariant::PTP(_)))));
+    }
+
+    #[test]
+    fn offset_test() {
+        let dtg = Timestamp::with_offset(datetime!(2024-01-01 09:59:58 +10));
+        assert_eq!(dtg.value, datetime!(2023-12-31 23:59:58));
+
+        let aest = UtcOffset::from_hms(10, 0, 0).unwrap();
+        assert_eq!(dtg.offsets, vec![UtcOffset::UTC, aest]);
+
+        // Stored as local time, or in UTC
+        let local = Timestamp::new(datetime!(2024-01-01 09:59:58));
+        let utc = Timestamp::new(datetime!(2023-12-31 23:59:58));
+        assert_eq!(dtg.matching_offset(&local), Some(aest));
+        assert_eq!(dtg.matching_offset(&utc), Some(UtcOffset::UTC));
+        assert!(local.matches(&dtg));
+        assert!(!local.matches(&utc));
+
+        // Every local time is discombobulated
+        assert_eq!(
+            dtg.discombobulate().len(),
+            utc.discombobulate().len() + local.discombobulate().len()
+        );
+
+        // The closest offset wins when the tolerance covers several
+        let offsets = Timestamp::whole_hour_offsets();
+        assert_eq!(offsets.len(), 27);
+        let any = Timestamp {
+            tolerance: Some(Duration::hours(1)),
+            ..Timestamp::with_offsets(datetime!(2023-12-31 23:00:00), &offsets)
+        };
+        assert_eq!(
+            any.matching_offset(&Timestamp::new(datetime!(2024-01-01 08:40:00))),
+            UtcOffset::from_hms(10, 0, 0).ok()
+        );
+        assert_eq!(
+            any.matching_offset(&Timestamp::new(datetime!(2024-01-02 23:00:00))),
+            None
+        );
+    }
+
     #[test]
     fn timestamp_test() {
-        let format =
-            format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second]").unwrap();
+        let format = format_description::parse_borrowed::<1>(
+            "[year]-[month]-[day] [hour]:[minute]:[second]",
+        )
+        .unwrap();
 
         let dtg = Timestamp::new(datetime!(2023-12-31 23:59:58));
         println!("DTG: {}", dtg.value.format(&format).unwrap());
@@ -1,9 +1,15 @@
 use anyhow::{anyhow, Result};
+use time::{macros::datetime, PrimitiveDateTime};
 
 use crate::needle::{
-    number::variants::IntegerVariant, timestamp::Timestamp, Interpret, Needle, Recombobulate,
+    number::variants::{FloatVariant, IntegerVariant},
+    timestamp::{epoch::Epoch, Timestamp},
+    Interpret, Needle, Recombobulate,
 };
 
+/// The middle of the first 18 hours the short NTP format can hold, which it's read near when there's nothing better to go on
+const NTP_SHORT_FIRST_WRAP: PrimitiveDateTime = datetime!(1900-01-01 09:06:08);
+
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum TimestampVariant {
     // Epoch seconds
@@ -20,6 +26,115 @@ pub enum TimestampVariant {
 
     // DOS time
     DOSTime(IntegerVariant),
+
+    // Windows FILETIME, in 100ns ticks since 1601
+    FileTime(IntegerVariant),
+
+    // A FILETIME as two big endian u32s, low half first
+    FileTimeHalves((Vec<u8>, u64)),
+
+    // WebKit/Chrome, in microseconds since 1601
+    WebKit(IntegerVariant),
+
+    // A whole number of units since another epoch
+    Offset(Epoch, IntegerVariant),
+
+    // A fractional number of units since another epoch
+    OffsetFloat(Epoch, FloatVariant),
+
+    // NTP, as big endian seconds since 1900 and a 32 bit fraction of a second
+    NTP((Vec<u8>, u32, u32)),
+
+    // NTP short format, as the low 16 bits of the seconds since 1900 and a 16 bit fraction of a second
+    NTPShort((Vec<u8>, u16, u16)),
+
+    // IEEE 1588 PTP, as big endian 48 bit seconds and 32 bit nanoseconds since the epoch
+    PTP((Vec<u8>, u64, u32)),
+}
+
+/// The bytes of a FILETIME struct on a big endian machine, which has the low u32 before the high one
+///
+/// On a little endian machine, as in WIN32_FIND_DATA, they're the same bytes as a u64 LE
+pub fn filetime_halves(value: u64) -> [u8; 8] {
+    let mut bytes = [0u8; 8];
+    bytes[..4].copy_from_slice(&(value as u32).to_be_bytes());
+    bytes[4..].copy_from_slice(&((value >> 32) as u32).to_be_bytes());
+    bytes
+}
+
+/// The FILETIME in the low and high u32 halves at the start of the data, if there's room for both
+pub fn decode_filetime_halves(data: &[u8]) -> Option<u64> {
+    let low = u32::from_be_bytes(data.get(..4)?.try_into().ok()?);
+    let high = u32::from_be_bytes(data.get(4..8)?.try_into().ok()?);
+    Some((high as u64) << 32 | low as u64)
+}
+
+impl TimestampVariant {
+    /// The most bytes a structured timestamp can take up, which is a PTP timestamp
+    pub const MAX_STRUCTURED_LEN: usize = 10;
+
+    pub fn byte_len(&self) -> usize {
+        self.byte_sequence().len()
+    }
+
+    pub fn byte_sequence(&self) -> &[u8] {
+        match self {
+            TimestampVariant::EpochSecs(v)
+            | TimestampVariant::EpochMillis(v)
+            | TimestampVariant::EpochMicros(v)
+            | TimestampVariant::EpochNanos(v)
+            | TimestampVariant::DOSTime(v)
+            | TimestampVariant::FileTime(v)
+            | TimestampVariant::WebKit(v)
+            | TimestampVariant::Offset(_, v) => v.byte_sequence(),
+            TimestampVariant::FileTimeHalves((bytes, _)) => bytes,
+            TimestampVariant::OffsetFloat(_, v) => v.byte_sequence(),
+            TimestampVariant::NTP((bytes, _, _))
+            | TimestampVariant::NTPShort((bytes, _, _))
+            | TimestampVariant::PTP((bytes, _, _)) => bytes,
+        }
+    }
+
+    /// Timestamps that are laid out as more than one value, rather than as a single integer or float
+    pub fn interpret_structured(data: &[u8]) -> Vec<Self> {
+        let mut interpretations = Vec::<Self>::new();
+
+        if let Some(value) = decode_filetime_halves(data) {
+            if i64::try_from(value).is_ok_and(|value| Timestamp::from_filetime(value).is_ok()) {
+                interpretations.push(TimestampVariant::FileTimeHalves((
+                    data[..8].to_vec(),
+                    value,
+                )));
+            }
+        }
+
+        // Every NTP value is a valid timestamp in one era or the other
+        if let Some(bytes) = data.get(..8) {
+            let secs = u32::from_be_bytes(bytes[..4].try_into().unwrap());
+            let fraction = u32::from_be_bytes(bytes[4..].try_into().unwrap());
+            interpretations.push(TimestampVariant::NTP((bytes.to_vec(), secs, fraction)));
+        }
+
+        // As is every short NTP value, once it's known which 18 hours it's from
+        if let Some(bytes) = data.get(..4) {
+            let secs = u16::from_be_bytes(bytes[..2].try_into().unwrap());
+            let fraction = u16::from_be_bytes(bytes[2..].try_into().unwrap());
+            interpretations.push(TimestampVariant::NTPShort((bytes.to_vec(), secs, fraction)));
+        }
+
+        if let Some(bytes) = data.get(..10) {
+            let mut secs = [0u8; 8];
+            secs[2..].copy_from_slice(&bytes[..6]);
+            let secs = u64::from_be_bytes(secs);
+            let nanos = u32::from_be_bytes(bytes[6..].try_into().unwrap());
+
+            if nanos < 1_000_000_000 {
+                interpretations.push(TimestampVariant::PTP((bytes.to_vec(), secs, nanos)));
+            }
+        }
+
+        interpretations
+    }
 }
 
 impl Recombobulate for TimestampVariant {
@@ -86,6 +201,57 @@ impl Recombobulate for TimestampVariant {
                     ))
                 }
             }
+            TimestampVariant::FileTime(v) => {
+                if let Ok(Needle::Integer(integer)) = v.recombobulate() {
+                    Ok(Needle::Timestamp(Timestamp::from_filetime(integer.value)?))
+                } else {
+                    Err(anyhow!(
+                        "Failed to recreate Needle::Timestamp from FILETIME"
+                    ))
+                }
+            }
+            TimestampVariant::FileTimeHalves((_, value)) => Ok(Needle::Timestamp(
+                Timestamp::from_filetime((*value).try_into()?)?,
+            )),
+            TimestampVariant::WebKit(v) => {
+                if let Ok(Needle::Integer(integer)) = v.recombobulate() {
+                    Ok(Needle::Timestamp(Timestamp::from_webkit(integer.value)?))
+                } else {
+                    Err(anyhow!(
+                        "Failed to recreate Needle::Timestamp from WebKit time"
+                    ))
+                }
+            }
+            TimestampVariant::Offset(epoch, v) => {
+                if let Ok(Needle::Integer(integer)) = v.recombobulate() {
+                    Ok(Needle::Timestamp(epoch.from_integer(integer.value)?))
+                } else {
+                    Err(anyhow!(
+                        "Failed to recreate Needle::Timestamp from {:?} time",
+                        epoch
+                    ))
+                }
+            }
+            TimestampVariant::NTP((_, secs, fraction)) => {
+                Ok(Needle::Timestamp(Timestamp::from_ntp(*secs, *fraction)?))
+            }
+            // Without a needle to read it near, a short NTP value is taken to be from the first 18 hours NTP counts
+            TimestampVariant::NTPShort((_, secs, fraction)) => Ok(Needle::Timestamp(
+                Timestamp::from_ntp_short(*secs, *fraction, NTP_SHORT_FIRST_WRAP)?,
+            )),
+            TimestampVariant::PTP((_, secs, nanos)) => {
+                Ok(Needle::Timestamp(Timestamp::from_ptp(*secs, *nanos)?))
+            }
+            TimestampVariant::OffsetFloat(epoch, v) => {
+                if let Ok(Needle::Float(float)) = v.recombobulate() {
+                    Ok(Needle::Timestamp(epoch.from_float(float.value)?))
+                } else {
+                    Err(anyhow!(
+                        "Failed to recreate Needle::Timestamp from {:?} time",
+                        epoch
+                    ))
+                }
+            }
         }
     }
 }
@@ -114,9 +280,34 @@ impl Interpret for TimestampVariant {
                 } {
                     intepretations.push(TimestampVariant::DOSTime(variant.clone()))
                 }
+
+                // Times since 1601 are too big for anything but 64 bit integers
+                if is_64_bit(variant) {
+                    intepretations.push(TimestampVariant::FileTime(variant.clone()));
+                    intepretations.push(TimestampVariant::WebKit(variant.clone()));
+                }
+
+                for epoch in Epoch::ALL {
+                    if variant
+                        .encoding()
+                        .is_some_and(|encoding| epoch.integer_encodings().contains(&encoding))
+                    {
+                        intepretations.push(TimestampVariant::Offset(epoch, variant.clone()));
+                    }
+                }
             }
         }
 
+        for epoch in Epoch::ALL {
+            for encoding in epoch.float_encodings() {
+                if let Ok(variant) = encoding.variant(data) {
+                    intepretations.push(TimestampVariant::OffsetFloat(epoch, variant));
+                }
+            }
+        }
+
+        intepretations.append(&mut TimestampVariant::interpret_structured(data));
+
         if intepretations.is_empty() {
             Err(anyhow!(
                 "Failed to interpret bytes as any valid TimestampVariant!"
@@ -127,10 +318,24 @@ impl Interpret for TimestampVariant {
     }
 }
 
+fn is_64_bit(variant: &IntegerVariant) -> bool {
+    matches!(
+        variant,
+        IntegerVariant::U64LE(_)
+            | IntegerVariant::U64BE(_)
+            | IntegerVariant::U64Varint(_)
+            | IntegerVariant::I64LE(_)
+            | IntegerVariant::I64BE(_)
+            | IntegerVariant::I64Varint(_)
+    )
+}
+
 #[cfg(test)]
 mod tests {
 
-    use crate::needle::Matches;
+    use time::macros::datetime;
+
+    use crate::needle::{variant::NeedleVariant, Discombobulate, Matches};
 
     use super::*;
 
@@ -179,4 +384,164 @@ mod tests {
         // Timestamp(DOSTimeBE([57, 9f, bf, 7d]))
         // DOS: 1470087037
     }
+
+    #[test]
+    fn filetime_variants() {
+        let target = Needle::new_timestamp("2023-12-31 23:59:58").unwrap();
+        let Needle::Timestamp(timestamp) = &target else {
+            unreachable!()
+        };
+
+        // Every FILETIME and WebKit variant should recombobulate to the same timestamp
+        let variants = target
+            .discombobulate()
+            .into_iter()
+            .filter_map(|variant| match variant {
+                NeedleVariant::Timestamp(
+                    v @ (TimestampVariant::FileTime(_)
+                    | TimestampVariant::FileTimeHalves(_)
+                    | TimestampVariant::WebKit(_)),
+                ) => Some(v),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(!variants.is_empty());
+
+        for variant in &variants {
+            assert_eq!(variant.recombobulate().unwrap(), target, "{:02x?}", variant);
+        }
+
+        // A FILETIME in a WIN32_FIND_DATA on a little endian machine is just a u64 LE
+        let filetime = timestamp.to_filetime() as u64;
+        let data = filetime.to_le_bytes();
+        let interps = TimestampVariant::interpret(&data).unwrap();
+        assert!(interps.iter().any(|variant| matches!(
+            variant,
+            TimestampVariant::FileTime(IntegerVariant::U64LE(_))
+        ) && variant.recombobulate().unwrap() == target));
+
+        // But a big endian machine still puts the low half first
+        let data = filetime_halves(filetime);
+        assert_eq!(data[..4], (filetime as u32).to_be_bytes());
+        let interps = TimestampVariant::interpret(&data).unwrap();
+        assert!(interps.contains(&TimestampVariant::FileTimeHalves((data.to_vec(), filetime))));
+        assert!(!interps.iter().any(|variant| matches!(
+            variant,
+            TimestampVariant::FileTime(IntegerVariant::U64BE(_))
+        ) && variant
+            .recombobulate()
+            .is_ok_and(|timestamp| timestamp == target)));
+
+        // Chrome's history stores WebKit timestamps as i64s
+        let data = timestamp.to_webkit().to_le_bytes();
+        let interps = TimestampVariant::interpret(&data).unwrap();
+        assert!(interps.iter().any(|variant| matches!(
+            variant,
+            TimestampVariant::WebKit(IntegerVariant::I64LE(_))
+        ) && variant.recombobulate().unwrap() == target));
+    }
+
+    #[test]
+    fn offset_variants() {
+        let target = Needle::new_timestamp("2023-12-31 23:59:58").unwrap();
+
+        // Every timestamp from another epoch should recombobulate to the same timestamp, whether it's an integer or a float
+        let variants = target
+            .discombobulate()
+            .into_iter()
+            .filter_map(|variant| match variant {
+                NeedleVariant::Timestamp(
+                    v @ (TimestampVariant::Offset(_, _) | TimestampVariant::OffsetFloat(_, _)),
+                ) => Some(v),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        for epoch in Epoch::ALL {
+            assert!(
+                variants.iter().any(|variant| matches!(
+                    variant,
+                    TimestampVariant::Offset(e, _) | TimestampVariant::OffsetFloat(e, _) if *e == epoch
+                )),
+                "{:?}",
+                epoch
+            );
+        }
+
+        for variant in &variants {
+            assert_eq!(variant.recombobulate().unwrap(), target, "{:02x?}", variant);
+        }
+
+        // An HFS+ date is a u32 BE
+        let data = 3_786_911_998u32.to_be_bytes();
+        let interps = TimestampVariant::interpret(&data).unwrap();
+        assert!(interps.iter().any(|variant| matches!(
+            variant,
+            TimestampVariant::Offset(Epoch::HFSPlus, IntegerVariant::U32BE(_))
+        ) && variant.recombobulate().unwrap() == target));
+
+        // And a Core Data date is an f64
+        let data = 725_759_998f64.to_le_bytes();
+        let interps = TimestampVariant::interpret(&data).unwrap();
+        assert!(interps.iter().any(|variant| matches!(
+            variant,
+            TimestampVariant::OffsetFloat(Epoch::Cocoa, FloatVariant::F64LE(_))
+        ) && variant.recombobulate().unwrap() == target));
+    }
+
+    #[test]
+    fn ntp_ptp_variants() {
+        // Half a second past, which NTP and PTP can both hold exactly
+        let target = Needle::Timestamp(Timestamp::new(datetime!(2023-12-31 23:59:58.5)));
+
+        let variants = target
+            .discombobulate()
+            .into_iter()
+            .filter_map(|variant| match variant {
+                NeedleVariant::Timestamp(
+                    v @ (TimestampVariant::NTP(_)
+                    | TimestampVariant::NTPShort(_)
+                    | TimestampVariant::PTP(_)),
+                ) => Some(v),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            variants,
+            vec![
+                TimestampVariant::NTP((
+                    vec![0xe9, 0x3c, 0x7e, 0xfe, 0x80, 0x00, 0x00, 0x00],
+                    0xe93c_7efe,
+                    0x8000_0000
+                )),
+                TimestampVariant::NTPShort((vec![0x7e, 0xfe, 0x80, 0x00], 0x7efe, 0x8000)),
+                TimestampVariant::PTP((
+                    vec![0x00, 0x00, 0x65, 0x92, 0x00, 0x7e, 0x1d, 0xcd, 0x65, 0x00],
+                    1_704_067_198,
+                    500_000_000
+                )),
+            ]
+        );
+
+        for variant in &variants {
+            assert_eq!(
+                NeedleVariant::Timestamp(variant.clone())
+                    .recombobulate_for(&target)
+                    .unwrap(),
+                target,
+                "{:02x?}",
+                variant
+            );
+
+            let interps = TimestampVariant::interpret(variant.byte_sequence()).unwrap();
+            assert!(interps.contains(variant), "{:02x?}", variant);
+        }
+
+        // PTP nanoseconds have to be less than a second
+        let data = [0x00, 0x00, 0x65, 0x92, 0x00, 0x7e, 0x3b, 0x9a, 0xca, 0x00];
+        assert!(!TimestampVariant::interpret_structured(&data)
+            .iter()
+            .any(|variant| matches!(variant, TimestampVariant::PTP(_))));
+    }
 }
@@ -0,0 +1,184 @@
+pub mod text;
+pub mod variant;
+
+use std::cmp::Ordering;
+
+use anyhow::{anyhow, Result};
+use time::{OffsetDateTime, PrimitiveDateTime};
+use uuid::{Uuid, Variant, Version};
+use variant::UUIDVariant;
+
+use super::{
+    macaddr::MACAddr, timestamp::Timestamp, variant::NeedleVariant, Discombobulate, Matches,
+};
+
+/// 100ns ticks between the start of the Gregorian calendar, which v1 and v6 UUIDs count from, and the Unix epoch
+const GREGORIAN_TICKS_TO_UNIX: i128 = 0x01b2_1dd2_1381_4000;
+
+/// Which UUIDs a UUID needle with a tolerance will match
+#[derive(Clone, Debug, PartialEq)]
+pub struct UUIDTolerance {
+    pub version: Option<usize>,
+    pub variant: Option<Variant>,
+}
+
+impl PartialOrd for UUIDTolerance {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        // uuid's Variant isn't ordered, so it's ordered by where its bits put it
+        let rank = |variant: Option<Variant>| {
+            variant.map(|variant| match variant {
+                Variant::NCS => 0,
+                Variant::RFC4122 => 1,
+                Variant::Microsoft => 2,
+                _ => 3,
+            })
+        };
+
+        (self.version, rank(self.variant)).partial_cmp(&(other.version, rank(other.variant)))
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct UUID {
+    pub value: Option<Uuid>,
+    pub tolerance: Option<UUIDTolerance>,
+}
+
+impl UUID {
+    pub fn new(uuid: Uuid) -> Result<Self> {
+        Ok(Self {
+            value: Some(uuid),
+            tolerance: None,
+        })
+    }
+
+    /// Any UUID of the given version and/or variant, e.g. any RFC 4122 v1 UUID
+    pub fn any(version: Option<usize>, variant: Option<Variant>) -> Result<Self> {
+        if version.is_none() && variant.is_none() {
+            return Err(anyhow!(
+                "Any UUID needs a version or variant, as any 16 bytes are a UUID"
+            ));
+        }
+        if version.is_some_and(|version| version > 15) {
+            return Err(anyhow!("UUID versions only go up to 15"));
+        }
+
+        Ok(Self {
+            value: None,
+            tolerance: Some(UUIDTolerance { version, variant }),
+        })
+    }
+
+    /// Whether it only matches a single UUID, so it can be discombobulated
+    pub fn is_exact(&self) -> bool {
+        self.value.is_some() && self.tolerance.is_none()
+    }
+
+    /// When a time-based UUID (v1, v6 or v7) was generated
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        let uuid = self.value?;
+
+        let nanos = match uuid.get_version()? {
+            Version::Mac | Version::SortMac => {
+                // Unix time can't be used for v1 and v6 UUIDs, as they can be from before 1970
+                let (ticks, _) = uuid.get_timestamp()?.to_gregorian();
+                (ticks as i128 - GREGORIAN_TICKS_TO_UNIX) * 100
+            }
+            Version::SortRand => {
+                let (secs, nanos) = uuid.get_timestamp()?.to_unix();
+                secs as i128 * 1_000_000_000 + nanos as i128
+            }
+            _ => return None,
+        };
+
+        let dtg = OffsetDateTime::from_unix_timestamp_nanos(nanos).ok()?;
+        Some(Timestamp::new(PrimitiveDateTime::new(
+            dtg.date(),
+            dtg.time(),
+        )))
+    }
+
+    /// The MAC address of the node that generated a v1 or v6 UUID, which may be random rather than a real one
+    pub fn node(&self) -> Option<MACAddr> {
+        MACAddr::new(self.value?.get_node_id()?.into()).ok()
+    }
+}
+
+impl Matches for UUID {
+    fn matches(&self, rhs: &Self) -> bool {
+        // If rhs has a tolerance, check that lhs falls wthin it
+        match &rhs.tolerance {
+            Some(tolerance) => self.value.is_some_and(|value| {
+                tolerance
+                    .version
+                    .is_none_or(|version| value.get_version_num() == version)
+                    && tolerance
+                        .variant
+                        .is_none_or(|variant| value.get_variant() == variant)
+            }),
+            None => self.value == rhs.value,
+        }
+    }
+}
+
+impl Discombobulate for UUID {
+    fn discombobulate(&self) -> Vec<NeedleVariant> {
+        match self.value {
+            Some(uuid) => UUIDVariant::all(uuid)
+                .into_iter()
+                .map(NeedleVariant::Uuid)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uuid_matches_test() {
+        // The DNS namespace UUID, a v1 UUID from 1998
+        let dns = UUID::new("6ba7b810-9dad-11d1-80b4-00c04fd430c8".parse().unwrap()).unwrap();
+        let random = UUID::new("f47ac10b-58cc-4372-a567-0e02b2c3d479".parse().unwrap()).unwrap();
+
+        assert!(dns.matches(&dns));
+        assert!(!random.matches(&dns));
+
+        let v1 = UUID::any(Some(1), Some(Variant::RFC4122)).unwrap();
+        assert!(dns.matches(&v1));
+        assert!(!random.matches(&v1));
+        assert!(random.matches(&UUID::any(Some(4), None).unwrap()));
+
+        assert!(UUID::any(None, None).is_err());
+        assert!(UUID::any(Some(16), None).is_err());
+    }
+
+    #[test]
+    fn embedded_test() {
+        let dns = UUID::new("6ba7b810-9dad-11d1-80b4-00c04fd430c8".parse().unwrap()).unwrap();
+
+        assert_eq!(
+            dns.timestamp().unwrap().value.to_string(),
+            "1998-02-04 22:13:53.1511824"
+        );
+        assert_eq!(
+            dns.node().unwrap().value,
+            Some("00:C0:4F:D4:30:C8".parse().unwrap())
+        );
+
+        // Random UUIDs have neither
+        let random = UUID::new("f47ac10b-58cc-4372-a567-0e02b2c3d479".parse().unwrap()).unwrap();
+        assert!(random.timestamp().is_none());
+        assert!(random.node().is_none());
+
+        // v1 UUIDs can be from long before the Unix epoch
+        let early = UUID::new("00000000-0000-1000-8000-000000000000".parse().unwrap()).unwrap();
+        assert_eq!(
+            early.timestamp().unwrap().value.to_string(),
+            "1582-10-15 0:00:00.0"
+        );
+    }
+}
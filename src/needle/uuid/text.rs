@@ -0,0 +1,130 @@
+use uuid::Uuid;
+
+/// The ways a UUID can be written out as text
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Hash)]
+pub enum UUIDFormat {
+    /// 6ba7b810-9dad-11d1-80b4-00c04fd430c8
+    Hyphenated,
+    /// {6ba7b810-9dad-11d1-80b4-00c04fd430c8}, as the registry and COM write them
+    Braced,
+    /// 6ba7b8109dad11d180b400c04fd430c8
+    Simple,
+}
+
+/// The most characters any format takes up
+pub const MAX_CHARS: usize = 38;
+
+impl UUIDFormat {
+    pub const ALL: [UUIDFormat; 3] = [
+        UUIDFormat::Hyphenated,
+        UUIDFormat::Braced,
+        UUIDFormat::Simple,
+    ];
+
+    fn len(self) -> usize {
+        match self {
+            UUIDFormat::Hyphenated => 36,
+            UUIDFormat::Braced => 38,
+            UUIDFormat::Simple => 32,
+        }
+    }
+
+    /// Write out a UUID in this format, in both lower and upper case
+    pub fn format(self, uuid: Uuid) -> Vec<String> {
+        let lower = match self {
+            UUIDFormat::Hyphenated => uuid.hyphenated().to_string(),
+            UUIDFormat::Braced => uuid.braced().to_string(),
+            UUIDFormat::Simple => uuid.simple().to_string(),
+        };
+        let upper = lower.to_ascii_uppercase();
+
+        if upper == lower {
+            vec![lower]
+        } else {
+            vec![lower, upper]
+        }
+    }
+
+    /// Read a UUID in this format from the start of some ASCII text, returning it and how many characters it took up
+    ///
+    /// The UUID has to be in a single case, and unless it's braced, be followed by the end of the text or something that isn't a hex digit
+    pub fn parse(self, text: &[u8]) -> Option<(Uuid, usize)> {
+        let len = self.len();
+        let chars = text.get(..len)?;
+
+        let (hex, braces) = match self {
+            UUIDFormat::Braced => (&chars[1..len - 1], Some((chars[0], chars[len - 1]))),
+            _ => (chars, None),
+        };
+        if braces.is_some_and(|braces| braces != (b'{', b'}')) {
+            return None;
+        }
+
+        let hyphenated = self != UUIDFormat::Simple;
+        for (i, c) in hex.iter().enumerate() {
+            let hyphen = hyphenated && [8, 13, 18, 23].contains(&i);
+            if hyphen != (*c == b'-') || (!hyphen && !c.is_ascii_hexdigit()) {
+                return None;
+            }
+        }
+
+        let one_case =
+            !hex.iter().any(u8::is_ascii_uppercase) || !hex.iter().any(u8::is_ascii_lowercase);
+        if !one_case || self.is_continued_by(text.get(len)) {
+            return None;
+        }
+
+        let uuid = Uuid::try_parse_ascii(chars).ok()?;
+        Some((uuid, len))
+    }
+
+    /// Whether a character following a UUID in this format would really be part of it
+    pub fn is_continued_by(self, next: Option<&u8>) -> bool {
+        match self {
+            UUIDFormat::Braced => false,
+            _ => next.is_some_and(u8::is_ascii_hexdigit),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        let uuid: Uuid = "6ba7b810-9dad-11d1-80b4-00c04fd430c8".parse().unwrap();
+
+        for format in UUIDFormat::ALL {
+            for text in format.format(uuid) {
+                assert_eq!(format.parse(text.as_bytes()), Some((uuid, text.len())));
+            }
+        }
+
+        assert_eq!(
+            UUIDFormat::Braced.format(uuid)[1],
+            "{6BA7B810-9DAD-11D1-80B4-00C04FD430C8}".to_string()
+        );
+
+        assert_eq!(
+            UUIDFormat::Hyphenated.parse(b"6ba7b810-9dad-11d1-80b4-00c04fd430C8"),
+            None
+        );
+        assert_eq!(
+            UUIDFormat::Hyphenated.parse(b"6ba7b810-9dad-11d1-80b4-00c04fd430c80"),
+            None
+        );
+        assert_eq!(
+            UUIDFormat::Simple.parse(b"6ba7b810-9dad-11d1-80b4-00c04fd430c8"),
+            None
+        );
+        assert_eq!(
+            UUIDFormat::Braced.parse(b"{6ba7b810-9dad-11d1-80b4-00c04fd430c8)"),
+            None
+        );
+        assert_eq!(
+            UUIDFormat::Braced.parse(b"{6ba7b810-9dad-11d1-80b4-00c04fd430c8}0"),
+            Some((uuid, 38))
+        );
+    }
+}
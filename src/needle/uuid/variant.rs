@@ -0,0 +1,155 @@
+use anyhow::{anyhow, Result};
+use uuid::Uuid;
+
+use crate::needle::{text::TextEncoding, Interpret, Needle, Recombobulate};
+
+use super::{
+    text::{self, UUIDFormat},
+    UUID,
+};
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum UUIDVariant {
+    /// RFC 4122 byte order, i.e. big endian throughout
+    BE([u8; 16]),
+    /// A Microsoft GUID, whose first three fields are little endian
+    Mixed([u8; 16]),
+    /// Written out as text, e.g. "{6ba7b810-9dad-11d1-80b4-00c04fd430c8}" in UTF-16LE
+    Text(UUIDFormat, TextEncoding, Vec<u8>),
+}
+
+impl UUIDVariant {
+    /// The most bytes a textual variant can take up, along with the character after it that shows where it ends
+    pub const MAX_TEXT_LEN: usize = (text::MAX_CHARS + 1) * 2;
+
+    pub fn byte_len(&self) -> usize {
+        self.byte_sequence().len()
+    }
+
+    pub fn byte_sequence(&self) -> &[u8] {
+        match self {
+            UUIDVariant::BE(v) | UUIDVariant::Mixed(v) => v,
+            UUIDVariant::Text(_, _, bytes) => bytes,
+        }
+    }
+
+    /// Every way a UUID can be laid out
+    pub fn all(uuid: Uuid) -> Vec<Self> {
+        let mut variants = vec![
+            UUIDVariant::BE(uuid.into_bytes()),
+            UUIDVariant::Mixed(uuid.to_bytes_le()),
+        ];
+
+        for format in UUIDFormat::ALL {
+            for text in format.format(uuid) {
+                for encoding in TextEncoding::ALL {
+                    variants.push(UUIDVariant::Text(format, encoding, encoding.encode(&text)));
+                }
+            }
+        }
+
+        variants
+    }
+
+    /// Whether the variant really ends where its byte sequence does, rather than carrying on into the bytes that follow
+    pub fn is_complete(&self, following: &[u8]) -> bool {
+        match self {
+            UUIDVariant::Text(format, encoding, _) => {
+                !format.is_continued_by(encoding.decode(following, 1).first())
+            }
+            _ => true,
+        }
+    }
+}
+
+impl Recombobulate for UUIDVariant {
+    fn recombobulate(&self) -> Result<Needle> {
+        match self {
+            UUIDVariant::BE(v) => Ok(Needle::Uuid(UUID::new(Uuid::from_bytes(*v))?)),
+            UUIDVariant::Mixed(v) => Ok(Needle::Uuid(UUID::new(Uuid::from_bytes_le(*v))?)),
+            UUIDVariant::Text(format, encoding, bytes) => {
+                let chars = encoding.decode(bytes, text::MAX_CHARS);
+
+                match format.parse(&chars) {
+                    Some((uuid, len)) if len == chars.len() => Ok(Needle::Uuid(UUID::new(uuid)?)),
+                    _ => Err(anyhow!("Failed to recreate Needle::Uuid from text")),
+                }
+            }
+        }
+    }
+}
+
+impl Interpret for UUIDVariant {
+    fn interpret(data: &[u8]) -> Result<Vec<Self>>
+    where
+        Self: std::marker::Sized,
+    {
+        let mut interpretations = Vec::<Self>::new();
+
+        // Any 16 bytes in either layout
+        if let Some(bytes) = data.get(..16) {
+            let bytes: [u8; 16] = bytes.try_into()?;
+            interpretations.push(UUIDVariant::BE(bytes));
+            interpretations.push(UUIDVariant::Mixed(bytes));
+        }
+
+        // Then as text, which always starts with a hex digit or a brace
+        if data
+            .first()
+            .is_some_and(|c| c.is_ascii_hexdigit() || *c == b'{')
+        {
+            for encoding in TextEncoding::ALL {
+                let chars = encoding.decode(data, text::MAX_CHARS + 1);
+
+                for format in UUIDFormat::ALL {
+                    if let Some((_, len)) = format.parse(&chars) {
+                        let bytes = data[..len * encoding.unit_len()].to_vec();
+                        interpretations.push(UUIDVariant::Text(format, encoding, bytes));
+                    }
+                }
+            }
+        }
+
+        if interpretations.is_empty() {
+            Err(anyhow!(
+                "Failed to interpret bytes as any valid UUIDVariant!"
+            ))
+        } else {
+            Ok(interpretations)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::needle::Discombobulate;
+
+    use super::*;
+
+    #[test]
+    fn recombobulation_test() {
+        let actual_uuid =
+            UUID::new("6ba7b810-9dad-11d1-80b4-00c04fd430c8".parse().unwrap()).unwrap();
+
+        let variants = actual_uuid.discombobulate();
+        assert_eq!(variants.len(), 2 + 3 * 2 * 2);
+
+        for variant in &variants {
+            assert_eq!(
+                variant.recombobulate().unwrap(),
+                Needle::Uuid(actual_uuid.clone()),
+                "{:02x?}",
+                variant
+            );
+        }
+
+        // The first three fields of a GUID are little endian, but the last two aren't
+        assert_eq!(
+            variants[1],
+            crate::needle::variant::NeedleVariant::Uuid(UUIDVariant::Mixed([
+                0x10, 0xb8, 0xa7, 0x6b, 0xad, 0x9d, 0xd1, 0x11, 0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4,
+                0x30, 0xc8
+            ]))
+        );
+    }
+}
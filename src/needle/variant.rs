@@ -1,14 +1,34 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use time::Duration;
 
 use super::{
-    ipaddr::variant::IPv4Variant,
+    custom::{BoxedVariant, CustomNeedle},
+    ipaddr::variant::{IPv4Variant, IPv6Variant},
     location::variant::LocationVariant,
     macaddr::variant::MACAddrVariant,
     number::variants::{FloatVariant, IntegerVariant},
-    timestamp::variants::TimestampVariant,
-    Needle, Recombobulate,
+    sockaddr::variant::SockAddrVariant,
+    string::variant::StringVariant,
+    timestamp::{variants::TimestampVariant, Timestamp, NTP_SHORT_WRAP_SECS},
+    uuid::variant::UUIDVariant,
+    Matches, Needle, Recombobulate,
 };
 
+/// The names of the built-in variant families, which interpreters are registered and enabled under
+pub mod family {
+    pub const INTEGER: &str = "integer";
+    pub const FLOAT: &str = "float";
+    pub const TIMESTAMP: &str = "timestamp";
+    pub const LOCATION: &str = "location";
+    pub const IPADDR: &str = "ipaddr";
+    pub const IPV6ADDR: &str = "ipv6addr";
+    pub const MACADDR: &str = "macaddr";
+    pub const SOCKADDR: &str = "sockaddr";
+    pub const STRING: &str = "string";
+    pub const UUID: &str = "uuid";
+    pub const BYTES: &str = "bytes";
+}
+
 /// Enum to represent all the possible byte sequences for each variant
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum NeedleVariant {
@@ -17,19 +37,132 @@ pub enum NeedleVariant {
     Timestamp(TimestampVariant),
     Location(LocationVariant),
     IpAddr(IPv4Variant),
+    Ipv6Addr(IPv6Variant),
     MacAddr(MACAddrVariant),
+    SockAddr(SockAddrVariant),
+    String(StringVariant),
+    Uuid(UUIDVariant),
+    /// The bytes a Bytes or Pattern needle was found as
+    Bytes(Vec<u8>),
+    Custom(BoxedVariant),
 }
 
 impl NeedleVariant {
-    // pub fn byte_sequence(&self) -> &[u8] {
-    //     match self {
-    //         NeedleVariant::Integer(v) => v.byte_sequence(),
-    //         NeedleVariant::Float(v) => v.byte_sequence(),
-    //         NeedleVariant::Timestamp(_) => todo!(),
-    //         NeedleVariant::Location(_) => todo!(),
-    //         NeedleVariant::IpAddr(_) => todo!(),
-    //     }
-    // }
+    /// Wrap up one of the variants of a custom needle
+    pub fn custom<N: CustomNeedle>(variant: N::Variant) -> Self {
+        NeedleVariant::Custom(BoxedVariant::new::<N>(variant))
+    }
+
+    /// The name of the family of variants this belongs to
+    pub fn family(&self) -> &'static str {
+        match self {
+            NeedleVariant::Integer(_) => family::INTEGER,
+            NeedleVariant::Float(_) => family::FLOAT,
+            NeedleVariant::Timestamp(_) => family::TIMESTAMP,
+            NeedleVariant::Location(_) => family::LOCATION,
+            NeedleVariant::IpAddr(_) => family::IPADDR,
+            NeedleVariant::Ipv6Addr(_) => family::IPV6ADDR,
+            NeedleVariant::MacAddr(_) => family::MACADDR,
+            NeedleVariant::SockAddr(_) => family::SOCKADDR,
+            NeedleVariant::String(_) => family::STRING,
+            NeedleVariant::Uuid(_) => family::UUID,
+            NeedleVariant::Bytes(_) => family::BYTES,
+            NeedleVariant::Custom(v) => v.family(),
+        }
+    }
+
+    /// The number of bytes this variant occupied in the haystack
+    pub fn byte_len(&self) -> usize {
+        match self {
+            NeedleVariant::Integer(v) => v.byte_sequence().len(),
+            NeedleVariant::Float(v) => v.byte_sequence().len(),
+            NeedleVariant::Timestamp(v) => v.byte_len(),
+            NeedleVariant::Location(v) => v.byte_len(),
+            NeedleVariant::IpAddr(v) => v.byte_len(),
+            NeedleVariant::Ipv6Addr(v) => v.byte_len(),
+            NeedleVariant::MacAddr(v) => v.byte_len(),
+            NeedleVariant::SockAddr(v) => v.byte_len(),
+            NeedleVariant::String(v) => v.byte_len(),
+            NeedleVariant::Uuid(v) => v.byte_len(),
+            NeedleVariant::Bytes(v) => v.len(),
+            NeedleVariant::Custom(v) => v.byte_sequence().len(),
+        }
+    }
+
+    /// Whether the variant really starts and ends where its byte sequence does, given the bytes either side of it
+    ///
+    /// Only text can carry on past its byte sequence, e.g. "192.168.0.1" is the start of "192.168.0.123" and the end of "2192.168.0.1", and "10.0.0.1:80" is the start of "10.0.0.1:8080"
+    pub fn is_complete(&self, preceding: &[u8], following: &[u8]) -> bool {
+        match self {
+            NeedleVariant::IpAddr(v) => v.is_complete(preceding, following),
+            NeedleVariant::MacAddr(v) => v.is_complete(preceding, following),
+            NeedleVariant::SockAddr(v) => v.is_complete(preceding, following),
+            NeedleVariant::Uuid(v) => v.is_complete(following),
+            _ => true,
+        }
+    }
+
+    /// The same variant laid out as the bytes actually found, where a case insensitive search may have found different ones
+    ///
+    /// Only strings can be searched for without regard to case, so anything else is unchanged
+    pub fn with_bytes(&self, bytes: &[u8]) -> Self {
+        match self {
+            NeedleVariant::String(v) => NeedleVariant::String(v.with_bytes(bytes)),
+            _ => self.clone(),
+        }
+    }
+
+    /// Recombobulate the variant to be compared with a target, reading anything that only holds part of its value as whichever value nearest the target it could be
+    ///
+    /// The short NTP format wraps around every 18 hours, so it's read as the time nearest the target (in whichever of its offsets matches), unless the target's tolerance is too wide for that to tell one wrap from the next
+    pub fn recombobulate_for(&self, target: &Needle) -> Result<Needle> {
+        match (self, target) {
+            (
+                NeedleVariant::Timestamp(TimestampVariant::NTPShort((_, secs, fraction))),
+                Needle::Timestamp(target),
+            ) => {
+                if target.tolerance.is_some_and(|tolerance| {
+                    tolerance.abs() >= Duration::seconds(NTP_SHORT_WRAP_SECS / 2)
+                }) {
+                    return Err(anyhow!(
+                        "Needle::Timestamp tolerance is too wide to read an NTP short value near it"
+                    ));
+                }
+
+                let candidates = target
+                    .local_times()
+                    .into_iter()
+                    .map(|(_, local)| Timestamp::from_ntp_short(*secs, *fraction, local))
+                    .collect::<Result<Vec<_>>>()?;
+
+                candidates
+                    .iter()
+                    .find(|candidate| candidate.matches(target))
+                    .or(candidates.first())
+                    .map(|candidate| Needle::Timestamp(candidate.clone()))
+                    .ok_or_else(|| anyhow!("Needle::Timestamp has no times to read near"))
+            }
+            _ => self.recombobulate(),
+        }
+    }
+
+    /// The bytes this variant occupies in the haystack
+    pub fn byte_sequence(&self) -> Vec<u8> {
+        match self {
+            NeedleVariant::Integer(v) => v.byte_sequence().to_vec(),
+            NeedleVariant::Float(v) => v.byte_sequence().to_vec(),
+            NeedleVariant::Timestamp(v) => v.byte_sequence().to_vec(),
+            NeedleVariant::Location(v) => v.byte_sequence(),
+            NeedleVariant::IpAddr(v) => v.byte_sequence().to_vec(),
+            NeedleVariant::Ipv6Addr(v) => v.byte_sequence().to_vec(),
+            NeedleVariant::MacAddr(v) => v.byte_sequence().to_vec(),
+            NeedleVariant::SockAddr(v) => v.byte_sequence().to_vec(),
+            NeedleVariant::String(v) => v.byte_sequence().to_vec(),
+            NeedleVariant::Uuid(v) => v.byte_sequence().to_vec(),
+            NeedleVariant::Bytes(v) => v.clone(),
+            NeedleVariant::Custom(v) => v.byte_sequence().to_vec(),
+        }
+    }
 }
 
 impl Recombobulate for NeedleVariant {
@@ -40,7 +173,13 @@ impl Recombobulate for NeedleVariant {
             NeedleVariant::Timestamp(timestamp_variant) => timestamp_variant.recombobulate(),
             NeedleVariant::Location(location_variant) => location_variant.recombobulate(),
             NeedleVariant::IpAddr(ip_variant) => ip_variant.recombobulate(),
+            NeedleVariant::Ipv6Addr(ip_variant) => ip_variant.recombobulate(),
             NeedleVariant::MacAddr(macaddr_variant) => macaddr_variant.recombobulate(),
+            NeedleVariant::SockAddr(sockaddr_variant) => sockaddr_variant.recombobulate(),
+            NeedleVariant::String(string_variant) => string_variant.recombobulate(),
+            NeedleVariant::Uuid(uuid_variant) => uuid_variant.recombobulate(),
+            NeedleVariant::Bytes(bytes) => Ok(Needle::Bytes(bytes.clone())),
+            NeedleVariant::Custom(custom_variant) => custom_variant.recombobulate(),
         }
     }
 }